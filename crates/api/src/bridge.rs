@@ -1,18 +1,27 @@
-use std::path::PathBuf;
-
 use axum::{Json, extract::State};
 use entity_core::error::CoreError;
-use executors::BridgeExecutor;
+use entity_core::model::ConflictPolicy;
+use entity_core::selections::Selections;
+use executors::{BridgeExecutor, DependencyExecutor, WriteOptions};
 use serde::Deserialize;
 use uuid::Uuid;
 
 use crate::state::{AppState, SessionState};
+use crate::workspace::workspace_dir;
 
 #[derive(Deserialize)]
 pub struct BridgeScaffoldReq {
     #[serde(rename = "nodeId")]
     node_id: String,
     workspace: Option<String>,
+    #[serde(default)]
+    selections: Selections,
+    #[serde(rename = "withDeps", default)]
+    with_deps: bool,
+    #[serde(rename = "dryRun", default)]
+    dry_run: bool,
+    #[serde(rename = "conflictPolicy", default)]
+    conflict_policy: Option<ConflictPolicy>,
 }
 
 #[derive(Deserialize)]
@@ -20,6 +29,8 @@ pub struct BridgeStartReq {
     #[serde(rename = "nodeId")]
     node_id: String,
     workspace: Option<String>,
+    #[serde(default)]
+    selections: Selections,
 }
 
 #[derive(Deserialize)]
@@ -66,22 +77,29 @@ pub async fn bridge_scaffold(
         return Json(serde_json::to_value(env).unwrap());
     };
 
-    let exec = BridgeExecutor::new(session.engine.registry());
-    let ws = workspace_or_default(req.workspace);
-    match exec.scaffold(&req.node_id, &ws) {
-        Ok(report) => Json(serde_json::json!({
-            "copied": report
-                .copied
-                .iter()
-                .map(|c| serde_json::json!({
-                    "from": c.from,
-                    "to": c.to,
-                    "count": c.count
-                }))
-                .collect::<Vec<_>>(),
-            "notes": report.notes,
-        })),
-        Err(err) => Json(serde_json::to_value(err.envelope(None)).unwrap()),
+    let options = WriteOptions {
+        dry_run: req.dry_run,
+        conflict_policy: req.conflict_policy,
+    };
+    let exec = BridgeExecutor::new(session.engine.registry()).with_options(options.clone());
+    let ws = match workspace_dir(req.workspace) {
+        Ok(ws) => ws,
+        Err(env) => return env,
+    };
+    if req.with_deps {
+        return Json(
+            match DependencyExecutor::new(session.engine.registry())
+                .with_options(options)
+                .run(&req.node_id, &req.selections, &ws)
+            {
+                Ok(report) => serde_json::to_value(report).unwrap(),
+                Err(err) => serde_json::to_value(err.envelope(err.details())).unwrap(),
+            },
+        );
+    }
+    match exec.scaffold(&req.node_id, &req.selections, &ws) {
+        Ok(report) => Json(serde_json::to_value(report).unwrap()),
+        Err(err) => Json(serde_json::to_value(err.envelope(err.details())).unwrap()),
     }
 }
 
@@ -98,21 +116,27 @@ pub async fn bridge_start(
     };
 
     let exec = BridgeExecutor::new(engine.registry());
-    let ws = workspace_or_default(req.workspace);
-    match exec.spawn_descriptor(&req.node_id) {
+    let ws = match workspace_dir(req.workspace) {
+        Ok(ws) => ws,
+        Err(env) => return env,
+    };
+    match exec.spawn_descriptor(&req.node_id, &req.selections) {
         Ok(info) => {
             let state_id = Uuid::new_v4().to_string();
             match exec.persist_state(&req.node_id, info, &ws, packs_path, &state_id) {
                 Ok(_) => Json(serde_json::json!({"stateId": state_id})),
-                Err(err) => Json(serde_json::to_value(err.envelope(None)).unwrap()),
+                Err(err) => Json(serde_json::to_value(err.envelope(err.details())).unwrap()),
             }
         }
-        Err(err) => Json(serde_json::to_value(err.envelope(None)).unwrap()),
+        Err(err) => Json(serde_json::to_value(err.envelope(err.details())).unwrap()),
     }
 }
 
 pub async fn bridge_status(Json(req): Json<BridgeStatusReq>) -> Json<serde_json::Value> {
-    let ws = workspace_or_default(req.workspace);
+    let ws = match workspace_dir(req.workspace) {
+        Ok(ws) => ws,
+        Err(env) => return env,
+    };
     match BridgeExecutor::read_state(&ws, &req.node_id) {
         Ok(Some(state)) => Json(serde_json::json!({
             "stateId": state.id,
@@ -140,14 +164,17 @@ pub async fn bridge_status(Json(req): Json<BridgeStatusReq>) -> Json<serde_json:
         })),
         Ok(None) => {
             let err = CoreError::TargetNotFound("bridge not started for requested node".into());
-            Json(serde_json::to_value(err.envelope(None)).unwrap())
+            Json(serde_json::to_value(err.envelope(err.details())).unwrap())
         }
-        Err(err) => Json(serde_json::to_value(err.envelope(None)).unwrap()),
+        Err(err) => Json(serde_json::to_value(err.envelope(err.details())).unwrap()),
     }
 }
 
 pub async fn bridge_stop(Json(req): Json<BridgeStopReq>) -> Json<serde_json::Value> {
-    let ws = workspace_or_default(req.workspace);
+    let ws = match workspace_dir(req.workspace) {
+        Ok(ws) => ws,
+        Err(env) => return env,
+    };
     match BridgeExecutor::stop(&ws, &req.node_id) {
         Ok(Some(result)) => Json(serde_json::json!({
             "stopped": true,
@@ -157,14 +184,17 @@ pub async fn bridge_stop(Json(req): Json<BridgeStopReq>) -> Json<serde_json::Val
         })),
         Ok(None) => {
             let err = CoreError::TargetNotFound("no running bridge found for node".into());
-            Json(serde_json::to_value(err.envelope(None)).unwrap())
+            Json(serde_json::to_value(err.envelope(err.details())).unwrap())
         }
-        Err(err) => Json(serde_json::to_value(err.envelope(None)).unwrap()),
+        Err(err) => Json(serde_json::to_value(err.envelope(err.details())).unwrap()),
     }
 }
 
 pub async fn bridge_attach(Json(req): Json<BridgeAttachReq>) -> Json<serde_json::Value> {
-    let ws = workspace_or_default(req.workspace);
+    let ws = match workspace_dir(req.workspace) {
+        Ok(ws) => ws,
+        Err(env) => return env,
+    };
     match BridgeExecutor::attach_pid(
         &ws,
         &req.node_id,
@@ -182,14 +212,17 @@ pub async fn bridge_attach(Json(req): Json<BridgeAttachReq>) -> Json<serde_json:
         })),
         Ok(None) => {
             let err = CoreError::TargetNotFound("bridge state not found".into());
-            Json(serde_json::to_value(err.envelope(None)).unwrap())
+            Json(serde_json::to_value(err.envelope(err.details())).unwrap())
         }
-        Err(err) => Json(serde_json::to_value(err.envelope(None)).unwrap()),
+        Err(err) => Json(serde_json::to_value(err.envelope(err.details())).unwrap()),
     }
 }
 
 pub async fn bridge_heartbeat(Json(req): Json<BridgeHeartbeatReq>) -> Json<serde_json::Value> {
-    let ws = workspace_or_default(req.workspace);
+    let ws = match workspace_dir(req.workspace) {
+        Ok(ws) => ws,
+        Err(env) => return env,
+    };
     match BridgeExecutor::heartbeat(
         &ws,
         &req.node_id,
@@ -205,14 +238,8 @@ pub async fn bridge_heartbeat(Json(req): Json<BridgeHeartbeatReq>) -> Json<serde
         })),
         Ok(None) => {
             let err = CoreError::TargetNotFound("bridge state not found".into());
-            Json(serde_json::to_value(err.envelope(None)).unwrap())
+            Json(serde_json::to_value(err.envelope(err.details())).unwrap())
         }
-        Err(err) => Json(serde_json::to_value(err.envelope(None)).unwrap()),
+        Err(err) => Json(serde_json::to_value(err.envelope(err.details())).unwrap()),
     }
 }
-
-fn workspace_or_default(workspace: Option<String>) -> PathBuf {
-    workspace
-        .map(PathBuf::from)
-        .unwrap_or_else(|| std::env::current_dir().unwrap())
-}
@@ -3,6 +3,7 @@ use std::path::PathBuf;
 use axum::{Json, extract::State};
 use engine::Engine;
 use entity_core::error::CoreError;
+use entity_core::selections::Selections;
 use serde::Deserialize;
 
 use crate::state::{AppState, SessionState};
@@ -16,6 +17,8 @@ pub struct DocsReadReq {
     packs_path: Option<String>,
     #[allow(dead_code)]
     product: Option<String>,
+    #[serde(default)]
+    selections: Selections,
 }
 
 pub async fn docs_read(
@@ -54,16 +57,8 @@ pub async fn docs_read(
     };
 
     let exec = DocsExecutor::new(engine.registry());
-    match exec.read(&req.node_id) {
+    match exec.read(&req.node_id, &req.selections) {
         Ok(content) => Json(serde_json::json!({"content": content})),
-        Err(err) => {
-            let details = match &err {
-                CoreError::MissingSelections(keys) => Some(serde_json::json!({"missing": keys})),
-                CoreError::InvalidSelection(msg) => Some(serde_json::json!({"message": msg})),
-                CoreError::InvalidNames(list) => Some(serde_json::json!({"invalidNames": list})),
-                _ => None,
-            };
-            Json(serde_json::to_value(err.envelope(details)).unwrap())
-        }
+        Err(err) => Json(serde_json::to_value(err.envelope(err.details())).unwrap()),
     }
 }
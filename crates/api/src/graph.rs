@@ -0,0 +1,28 @@
+use std::collections::HashSet;
+
+use axum::{Json, extract::State};
+use entity_core::error::CoreError;
+use entity_core::query::NodeQuery;
+
+use crate::state::AppState;
+
+/// Same filters as `entity-cli graph query`, applied to the session graph.
+pub async fn graph_query(
+    State(state): State<AppState>,
+    Json(query): Json<NodeQuery>,
+) -> Json<serde_json::Value> {
+    let Some(session) = state.session() else {
+        let env = CoreError::InvalidDescriptor("session not initialized".into()).envelope(None);
+        return Json(serde_json::to_value(env).unwrap());
+    };
+
+    let registry = session.engine.registry();
+    let ids: HashSet<&str> = registry
+        .query(&query)
+        .into_iter()
+        .map(|n| n.id.as_str())
+        .collect();
+    let mut graph = session.graph.clone();
+    graph.retain_nodes(&ids);
+    Json(serde_json::to_value(&graph).unwrap())
+}
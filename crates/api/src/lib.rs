@@ -2,10 +2,12 @@ use axum::{Router, routing::post};
 
 mod bridge;
 mod docs;
+mod graph;
 mod session;
 mod setup;
 mod state;
 mod ui;
+mod workspace;
 
 pub use state::AppState;
 
@@ -16,8 +18,13 @@ pub async fn build_router() -> anyhow::Result<Router> {
     let state = AppState::default();
     let router = Router::new()
         .route("/session/init", post(session::session_init))
+        .route("/graph/query", post(graph::graph_query))
         .route("/docs/read", post(docs::docs_read))
         .route("/ui/install", post(ui::ui_install))
+        .route("/ui/status", post(ui::ui_status))
+        .route("/ui/update", post(ui::ui_update))
+        .route("/ui/uninstall", post(ui::ui_uninstall))
+        .route("/ui/diff", post(ui::ui_diff))
         .route("/setup/run", post(setup::setup_run))
         .route("/bridge/scaffold", post(bridge::bridge_scaffold))
         .route("/bridge/start", post(bridge::bridge_start))
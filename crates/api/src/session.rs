@@ -2,6 +2,7 @@ use std::path::PathBuf;
 
 use axum::{Json, extract::State};
 use engine::Engine;
+use engine::compact::compact_graph;
 use entity_core::error::CoreError;
 use serde::Deserialize;
 
@@ -13,6 +14,11 @@ pub struct SessionInitReq {
     packs_path: String,
     #[allow(dead_code)]
     product: Option<String>,
+    /// Respond with the compact graph instead of the full one
+    #[serde(default)]
+    compact: bool,
+    /// Approximate token budget for the compact graph (implies `compact`)
+    budget: Option<usize>,
 }
 
 pub async fn session_init(
@@ -23,6 +29,9 @@ pub async fn session_init(
     match Engine::bootstrap(packs_path.clone(), req.product.as_deref()) {
         Ok((engine, graph)) => {
             state.set_session(SessionState::new(engine.clone(), graph.clone(), packs_path));
+            if req.compact || req.budget.is_some() {
+                return Json(serde_json::to_value(compact_graph(&graph, req.budget)).unwrap());
+            }
             Json(serde_json::to_value(&graph).unwrap())
         }
         Err(err) => {
@@ -1,17 +1,26 @@
-use std::path::PathBuf;
-
 use axum::{Json, extract::State};
 use entity_core::error::CoreError;
-use executors::SetupExecutor;
+use entity_core::model::ConflictPolicy;
+use entity_core::selections::Selections;
+use executors::{DependencyExecutor, SetupExecutor, WriteOptions};
 use serde::Deserialize;
 
 use crate::state::AppState;
+use crate::workspace::workspace_dir;
 
 #[derive(Deserialize)]
 pub struct SetupRunReq {
     #[serde(rename = "nodeId")]
     node_id: String,
     workspace: Option<String>,
+    #[serde(default)]
+    selections: Selections,
+    #[serde(rename = "withDeps", default)]
+    with_deps: bool,
+    #[serde(rename = "dryRun", default)]
+    dry_run: bool,
+    #[serde(rename = "conflictPolicy", default)]
+    conflict_policy: Option<ConflictPolicy>,
 }
 
 pub async fn setup_run(
@@ -23,21 +32,28 @@ pub async fn setup_run(
         return Json(serde_json::to_value(env).unwrap());
     };
 
-    let exec = SetupExecutor::new(session.engine.registry());
-    let ws = req
-        .workspace
-        .map(PathBuf::from)
-        .unwrap_or_else(|| std::env::current_dir().unwrap());
-    match exec.run(&req.node_id, &ws) {
-        Ok(report) => Json(serde_json::json!({
-            "scaffolded": report.scaffolded,
-            "copied": report
-                .copied
-                .iter()
-                .map(|c| serde_json::json!({"from": c.from, "to": c.to, "count": c.count}))
-                .collect::<Vec<_>>(),
-            "notes": report.notes,
-        })),
-        Err(err) => Json(serde_json::to_value(err.envelope(None)).unwrap()),
+    let options = WriteOptions {
+        dry_run: req.dry_run,
+        conflict_policy: req.conflict_policy,
+    };
+    let exec = SetupExecutor::new(session.engine.registry()).with_options(options.clone());
+    let ws = match workspace_dir(req.workspace) {
+        Ok(ws) => ws,
+        Err(env) => return env,
+    };
+    if req.with_deps {
+        return Json(
+            match DependencyExecutor::new(session.engine.registry())
+                .with_options(options)
+                .run(&req.node_id, &req.selections, &ws)
+            {
+                Ok(report) => serde_json::to_value(report).unwrap(),
+                Err(err) => serde_json::to_value(err.envelope(err.details())).unwrap(),
+            },
+        );
+    }
+    match exec.run(&req.node_id, &req.selections, &ws) {
+        Ok(report) => Json(serde_json::to_value(report).unwrap()),
+        Err(err) => Json(serde_json::to_value(err.envelope(err.details())).unwrap()),
     }
 }
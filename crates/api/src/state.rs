@@ -33,7 +33,6 @@ impl AppState {
 #[derive(Clone)]
 pub struct SessionState {
     pub engine: Engine,
-    #[allow(dead_code)]
     pub graph: GraphPackage,
     #[allow(dead_code)]
     pub packs_path: PathBuf,
@@ -169,3 +169,75 @@ async fn bridge_start_then_status_returns_state() {
     let value: Value = serde_json::from_slice(&bytes).unwrap();
     assert_eq!(value["status"], "pending");
 }
+
+#[tokio::test]
+async fn graph_query_returns_matching_subset_of_session_graph() {
+    let packs = TempDir::new().unwrap();
+    let docs_dir = packs.path().join("entity-auth/docs");
+    write_file(&docs_dir.join("content/a.md"), "a");
+    let docs_nodes = json!([
+        {
+            "id": "entityauth:docs:a",
+            "kind": "doc",
+            "title": "A",
+            "meta": { "section": "Setup", "tags": ["intro"], "level": 1 },
+            "payload": { "contentPath": "content/a.md" }
+        },
+        {
+            "id": "entityauth:docs:b",
+            "kind": "doc",
+            "title": "B",
+            "meta": { "section": "Setup", "tags": ["intro"], "level": 2 },
+            "payload": { "contentPath": "content/a.md" }
+        },
+        {
+            "id": "entityauth:docs:c",
+            "kind": "doc",
+            "title": "C",
+            "meta": { "section": "Bridge", "tags": ["intro"] },
+            "payload": { "contentPath": "content/a.md" }
+        }
+    ]);
+    write_file(&docs_dir.join("nodes.json"), &docs_nodes.to_string());
+
+    let app = build_router().await.unwrap();
+    let init_body = json!({"packsPath": packs.path().to_string_lossy()});
+    app.clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/session/init")
+                .header("content-type", "application/json")
+                .body(Body::from(init_body.to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    let query = json!({ "tag": "intro", "section": "Setup", "meta": { "level": 2 } });
+    let res = app
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/graph/query")
+                .header("content-type", "application/json")
+                .body(Body::from(query.to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let mut body = res.into_body().into_data_stream();
+    let mut bytes = Vec::new();
+    while let Some(chunk) = body.next().await {
+        bytes.extend_from_slice(&chunk.unwrap());
+    }
+    let value: Value = serde_json::from_slice(&bytes).unwrap();
+    let ids: Vec<&str> = value["nodes"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|n| n["id"].as_str().unwrap())
+        .collect();
+    assert_eq!(ids, ["entityauth:docs:b"]);
+    assert!(value["command_shapes"].is_object());
+}
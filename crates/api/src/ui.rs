@@ -3,99 +3,269 @@ use std::path::PathBuf;
 use axum::{Json, extract::State};
 use engine::Engine;
 use entity_core::error::CoreError;
-use executors::ComponentsExecutor;
+use entity_core::model::ConflictPolicy;
+use entity_core::selections::Selections;
+use executors::{
+    ComponentsExecutor, DependencyExecutor, FRAMEWORK_KEY, MODE_KEY, NAMES_KEY, WriteOptions,
+};
 use serde::Deserialize;
+use serde_json::Value;
 
 use crate::state::{AppState, SessionState};
+use crate::workspace::workspace_dir;
+
+#[derive(Deserialize)]
+pub struct UiStatusReq {
+    workspace: Option<String>,
+    #[serde(rename = "packsPath")]
+    packs_path: Option<String>,
+    product: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct UiUpdateReq {
+    names: Option<Vec<String>>,
+    workspace: Option<String>,
+    #[serde(rename = "nodeId")]
+    node_id: Option<String>,
+    #[serde(rename = "packsPath")]
+    packs_path: Option<String>,
+    product: Option<String>,
+    #[serde(rename = "dryRun", default)]
+    dry_run: bool,
+}
+
+#[derive(Deserialize)]
+pub struct UiUninstallReq {
+    names: Option<Vec<String>>,
+    workspace: Option<String>,
+    #[serde(rename = "nodeId")]
+    node_id: Option<String>,
+    #[serde(rename = "packsPath")]
+    packs_path: Option<String>,
+    product: Option<String>,
+    #[serde(default)]
+    force: bool,
+    #[serde(rename = "dryRun", default)]
+    dry_run: bool,
+}
+
+#[derive(Deserialize)]
+pub struct UiDiffReq {
+    names: Option<Vec<String>>,
+    framework: Option<String>,
+    workspace: Option<String>,
+    #[serde(rename = "nodeId")]
+    node_id: Option<String>,
+    #[serde(rename = "packsPath")]
+    packs_path: Option<String>,
+    product: Option<String>,
+    /// Answer `{ "patch": "<unified diff>" }` instead of the report
+    #[serde(default)]
+    patch: bool,
+}
 
 #[derive(Deserialize)]
 pub struct UiInstallReq {
     mode: Option<String>,
     names: Option<Vec<String>>,
+    framework: Option<String>,
     workspace: Option<String>,
     #[serde(rename = "nodeId")]
     node_id: Option<String>,
     #[serde(rename = "packsPath")]
     packs_path: Option<String>,
-    #[allow(dead_code)]
     product: Option<String>,
+    #[serde(default)]
+    selections: Selections,
+    #[serde(rename = "withDeps", default)]
+    with_deps: bool,
+    #[serde(rename = "dryRun", default)]
+    dry_run: bool,
+    #[serde(rename = "conflictPolicy", default)]
+    conflict_policy: Option<ConflictPolicy>,
 }
 
 pub async fn ui_install(
     State(state): State<AppState>,
     Json(req): Json<UiInstallReq>,
-) -> Json<serde_json::Value> {
-    if req.mode.is_none() {
-        let err =
-            CoreError::MissingSelections(vec!["selection.mode".into(), "selection.names".into()]);
+) -> Json<Value> {
+    // `mode`/`names`/`framework` are shorthands for selection.mode/names/framework
+    let mut selections = req.selections;
+    if let Some(mode) = req.mode {
+        selections.set(MODE_KEY, mode.into());
+    }
+    if let Some(names) = req.names {
+        selections.set(NAMES_KEY, names.into());
+    }
+    if let Some(framework) = req.framework {
+        selections.set(FRAMEWORK_KEY, framework.into());
+    }
+    if selections.get(MODE_KEY).is_none() {
+        let err = CoreError::MissingSelections(vec![MODE_KEY.into(), NAMES_KEY.into()]);
+        return Json(serde_json::to_value(err.envelope(err.details())).unwrap());
+    }
+
+    let engine = match session_engine(&state, req.packs_path, req.product.as_deref()) {
+        Ok(engine) => engine,
+        Err(env) => return env,
+    };
+
+    let options = WriteOptions {
+        dry_run: req.dry_run,
+        conflict_policy: req.conflict_policy,
+    };
+    let exec = ComponentsExecutor::new(engine.registry()).with_options(options.clone());
+    let ws = match workspace_dir(req.workspace) {
+        Ok(ws) => ws,
+        Err(env) => return env,
+    };
+    let node_id = req
+        .node_id
+        .unwrap_or_else(|| "entityauth:components:install".into());
+    if req.with_deps {
         return Json(
-            serde_json::to_value(err.envelope(Some(serde_json::json!({
-                "missing": ["selection.mode", "selection.names"]
-            }))))
-            .unwrap(),
+            match DependencyExecutor::new(engine.registry())
+                .with_options(options)
+                .run(&node_id, &selections, &ws)
+            {
+                Ok(report) => serde_json::to_value(report).unwrap(),
+                Err(err) => serde_json::to_value(err.envelope(err.details())).unwrap(),
+            },
         );
     }
+    match exec.install(&node_id, &selections, &ws) {
+        Ok(report) => Json(serde_json::to_value(report).unwrap()),
+        Err(err) => Json(serde_json::to_value(err.envelope(err.details())).unwrap()),
+    }
+}
 
-    let maybe_session = state.session();
-    let engine = if let Some(sess) = maybe_session {
-        sess.engine
-    } else if let Some(p) = req.packs_path.clone() {
-        match Engine::bootstrap(PathBuf::from(&p), req.product.as_deref()) {
-            Ok((engine, graph)) => {
-                state.set_session(SessionState::new(engine.clone(), graph, PathBuf::from(&p)));
-                engine
-            }
-            Err(err) => {
-                let env = if let Some(core) = err.downcast_ref::<CoreError>() {
-                    let details = if matches!(core, CoreError::PacksNotFound(_)) {
-                        Some(serde_json::json!({"packsPath": p}))
-                    } else {
-                        None
-                    };
-                    core.envelope(details)
-                } else {
-                    CoreError::InvalidDescriptor(err.to_string()).envelope(None)
-                };
-                return Json(serde_json::to_value(env).unwrap());
-            }
-        }
-    } else {
-        let err = CoreError::PacksNotFound("<unset>".into());
-        let env = err.envelope(Some(
-            serde_json::json!({"packsPath": serde_json::Value::Null}),
-        ));
-        return Json(serde_json::to_value(env).unwrap());
+pub async fn ui_status(State(state): State<AppState>, Json(req): Json<UiStatusReq>) -> Json<Value> {
+    let engine = match session_engine(&state, req.packs_path, req.product.as_deref()) {
+        Ok(engine) => engine,
+        Err(env) => return env,
     };
+    let ws = match workspace_dir(req.workspace) {
+        Ok(ws) => ws,
+        Err(env) => return env,
+    };
+    match ComponentsExecutor::new(engine.registry()).status(&ws) {
+        Ok(report) => Json(serde_json::to_value(report).unwrap()),
+        Err(err) => Json(serde_json::to_value(err.envelope(err.details())).unwrap()),
+    }
+}
+
+pub async fn ui_update(State(state): State<AppState>, Json(req): Json<UiUpdateReq>) -> Json<Value> {
+    let engine = match session_engine(&state, req.packs_path, req.product.as_deref()) {
+        Ok(engine) => engine,
+        Err(env) => return env,
+    };
+    let ws = match workspace_dir(req.workspace) {
+        Ok(ws) => ws,
+        Err(env) => return env,
+    };
+    let node_id = req
+        .node_id
+        .unwrap_or_else(|| "entityauth:components:install".into());
+    let options = WriteOptions {
+        dry_run: req.dry_run,
+        ..Default::default()
+    };
+    match ComponentsExecutor::new(engine.registry())
+        .with_options(options)
+        .update(&node_id, req.names.as_deref(), &ws)
+    {
+        Ok(report) => Json(serde_json::to_value(report).unwrap()),
+        Err(err) => Json(serde_json::to_value(err.envelope(err.details())).unwrap()),
+    }
+}
 
-    let exec = ComponentsExecutor::new(engine.registry());
-    let names_opt = req.names;
-    let ws = req
-        .workspace
-        .map(PathBuf::from)
-        .unwrap_or_else(|| std::env::current_dir().unwrap());
-    match exec.install(
-        &req.node_id
-            .unwrap_or_else(|| "entityauth:components:install".into()),
-        &req.mode.unwrap(),
-        names_opt,
-        &ws,
-    ) {
-        Ok(report) => Json(serde_json::json!({
-            "copied": report
-                .copied
-                .iter()
-                .map(|c| serde_json::json!({"from": c.from, "to": c.to, "count": c.count}))
-                .collect::<Vec<_>>(),
-            "notes": report.notes,
-        })),
+pub async fn ui_uninstall(
+    State(state): State<AppState>,
+    Json(req): Json<UiUninstallReq>,
+) -> Json<Value> {
+    let engine = match session_engine(&state, req.packs_path, req.product.as_deref()) {
+        Ok(engine) => engine,
+        Err(env) => return env,
+    };
+    let ws = match workspace_dir(req.workspace) {
+        Ok(ws) => ws,
+        Err(env) => return env,
+    };
+    let node_id = req
+        .node_id
+        .unwrap_or_else(|| "entityauth:components:install".into());
+    let options = WriteOptions {
+        dry_run: req.dry_run,
+        ..Default::default()
+    };
+    match ComponentsExecutor::new(engine.registry())
+        .with_options(options)
+        .uninstall(&node_id, req.names.as_deref(), &ws, req.force)
+    {
+        Ok(report) => Json(serde_json::to_value(report).unwrap()),
+        Err(err) => Json(serde_json::to_value(err.envelope(err.details())).unwrap()),
+    }
+}
+
+pub async fn ui_diff(State(state): State<AppState>, Json(req): Json<UiDiffReq>) -> Json<Value> {
+    let engine = match session_engine(&state, req.packs_path, req.product.as_deref()) {
+        Ok(engine) => engine,
+        Err(env) => return env,
+    };
+    let mut selections = Selections::new();
+    if let Some(names) = req.names {
+        selections.set(NAMES_KEY, names.into());
+    }
+    if let Some(framework) = req.framework {
+        selections.set(FRAMEWORK_KEY, framework.into());
+    }
+    let ws = match workspace_dir(req.workspace) {
+        Ok(ws) => ws,
+        Err(env) => return env,
+    };
+    let node_id = req
+        .node_id
+        .unwrap_or_else(|| "entityauth:components:install".into());
+    match ComponentsExecutor::new(engine.registry()).diff(&node_id, &selections, &ws) {
+        Ok(report) if req.patch => Json(serde_json::json!({ "patch": report.patch() })),
+        Ok(report) => Json(serde_json::to_value(report).unwrap()),
+        Err(err) => Json(serde_json::to_value(err.envelope(err.details())).unwrap()),
+    }
+}
+
+/// The session engine, or one bootstrapped from `packs_path` (which then becomes
+/// the session); the error envelope otherwise.
+fn session_engine(
+    state: &AppState,
+    packs_path: Option<String>,
+    product: Option<&str>,
+) -> Result<Engine, Json<Value>> {
+    if let Some(sess) = state.session() {
+        return Ok(sess.engine);
+    }
+    let Some(p) = packs_path else {
+        let err = CoreError::PacksNotFound("<unset>".into());
+        let env = err.envelope(Some(serde_json::json!({"packsPath": Value::Null})));
+        return Err(Json(serde_json::to_value(env).unwrap()));
+    };
+    match Engine::bootstrap(PathBuf::from(&p), product) {
+        Ok((engine, graph)) => {
+            state.set_session(SessionState::new(engine.clone(), graph, PathBuf::from(&p)));
+            Ok(engine)
+        }
         Err(err) => {
-            let details = match &err {
-                CoreError::MissingSelections(keys) => Some(serde_json::json!({"missing": keys})),
-                CoreError::InvalidSelection(msg) => Some(serde_json::json!({"message": msg})),
-                CoreError::InvalidNames(list) => Some(serde_json::json!({"invalidNames": list})),
-                _ => None,
+            let env = if let Some(core) = err.downcast_ref::<CoreError>() {
+                let details = if matches!(core, CoreError::PacksNotFound(_)) {
+                    Some(serde_json::json!({"packsPath": p}))
+                } else {
+                    None
+                };
+                core.envelope(details)
+            } else {
+                CoreError::InvalidDescriptor(err.to_string()).envelope(None)
             };
-            Json(serde_json::to_value(err.envelope(details)).unwrap())
+            Err(Json(serde_json::to_value(env).unwrap()))
         }
     }
 }
@@ -0,0 +1,17 @@
+use std::path::PathBuf;
+
+use axum::Json;
+use entity_core::error::CoreError;
+use serde_json::Value;
+
+/// The request's `workspace`, defaulting to the server's current directory; the
+/// error envelope when that can't be read.
+pub(crate) fn workspace_dir(workspace: Option<String>) -> Result<PathBuf, Json<Value>> {
+    match workspace {
+        Some(path) => Ok(PathBuf::from(path)),
+        None => std::env::current_dir().map_err(|err| {
+            let err = CoreError::from(err);
+            Json(serde_json::to_value(err.envelope(err.details())).unwrap())
+        }),
+    }
+}
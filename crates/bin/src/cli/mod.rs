@@ -1,6 +1,7 @@
 use std::path::PathBuf;
 
 use clap::{Args, Parser, Subcommand};
+use entity_core::model::ConflictPolicy;
 
 #[derive(Parser, Debug)]
 #[command(
@@ -15,6 +16,10 @@ pub struct Cli {
     #[arg(long, global = true)]
     pub packs: Option<PathBuf>,
 
+    /// Print error envelopes to stderr; payloads stay on stdout
+    #[arg(long, global = true)]
+    pub errors_to_stderr: bool,
+
     #[command(subcommand)]
     pub command: Commands,
 }
@@ -24,6 +29,9 @@ pub enum Commands {
     /// Emit the graph once as JSON
     Init(InitArgs),
 
+    /// Query a subset of the graph
+    Graph(GraphCmd),
+
     /// Docs related commands
     Docs(DocsCmd),
 
@@ -36,6 +44,12 @@ pub enum Commands {
     /// Bridge commands
     Bridge(BridgeCmd),
 
+    /// Pack authoring commands
+    Pack(PackCmd),
+
+    /// Revert the last operations applied to the workspace
+    Undo(UndoArgs),
+
     /// Serve minimal HTTP API for agents
     Serve(ServeCmd),
 }
@@ -44,6 +58,43 @@ pub enum Commands {
 pub struct InitArgs {
     /// Product/pack name (e.g., entity-auth, microsoft)
     pub product: String,
+    /// Emit a compact graph: no payload internals, nodes grouped by section
+    #[arg(long)]
+    pub compact: bool,
+    /// Approximate token budget for the compact graph (implies --compact)
+    #[arg(long, value_name = "TOKENS")]
+    pub budget: Option<usize>,
+}
+
+#[derive(Args, Debug)]
+pub struct GraphCmd {
+    #[command(subcommand)]
+    pub command: GraphSubcommand,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum GraphSubcommand {
+    /// Emit only the nodes matching every filter, in the same shape as init
+    Query(GraphQueryArgs),
+}
+
+#[derive(Args, Debug)]
+pub struct GraphQueryArgs {
+    /// Product/pack name (e.g., entity-auth)
+    pub product: String,
+    #[arg(long, value_parser = ["doc", "component", "setup", "bridge"])]
+    pub kind: Option<String>,
+    /// Entry of meta.tags
+    #[arg(long)]
+    pub tag: Option<String>,
+    /// Value of meta.section
+    #[arg(long)]
+    pub section: Option<String>,
+    #[arg(long)]
+    pub id_prefix: Option<String>,
+    /// meta equality as key=value; repeatable, value parsed as JSON when possible
+    #[arg(long = "meta", value_name = "KEY=VALUE")]
+    pub meta: Vec<String>,
 }
 
 #[derive(Args, Debug)]
@@ -58,12 +109,24 @@ pub enum DocsSubcommand {
     Read(DocsReadArgs),
 }
 
+#[derive(Args, Debug, Default, Clone)]
+pub struct SelectionArgs {
+    /// Selection as key=value; repeatable, value parsed as JSON when possible
+    #[arg(long = "set", value_name = "KEY=VALUE")]
+    pub set: Vec<String>,
+    /// JSON file containing a selections object
+    #[arg(long = "selections", value_name = "FILE")]
+    pub file: Option<PathBuf>,
+}
+
 #[derive(Args, Debug)]
 pub struct DocsReadArgs {
     /// Product/pack name (e.g., entity-auth)
     pub product: String,
     #[arg(long)]
     pub node: String,
+    #[command(flatten)]
+    pub selections: SelectionArgs,
 }
 
 #[derive(Args, Debug)]
@@ -76,6 +139,14 @@ pub struct UiCmd {
 pub enum UiSubcommand {
     /// Install UI components
     Install(UiInstallArgs),
+    /// Compare installed components with their install record and the pack
+    Status(UiStatusArgs),
+    /// Merge pack changes into installed components, keeping local edits
+    Update(UiUpdateArgs),
+    /// Remove the files installed components wrote
+    Uninstall(UiUninstallArgs),
+    /// Show unified diffs between the pack components and the installed files
+    Diff(UiDiffArgs),
 }
 
 #[derive(Args, Debug)]
@@ -86,8 +157,122 @@ pub struct UiInstallArgs {
     pub mode: Option<String>,
     #[arg(long, num_args = 1..)]
     pub names: Option<Vec<String>>, // allow omission to distinguish for mode=all
+    /// Component variant to install (react, vue, svelte, ...) instead of the
+    /// framework detected from package.json
+    #[arg(long)]
+    pub framework: Option<String>,
+    #[arg(long, default_value = "entityauth:components:install")]
+    pub node: String,
+    /// Workspace directory to install into (defaults to cwd)
+    #[arg(long)]
+    pub workspace: Option<String>,
+    #[command(flatten)]
+    pub selections: SelectionArgs,
+    /// Also apply every node this one requires, dependencies first
+    #[arg(long)]
+    pub with_deps: bool,
+    /// Print the files and commands the run would touch without writing anything
+    #[arg(long)]
+    pub dry_run: bool,
+    /// What to do with existing files that differ from the pack
+    /// (overwrite, skip-existing, backup or fail-on-conflict)
+    #[arg(long, value_name = "POLICY", value_parser = str::parse::<ConflictPolicy>)]
+    pub conflict_policy: Option<ConflictPolicy>,
+}
+
+#[derive(Args, Debug)]
+pub struct UiStatusArgs {
+    /// Product/pack name (e.g., entity-auth)
+    pub product: String,
+    /// Workspace directory to inspect (defaults to cwd)
+    #[arg(long)]
+    pub workspace: Option<String>,
+}
+
+#[derive(Args, Debug)]
+pub struct UiUpdateArgs {
+    /// Product/pack name (e.g., entity-auth)
+    pub product: String,
+    /// Installed components to update (defaults to all)
+    #[arg(long, num_args = 1..)]
+    pub names: Option<Vec<String>>,
+    #[arg(long, default_value = "entityauth:components:install")]
+    pub node: String,
+    /// Workspace directory to update (defaults to cwd)
+    #[arg(long)]
+    pub workspace: Option<String>,
+    /// Report what would be merged without writing anything
+    #[arg(long)]
+    pub dry_run: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct UiUninstallArgs {
+    /// Product/pack name (e.g., entity-auth)
+    pub product: String,
+    /// Installed components to remove (defaults to all)
+    #[arg(long, num_args = 1..)]
+    pub names: Option<Vec<String>>,
+    #[arg(long, default_value = "entityauth:components:install")]
+    pub node: String,
+    /// Workspace directory to clean (defaults to cwd)
+    #[arg(long)]
+    pub workspace: Option<String>,
+    /// Also remove files edited since install
+    #[arg(long)]
+    pub force: bool,
+    /// Report what would be removed without deleting anything
+    #[arg(long)]
+    pub dry_run: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct UiDiffArgs {
+    /// Product/pack name (e.g., entity-auth)
+    pub product: String,
+    /// Components to compare (defaults to all)
+    #[arg(long, num_args = 1..)]
+    pub names: Option<Vec<String>>,
+    /// Component variant to compare against instead of the framework detected
+    /// from package.json
+    #[arg(long)]
+    pub framework: Option<String>,
     #[arg(long, default_value = "entityauth:components:install")]
     pub node: String,
+    /// Workspace directory to compare (defaults to cwd)
+    #[arg(long)]
+    pub workspace: Option<String>,
+    /// Print a plain-text unified patch instead of JSON
+    #[arg(long)]
+    pub patch: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct PackCmd {
+    #[command(subcommand)]
+    pub command: PackSubcommand,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum PackSubcommand {
+    /// Check a pack for every descriptor problem and print a JSON report
+    Lint(PackLintArgs),
+}
+
+#[derive(Args, Debug)]
+pub struct PackLintArgs {
+    /// Product/pack name (e.g., entity-auth)
+    pub product: String,
+}
+
+#[derive(Args, Debug)]
+pub struct UndoArgs {
+    /// Number of operations to revert, newest first
+    #[arg(long, default_value_t = 1)]
+    pub steps: usize,
+    /// Workspace directory to restore (defaults to cwd)
+    #[arg(long)]
+    pub workspace: Option<String>,
 }
 
 #[derive(Args, Debug)]
@@ -119,6 +304,18 @@ pub struct SetupRunArgs {
     /// Workspace directory to operate in (defaults to cwd)
     #[arg(long)]
     pub workspace: Option<String>,
+    #[command(flatten)]
+    pub selections: SelectionArgs,
+    /// Also apply every node this one requires, dependencies first
+    #[arg(long)]
+    pub with_deps: bool,
+    /// Print the files and commands the run would touch without writing anything
+    #[arg(long)]
+    pub dry_run: bool,
+    /// What to do with existing files that differ from the pack
+    /// (overwrite, skip-existing, backup or fail-on-conflict)
+    #[arg(long, value_name = "POLICY", value_parser = str::parse::<ConflictPolicy>)]
+    pub conflict_policy: Option<ConflictPolicy>,
 }
 
 #[derive(Args, Debug)]
@@ -159,12 +356,26 @@ pub struct BridgeArgsBase {
 pub struct BridgeScaffoldArgs {
     #[command(flatten)]
     pub base: BridgeArgsBase,
+    #[command(flatten)]
+    pub selections: SelectionArgs,
+    /// Also apply every node this one requires, dependencies first
+    #[arg(long)]
+    pub with_deps: bool,
+    /// Print the files and commands the run would touch without writing anything
+    #[arg(long)]
+    pub dry_run: bool,
+    /// What to do with existing files that differ from the pack
+    /// (overwrite, skip-existing, backup or fail-on-conflict)
+    #[arg(long, value_name = "POLICY", value_parser = str::parse::<ConflictPolicy>)]
+    pub conflict_policy: Option<ConflictPolicy>,
 }
 
 #[derive(Args, Debug)]
 pub struct BridgeStartArgs {
     #[command(flatten)]
     pub base: BridgeArgsBase,
+    #[command(flatten)]
+    pub selections: SelectionArgs,
 }
 
 #[derive(Args, Debug)]
@@ -1,17 +1,23 @@
-use std::path::PathBuf;
-
 use anyhow::Result;
 use engine::Engine;
-use executors::BridgeExecutor;
+use executors::{BridgeExecutor, DependencyExecutor, WriteOptions};
 use uuid::Uuid;
 
-use crate::cli::{BridgeAttachArgs, BridgeCmd, BridgeHeartbeatArgs, BridgeSubcommand};
-use crate::support::{AppContext, emit_error};
+use crate::cli::{
+    BridgeAttachArgs, BridgeCmd, BridgeHeartbeatArgs, BridgeSubcommand, SelectionArgs,
+};
+use crate::support::{AppContext, collect_selections, workspace_dir};
 
 pub fn run(ctx: &AppContext, BridgeCmd { command }: BridgeCmd) -> Result<()> {
     match command {
-        BridgeSubcommand::Scaffold(args) => scaffold(ctx, args.base),
-        BridgeSubcommand::Start(args) => start(ctx, args.base),
+        BridgeSubcommand::Scaffold(args) => {
+            let options = WriteOptions {
+                dry_run: args.dry_run,
+                conflict_policy: args.conflict_policy,
+            };
+            scaffold(ctx, args.base, args.selections, args.with_deps, options)
+        }
+        BridgeSubcommand::Start(args) => start(ctx, args.base, args.selections),
         BridgeSubcommand::Status(args) => status(ctx, args.base),
         BridgeSubcommand::Stop(args) => stop(ctx, args.base),
         BridgeSubcommand::Attach(args) => attach(ctx, args),
@@ -19,11 +25,25 @@ pub fn run(ctx: &AppContext, BridgeCmd { command }: BridgeCmd) -> Result<()> {
     }
 }
 
-fn scaffold(ctx: &AppContext, base: crate::cli::BridgeArgsBase) -> Result<()> {
+fn scaffold(
+    ctx: &AppContext,
+    base: crate::cli::BridgeArgsBase,
+    selections: SelectionArgs,
+    with_deps: bool,
+    options: WriteOptions,
+) -> Result<()> {
+    let selections = match collect_selections(&selections) {
+        Ok(s) => s,
+        Err(err) => {
+            ctx.emit_error(&err);
+            return Ok(());
+        }
+    };
+
     let packs = match ctx.resolve_packs() {
         Ok(p) => p,
         Err(e) => {
-            emit_error(&entity_core::error::CoreError::InvalidDescriptor(
+            ctx.emit_error(&entity_core::error::CoreError::InvalidDescriptor(
                 e.to_string(),
             ));
             return Ok(());
@@ -32,35 +52,29 @@ fn scaffold(ctx: &AppContext, base: crate::cli::BridgeArgsBase) -> Result<()> {
 
     match Engine::bootstrap(packs, Some(&base.product)) {
         Ok((engine, _graph)) => {
-            let exec = BridgeExecutor::new(engine.registry());
+            let exec = BridgeExecutor::new(engine.registry()).with_options(options.clone());
             let ws = workspace_dir(base.workspace)?;
-
-            match exec.scaffold(base.node.as_str(), &ws) {
-                Ok(report) => {
-                    println!(
-                        "{}",
-                        serde_json::to_string_pretty(&serde_json::json!({
-                            "copied": report
-                                .copied
-                                .iter()
-                                .map(|c| serde_json::json!({
-                                    "from": c.from,
-                                    "to": c.to,
-                                    "count": c.count
-                                }))
-                                .collect::<Vec<_>>(),
-                            "notes": report.notes,
-                        }))?
-                    );
+            if with_deps {
+                match DependencyExecutor::new(engine.registry())
+                    .with_options(options)
+                    .run(base.node.as_str(), &selections, &ws)
+                {
+                    Ok(report) => println!("{}", serde_json::to_string_pretty(&report)?),
+                    Err(err) => ctx.emit_error(&err),
                 }
-                Err(err) => emit_error(&err),
+                return Ok(());
+            }
+
+            match exec.scaffold(base.node.as_str(), &selections, &ws) {
+                Ok(report) => println!("{}", serde_json::to_string_pretty(&report)?),
+                Err(err) => ctx.emit_error(&err),
             }
         }
         Err(err) => {
             if let Some(core) = err.downcast_ref() {
-                emit_error(core);
+                ctx.emit_error(core);
             } else {
-                emit_error(&entity_core::error::CoreError::InvalidDescriptor(
+                ctx.emit_error(&entity_core::error::CoreError::InvalidDescriptor(
                     err.to_string(),
                 ));
             }
@@ -70,11 +84,23 @@ fn scaffold(ctx: &AppContext, base: crate::cli::BridgeArgsBase) -> Result<()> {
     Ok(())
 }
 
-fn start(ctx: &AppContext, base: crate::cli::BridgeArgsBase) -> Result<()> {
+fn start(
+    ctx: &AppContext,
+    base: crate::cli::BridgeArgsBase,
+    selections: SelectionArgs,
+) -> Result<()> {
+    let selections = match collect_selections(&selections) {
+        Ok(s) => s,
+        Err(err) => {
+            ctx.emit_error(&err);
+            return Ok(());
+        }
+    };
+
     let packs = match ctx.resolve_packs() {
         Ok(p) => p,
         Err(e) => {
-            emit_error(&entity_core::error::CoreError::InvalidDescriptor(
+            ctx.emit_error(&entity_core::error::CoreError::InvalidDescriptor(
                 e.to_string(),
             ));
             return Ok(());
@@ -89,7 +115,7 @@ fn start(ctx: &AppContext, base: crate::cli::BridgeArgsBase) -> Result<()> {
             let node_id = base.node.clone();
             let packs_display = packs.display().to_string();
 
-            match exec.spawn_descriptor(base.node.as_str()) {
+            match exec.spawn_descriptor(base.node.as_str(), &selections) {
                 Ok(info) => {
                     let state_id = Uuid::new_v4().to_string();
                     match exec.persist_state(
@@ -125,17 +151,17 @@ fn start(ctx: &AppContext, base: crate::cli::BridgeArgsBase) -> Result<()> {
                             });
                             println!("{}", serde_json::to_string_pretty(&payload)?);
                         }
-                        Err(err) => emit_error(&err),
+                        Err(err) => ctx.emit_error(&err),
                     }
                 }
-                Err(err) => emit_error(&err),
+                Err(err) => ctx.emit_error(&err),
             }
         }
         Err(err) => {
             if let Some(core) = err.downcast_ref() {
-                emit_error(core);
+                ctx.emit_error(core);
             } else {
-                emit_error(&entity_core::error::CoreError::InvalidDescriptor(
+                ctx.emit_error(&entity_core::error::CoreError::InvalidDescriptor(
                     err.to_string(),
                 ));
             }
@@ -145,7 +171,7 @@ fn start(ctx: &AppContext, base: crate::cli::BridgeArgsBase) -> Result<()> {
     Ok(())
 }
 
-fn status(_ctx: &AppContext, base: crate::cli::BridgeArgsBase) -> Result<()> {
+fn status(ctx: &AppContext, base: crate::cli::BridgeArgsBase) -> Result<()> {
     let ws = workspace_dir(base.workspace)?;
 
     match BridgeExecutor::read_state(&ws, base.node.as_str()) {
@@ -179,17 +205,17 @@ fn status(_ctx: &AppContext, base: crate::cli::BridgeArgsBase) -> Result<()> {
             );
         }
         Ok(None) => {
-            emit_error(&entity_core::error::CoreError::TargetNotFound(
+            ctx.emit_error(&entity_core::error::CoreError::TargetNotFound(
                 "bridge not started for requested node".into(),
             ));
         }
-        Err(err) => emit_error(&err),
+        Err(err) => ctx.emit_error(&err),
     }
 
     Ok(())
 }
 
-fn stop(_ctx: &AppContext, base: crate::cli::BridgeArgsBase) -> Result<()> {
+fn stop(ctx: &AppContext, base: crate::cli::BridgeArgsBase) -> Result<()> {
     let ws = workspace_dir(base.workspace)?;
 
     match BridgeExecutor::stop(&ws, base.node.as_str()) {
@@ -205,17 +231,17 @@ fn stop(_ctx: &AppContext, base: crate::cli::BridgeArgsBase) -> Result<()> {
             );
         }
         Ok(None) => {
-            emit_error(&entity_core::error::CoreError::TargetNotFound(
+            ctx.emit_error(&entity_core::error::CoreError::TargetNotFound(
                 "no running bridge found for node".into(),
             ));
         }
-        Err(err) => emit_error(&err),
+        Err(err) => ctx.emit_error(&err),
     }
 
     Ok(())
 }
 
-fn attach(_ctx: &AppContext, args: BridgeAttachArgs) -> Result<()> {
+fn attach(ctx: &AppContext, args: BridgeAttachArgs) -> Result<()> {
     let ws = workspace_dir(args.base.workspace)?;
     match BridgeExecutor::attach_pid(
         &ws,
@@ -238,16 +264,16 @@ fn attach(_ctx: &AppContext, args: BridgeAttachArgs) -> Result<()> {
             );
         }
         Ok(None) => {
-            emit_error(&entity_core::error::CoreError::TargetNotFound(
+            ctx.emit_error(&entity_core::error::CoreError::TargetNotFound(
                 "bridge state not found".into(),
             ));
         }
-        Err(err) => emit_error(&err),
+        Err(err) => ctx.emit_error(&err),
     }
     Ok(())
 }
 
-fn heartbeat(_ctx: &AppContext, args: BridgeHeartbeatArgs) -> Result<()> {
+fn heartbeat(ctx: &AppContext, args: BridgeHeartbeatArgs) -> Result<()> {
     let ws = workspace_dir(args.base.workspace)?;
     match BridgeExecutor::heartbeat(
         &ws,
@@ -268,18 +294,11 @@ fn heartbeat(_ctx: &AppContext, args: BridgeHeartbeatArgs) -> Result<()> {
             );
         }
         Ok(None) => {
-            emit_error(&entity_core::error::CoreError::TargetNotFound(
+            ctx.emit_error(&entity_core::error::CoreError::TargetNotFound(
                 "bridge state not found".into(),
             ));
         }
-        Err(err) => emit_error(&err),
+        Err(err) => ctx.emit_error(&err),
     }
     Ok(())
 }
-
-fn workspace_dir(workspace: Option<String>) -> Result<PathBuf> {
-    match workspace {
-        Some(path) => Ok(PathBuf::from(path)),
-        None => Ok(std::env::current_dir()?),
-    }
-}
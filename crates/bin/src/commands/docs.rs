@@ -2,20 +2,32 @@ use anyhow::Result;
 use engine::Engine;
 use executors::DocsExecutor;
 
-use crate::cli::{DocsCmd, DocsReadArgs, DocsSubcommand};
-use crate::support::{AppContext, emit_error};
+use crate::cli::{DocsCmd, DocsReadArgs, DocsSubcommand, SelectionArgs};
+use crate::support::{AppContext, collect_selections};
 
 pub fn run(ctx: &AppContext, DocsCmd { command }: DocsCmd) -> Result<()> {
     match command {
-        DocsSubcommand::Read(DocsReadArgs { product, node }) => read(ctx, product, node),
+        DocsSubcommand::Read(DocsReadArgs {
+            product,
+            node,
+            selections,
+        }) => read(ctx, product, node, selections),
     }
 }
 
-fn read(ctx: &AppContext, product: String, node: String) -> Result<()> {
+fn read(ctx: &AppContext, product: String, node: String, selections: SelectionArgs) -> Result<()> {
+    let selections = match collect_selections(&selections) {
+        Ok(s) => s,
+        Err(err) => {
+            ctx.emit_error(&err);
+            return Ok(());
+        }
+    };
+
     let packs = match ctx.resolve_packs() {
         Ok(p) => p,
         Err(e) => {
-            emit_error(&entity_core::error::CoreError::InvalidDescriptor(
+            ctx.emit_error(&entity_core::error::CoreError::InvalidDescriptor(
                 e.to_string(),
             ));
             return Ok(());
@@ -25,16 +37,16 @@ fn read(ctx: &AppContext, product: String, node: String) -> Result<()> {
     match Engine::bootstrap(packs, Some(&product)) {
         Ok((engine, _graph)) => {
             let exec = DocsExecutor::new(engine.registry());
-            match exec.read(node.as_str()) {
+            match exec.read(node.as_str(), &selections) {
                 Ok(content) => println!("{}", content),
-                Err(err) => emit_error(&err),
+                Err(err) => ctx.emit_error(&err),
             }
         }
         Err(err) => {
             if let Some(core) = err.downcast_ref() {
-                emit_error(core);
+                ctx.emit_error(core);
             } else {
-                emit_error(&entity_core::error::CoreError::InvalidDescriptor(
+                ctx.emit_error(&entity_core::error::CoreError::InvalidDescriptor(
                     err.to_string(),
                 ));
             }
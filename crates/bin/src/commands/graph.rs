@@ -0,0 +1,87 @@
+use std::collections::HashSet;
+
+use anyhow::Result;
+use engine::Engine;
+use entity_core::error::CoreResult;
+use entity_core::query::NodeQuery;
+use entity_core::selections::Selections;
+use entity_core::workspace::WorkspaceConfig;
+
+use crate::cli::{GraphCmd, GraphQueryArgs, GraphSubcommand};
+use crate::support::{AppContext, workspace_dir};
+
+pub fn run(ctx: &AppContext, GraphCmd { command }: GraphCmd) -> Result<()> {
+    match command {
+        GraphSubcommand::Query(args) => query(ctx, args),
+    }
+}
+
+fn query(ctx: &AppContext, args: GraphQueryArgs) -> Result<()> {
+    let product = args.product.clone();
+    let query = match build_query(args) {
+        Ok(q) => q,
+        Err(err) => {
+            ctx.emit_error(&err);
+            return Ok(());
+        }
+    };
+    let packs = match ctx.resolve_packs() {
+        Ok(p) => p,
+        Err(e) => {
+            ctx.emit_error(&entity_core::error::CoreError::InvalidDescriptor(
+                e.to_string(),
+            ));
+            return Ok(());
+        }
+    };
+
+    match Engine::bootstrap(packs, Some(&product)) {
+        Ok((engine, mut graph)) => {
+            match workspace_dir(None).and_then(|ws| WorkspaceConfig::load(&ws)) {
+                Ok(config) => graph.semantics.apply_workspace_config(&config),
+                Err(err) => {
+                    ctx.emit_error(&err);
+                    return Ok(());
+                }
+            }
+            let ids: HashSet<&str> = engine
+                .registry()
+                .query(&query)
+                .into_iter()
+                .map(|n| n.id.as_str())
+                .collect();
+            graph.retain_nodes(&ids);
+            println!("{}", serde_json::to_string_pretty(&graph)?);
+        }
+        Err(err) => {
+            if let Some(core) = err.downcast_ref() {
+                ctx.emit_error(core);
+            } else {
+                ctx.emit_error(&entity_core::error::CoreError::InvalidDescriptor(
+                    err.to_string(),
+                ));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn build_query(args: GraphQueryArgs) -> CoreResult<NodeQuery> {
+    let mut meta = std::collections::BTreeMap::new();
+    for assignment in &args.meta {
+        let (key, value) = Selections::parse_assignment(assignment)?;
+        meta.insert(key, value);
+    }
+    Ok(NodeQuery {
+        // clap restricts --kind to the lowercase kind names
+        kind: args
+            .kind
+            .map(|k| serde_json::from_value(serde_json::Value::String(k)))
+            .transpose()?,
+        tag: args.tag,
+        section: args.section,
+        id_prefix: args.id_prefix,
+        meta,
+    })
+}
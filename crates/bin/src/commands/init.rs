@@ -1,21 +1,50 @@
 use anyhow::Result;
 use engine::Engine;
+use engine::compact::compact_graph;
+use entity_core::workspace::WorkspaceConfig;
 
 use crate::cli::InitArgs;
-use crate::support::{AppContext, emit_error};
+use crate::support::{AppContext, workspace_dir};
 
-pub fn run(ctx: &AppContext, InitArgs { product }: InitArgs) -> Result<()> {
-    let packs = ctx.resolve_packs()?;
+pub fn run(
+    ctx: &AppContext,
+    InitArgs {
+        product,
+        compact,
+        budget,
+    }: InitArgs,
+) -> Result<()> {
+    let packs = match ctx.resolve_packs() {
+        Ok(p) => p,
+        Err(e) => {
+            ctx.emit_error(&entity_core::error::CoreError::InvalidDescriptor(
+                e.to_string(),
+            ));
+            return Ok(());
+        }
+    };
 
     match Engine::bootstrap(packs, Some(&product)) {
-        Ok((_engine, graph)) => {
-            println!("{}", serde_json::to_string_pretty(&graph)?);
+        Ok((_engine, mut graph)) => {
+            match workspace_dir(None).and_then(|ws| WorkspaceConfig::load(&ws)) {
+                Ok(config) => graph.semantics.apply_workspace_config(&config),
+                Err(err) => {
+                    ctx.emit_error(&err);
+                    return Ok(());
+                }
+            }
+            if compact || budget.is_some() {
+                // Minified: the point is to spend as few tokens as possible
+                println!("{}", serde_json::to_string(&compact_graph(&graph, budget))?);
+            } else {
+                println!("{}", serde_json::to_string_pretty(&graph)?);
+            }
         }
         Err(err) => {
             if let Some(core) = err.downcast_ref() {
-                emit_error(core);
+                ctx.emit_error(core);
             } else {
-                emit_error(&entity_core::error::CoreError::InvalidDescriptor(
+                ctx.emit_error(&entity_core::error::CoreError::InvalidDescriptor(
                     err.to_string(),
                 ));
             }
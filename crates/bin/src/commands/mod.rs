@@ -1,6 +1,9 @@
 pub mod bridge;
 pub mod docs;
+pub mod graph;
 pub mod init;
+pub mod pack;
 pub mod serve;
 pub mod setup;
 pub mod ui;
+pub mod undo;
@@ -0,0 +1,36 @@
+use anyhow::Result;
+use engine::lint::lint_pack;
+use entity_core::error::exit_code_for;
+
+use crate::cli::{PackCmd, PackLintArgs, PackSubcommand};
+use crate::support::AppContext;
+
+pub fn run(ctx: &AppContext, PackCmd { command }: PackCmd) -> Result<()> {
+    match command {
+        PackSubcommand::Lint(PackLintArgs { product }) => lint(ctx, product),
+    }
+}
+
+fn lint(ctx: &AppContext, product: String) -> Result<()> {
+    let packs = match ctx.resolve_packs() {
+        Ok(p) => p,
+        Err(e) => {
+            ctx.emit_error(&entity_core::error::CoreError::InvalidDescriptor(
+                e.to_string(),
+            ));
+            return Ok(());
+        }
+    };
+
+    match lint_pack(&packs, &product) {
+        Ok(report) => {
+            println!("{}", serde_json::to_string_pretty(&report)?);
+            if !report.ok {
+                ctx.fail(exit_code_for("INVALID_DESCRIPTOR"));
+            }
+        }
+        Err(err) => ctx.emit_error(&err),
+    }
+
+    Ok(())
+}
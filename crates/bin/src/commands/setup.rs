@@ -1,11 +1,9 @@
-use std::path::PathBuf;
-
 use anyhow::Result;
 use engine::Engine;
-use executors::SetupExecutor;
+use executors::{DependencyExecutor, SetupExecutor, WriteOptions};
 
 use crate::cli::{SetupCmd, SetupRunArgs, SetupSubcommand};
-use crate::support::{AppContext, emit_error};
+use crate::support::{AppContext, collect_selections, workspace_dir};
 
 pub fn run(ctx: &AppContext, SetupCmd { command }: SetupCmd) -> Result<()> {
     match command {
@@ -18,12 +16,24 @@ fn run_setup(ctx: &AppContext, args: SetupRunArgs) -> Result<()> {
         product,
         node,
         workspace,
+        selections,
+        with_deps,
+        dry_run,
+        conflict_policy,
     } = args;
 
+    let selections = match collect_selections(&selections) {
+        Ok(s) => s,
+        Err(err) => {
+            ctx.emit_error(&err);
+            return Ok(());
+        }
+    };
+
     let packs = match ctx.resolve_packs() {
         Ok(p) => p,
         Err(e) => {
-            emit_error(&entity_core::error::CoreError::InvalidDescriptor(
+            ctx.emit_error(&entity_core::error::CoreError::InvalidDescriptor(
                 e.to_string(),
             ));
             return Ok(());
@@ -32,38 +42,39 @@ fn run_setup(ctx: &AppContext, args: SetupRunArgs) -> Result<()> {
 
     match Engine::bootstrap(packs, Some(&product)) {
         Ok((engine, _graph)) => {
-            let exec = SetupExecutor::new(engine.registry());
-            let ws = workspace
-                .map(PathBuf::from)
-                .unwrap_or_else(|| std::env::current_dir().unwrap());
-
-            match exec.run(node.as_str(), &ws) {
-                Ok(report) => {
-                    println!(
-                        "{}",
-                        serde_json::to_string_pretty(&serde_json::json!({
-                            "scaffolded": report.scaffolded,
-                            "copied": report
-                                .copied
-                                .iter()
-                                .map(|c| serde_json::json!({
-                                    "from": c.from,
-                                    "to": c.to,
-                                    "count": c.count
-                                }))
-                                .collect::<Vec<_>>(),
-                            "notes": report.notes,
-                        }))?
-                    );
+            let options = WriteOptions {
+                dry_run,
+                conflict_policy,
+            };
+            let exec = SetupExecutor::new(engine.registry()).with_options(options.clone());
+            let ws = match workspace_dir(workspace) {
+                Ok(ws) => ws,
+                Err(err) => {
+                    ctx.emit_error(&err);
+                    return Ok(());
+                }
+            };
+            if with_deps {
+                match DependencyExecutor::new(engine.registry())
+                    .with_options(options)
+                    .run(node.as_str(), &selections, &ws)
+                {
+                    Ok(report) => println!("{}", serde_json::to_string_pretty(&report)?),
+                    Err(err) => ctx.emit_error(&err),
                 }
-                Err(err) => emit_error(&err),
+                return Ok(());
+            }
+
+            match exec.run(node.as_str(), &selections, &ws) {
+                Ok(report) => println!("{}", serde_json::to_string_pretty(&report)?),
+                Err(err) => ctx.emit_error(&err),
             }
         }
         Err(err) => {
             if let Some(core) = err.downcast_ref() {
-                emit_error(core);
+                ctx.emit_error(core);
             } else {
-                emit_error(&entity_core::error::CoreError::InvalidDescriptor(
+                ctx.emit_error(&entity_core::error::CoreError::InvalidDescriptor(
                     err.to_string(),
                 ));
             }
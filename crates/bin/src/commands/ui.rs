@@ -1,13 +1,23 @@
 use anyhow::Result;
 use engine::Engine;
-use executors::ComponentsExecutor;
+use entity_core::error::exit_code_for;
+use entity_core::selections::Selections;
+use executors::{
+    ComponentsExecutor, DependencyExecutor, FRAMEWORK_KEY, MODE_KEY, NAMES_KEY, WriteOptions,
+};
 
-use crate::cli::{UiCmd, UiInstallArgs, UiSubcommand};
-use crate::support::{AppContext, emit_error};
+use crate::cli::{
+    UiCmd, UiDiffArgs, UiInstallArgs, UiStatusArgs, UiSubcommand, UiUninstallArgs, UiUpdateArgs,
+};
+use crate::support::{AppContext, collect_selections, workspace_dir};
 
 pub fn run(ctx: &AppContext, UiCmd { command }: UiCmd) -> Result<()> {
     match command {
         UiSubcommand::Install(args) => install(ctx, args),
+        UiSubcommand::Status(args) => status(ctx, args),
+        UiSubcommand::Update(args) => update(ctx, args),
+        UiSubcommand::Uninstall(args) => uninstall(ctx, args),
+        UiSubcommand::Diff(args) => diff(ctx, args),
     }
 }
 
@@ -16,63 +26,221 @@ fn install(ctx: &AppContext, args: UiInstallArgs) -> Result<()> {
         product,
         mode,
         names,
+        framework,
         node,
+        workspace,
+        selections,
+        with_deps,
+        dry_run,
+        conflict_policy,
     } = args;
 
-    if mode.is_none() {
-        emit_error(&entity_core::error::CoreError::MissingSelections(vec![
-            "selection.mode".into(),
-            "selection.names".into(),
+    // --mode/--names/--framework are shorthands for selection.mode/names/framework
+    let mut selections = match collect_selections(&selections) {
+        Ok(s) => s,
+        Err(err) => {
+            ctx.emit_error(&err);
+            return Ok(());
+        }
+    };
+    if let Some(mode) = mode {
+        selections.set(MODE_KEY, mode.into());
+    }
+    if let Some(names) = names {
+        selections.set(NAMES_KEY, names.into());
+    }
+    if let Some(framework) = framework {
+        selections.set(FRAMEWORK_KEY, framework.into());
+    }
+
+    if selections.get(MODE_KEY).is_none() {
+        ctx.emit_error(&entity_core::error::CoreError::MissingSelections(vec![
+            MODE_KEY.into(),
+            NAMES_KEY.into(),
         ]));
         return Ok(());
     }
 
-    let packs = match ctx.resolve_packs() {
-        Ok(p) => p,
-        Err(e) => {
-            emit_error(&entity_core::error::CoreError::InvalidDescriptor(
-                e.to_string(),
-            ));
+    let Some(engine) = bootstrap(ctx, &product) else {
+        return Ok(());
+    };
+    let options = WriteOptions {
+        dry_run,
+        conflict_policy,
+    };
+    let exec = ComponentsExecutor::new(engine.registry()).with_options(options.clone());
+    let ws = match workspace_dir(workspace) {
+        Ok(ws) => ws,
+        Err(err) => {
+            ctx.emit_error(&err);
+            return Ok(());
+        }
+    };
+    if with_deps {
+        match DependencyExecutor::new(engine.registry())
+            .with_options(options)
+            .run(node.as_str(), &selections, &ws)
+        {
+            Ok(report) => println!("{}", serde_json::to_string_pretty(&report)?),
+            Err(err) => ctx.emit_error(&err),
+        }
+        return Ok(());
+    }
+
+    match exec.install(node.as_str(), &selections, &ws) {
+        Ok(report) => println!("{}", serde_json::to_string_pretty(&report)?),
+        Err(err) => ctx.emit_error(&err),
+    }
+    Ok(())
+}
+
+fn status(ctx: &AppContext, UiStatusArgs { product, workspace }: UiStatusArgs) -> Result<()> {
+    let Some(engine) = bootstrap(ctx, &product) else {
+        return Ok(());
+    };
+    let ws = match workspace_dir(workspace) {
+        Ok(ws) => ws,
+        Err(err) => {
+            ctx.emit_error(&err);
             return Ok(());
         }
     };
+    match ComponentsExecutor::new(engine.registry()).status(&ws) {
+        Ok(report) => println!("{}", serde_json::to_string_pretty(&report)?),
+        Err(err) => ctx.emit_error(&err),
+    }
+    Ok(())
+}
 
-    match Engine::bootstrap(packs, Some(&product)) {
-        Ok((engine, _graph)) => {
-            let exec = ComponentsExecutor::new(engine.registry());
-            let cwd = std::env::current_dir()?;
+fn update(ctx: &AppContext, args: UiUpdateArgs) -> Result<()> {
+    let UiUpdateArgs {
+        product,
+        names,
+        node,
+        workspace,
+        dry_run,
+    } = args;
 
-            match exec.install(node.as_str(), &mode.unwrap(), names, &cwd) {
-                Ok(report) => {
-                    println!(
-                        "{}",
-                        serde_json::to_string_pretty(&serde_json::json!({
-                            "copied": report
-                                .copied
-                                .iter()
-                                .map(|c| serde_json::json!({
-                                    "from": c.from,
-                                    "to": c.to,
-                                    "count": c.count
-                                }))
-                                .collect::<Vec<_>>(),
-                            "notes": report.notes,
-                        }))?
-                    );
-                }
-                Err(err) => emit_error(&err),
+    let Some(engine) = bootstrap(ctx, &product) else {
+        return Ok(());
+    };
+    let ws = match workspace_dir(workspace) {
+        Ok(ws) => ws,
+        Err(err) => {
+            ctx.emit_error(&err);
+            return Ok(());
+        }
+    };
+    let options = WriteOptions {
+        dry_run,
+        ..Default::default()
+    };
+    match ComponentsExecutor::new(engine.registry())
+        .with_options(options)
+        .update(&node, names.as_deref(), &ws)
+    {
+        Ok(report) => {
+            println!("{}", serde_json::to_string_pretty(&report)?);
+            if !report.conflicts.is_empty() {
+                ctx.fail(exit_code_for("WRITE_CONFLICT"));
             }
         }
+        Err(err) => ctx.emit_error(&err),
+    }
+    Ok(())
+}
+
+fn uninstall(ctx: &AppContext, args: UiUninstallArgs) -> Result<()> {
+    let UiUninstallArgs {
+        product,
+        names,
+        node,
+        workspace,
+        force,
+        dry_run,
+    } = args;
+
+    let Some(engine) = bootstrap(ctx, &product) else {
+        return Ok(());
+    };
+    let ws = match workspace_dir(workspace) {
+        Ok(ws) => ws,
+        Err(err) => {
+            ctx.emit_error(&err);
+            return Ok(());
+        }
+    };
+    let options = WriteOptions {
+        dry_run,
+        ..Default::default()
+    };
+    match ComponentsExecutor::new(engine.registry())
+        .with_options(options)
+        .uninstall(&node, names.as_deref(), &ws, force)
+    {
+        Ok(report) => println!("{}", serde_json::to_string_pretty(&report)?),
+        Err(err) => ctx.emit_error(&err),
+    }
+    Ok(())
+}
+
+fn diff(ctx: &AppContext, args: UiDiffArgs) -> Result<()> {
+    let UiDiffArgs {
+        product,
+        names,
+        framework,
+        node,
+        workspace,
+        patch,
+    } = args;
+
+    let mut selections = Selections::new();
+    if let Some(names) = names {
+        selections.set(NAMES_KEY, names.into());
+    }
+    if let Some(framework) = framework {
+        selections.set(FRAMEWORK_KEY, framework.into());
+    }
+    let Some(engine) = bootstrap(ctx, &product) else {
+        return Ok(());
+    };
+    let ws = match workspace_dir(workspace) {
+        Ok(ws) => ws,
+        Err(err) => {
+            ctx.emit_error(&err);
+            return Ok(());
+        }
+    };
+    match ComponentsExecutor::new(engine.registry()).diff(&node, &selections, &ws) {
+        Ok(report) if patch => print!("{}", report.patch()),
+        Ok(report) => println!("{}", serde_json::to_string_pretty(&report)?),
+        Err(err) => ctx.emit_error(&err),
+    }
+    Ok(())
+}
+
+/// Engine for `product`, or `None` once the failure has been reported.
+fn bootstrap(ctx: &AppContext, product: &str) -> Option<Engine> {
+    let packs = match ctx.resolve_packs() {
+        Ok(p) => p,
+        Err(e) => {
+            ctx.emit_error(&entity_core::error::CoreError::InvalidDescriptor(
+                e.to_string(),
+            ));
+            return None;
+        }
+    };
+    match Engine::bootstrap(packs, Some(product)) {
+        Ok((engine, _graph)) => Some(engine),
         Err(err) => {
             if let Some(core) = err.downcast_ref() {
-                emit_error(core);
+                ctx.emit_error(core);
             } else {
-                emit_error(&entity_core::error::CoreError::InvalidDescriptor(
+                ctx.emit_error(&entity_core::error::CoreError::InvalidDescriptor(
                     err.to_string(),
                 ));
             }
+            None
         }
     }
-
-    Ok(())
 }
@@ -0,0 +1,19 @@
+use anyhow::Result;
+
+use crate::cli::UndoArgs;
+use crate::support::{AppContext, workspace_dir};
+
+pub fn run(ctx: &AppContext, UndoArgs { steps, workspace }: UndoArgs) -> Result<()> {
+    let ws = match workspace_dir(workspace) {
+        Ok(ws) => ws,
+        Err(err) => {
+            ctx.emit_error(&err);
+            return Ok(());
+        }
+    };
+    match executors::undo(&ws, steps) {
+        Ok(report) => println!("{}", serde_json::to_string_pretty(&report)?),
+        Err(err) => ctx.emit_error(&err),
+    }
+    Ok(())
+}
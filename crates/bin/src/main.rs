@@ -7,7 +7,7 @@ mod commands;
 mod support;
 
 use cli::{Cli, Commands};
-use commands::{bridge, docs, init, serve, setup, ui};
+use commands::{bridge, docs, graph, init, pack, serve, setup, ui, undo};
 use support::AppContext;
 
 fn main() -> Result<()> {
@@ -16,17 +16,25 @@ fn main() -> Result<()> {
         .init();
 
     let cli = Cli::parse();
-    let ctx = AppContext::new(cli.packs.clone());
+    let ctx = AppContext::new(cli.packs.clone()).with_errors_to_stderr(cli.errors_to_stderr);
 
     match cli.command {
         Commands::Init(args) => init::run(&ctx, args)?,
+        Commands::Graph(cmd) => graph::run(&ctx, cmd)?,
         Commands::Docs(cmd) => docs::run(&ctx, cmd)?,
         Commands::Ui(cmd) => ui::run(&ctx, cmd)?,
         Commands::Setup(cmd) => setup::run(&ctx, cmd)?,
         Commands::Bridge(cmd) => bridge::run(&ctx, cmd)?,
+        Commands::Pack(cmd) => pack::run(&ctx, cmd)?,
+        Commands::Undo(args) => undo::run(&ctx, args)?,
         Commands::Serve(args) => serve::run(args)?,
     }
 
+    // Envelopes were already printed; the exit code tells scripts what failed
+    if ctx.exit_code() != 0 {
+        std::process::exit(ctx.exit_code());
+    }
+
     Ok(())
 }
 
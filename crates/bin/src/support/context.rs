@@ -1,17 +1,31 @@
+use std::cell::Cell;
 use std::path::PathBuf;
 
 use anyhow::Result;
+use entity_core::error::CoreError;
 
 use super::resolve_packs;
 
 #[derive(Clone, Debug)]
 pub struct AppContext {
     packs_flag: Option<PathBuf>,
+    errors_to_stderr: bool,
+    exit_code: Cell<i32>,
 }
 
 impl AppContext {
     pub fn new(packs_flag: Option<PathBuf>) -> Self {
-        Self { packs_flag }
+        Self {
+            packs_flag,
+            errors_to_stderr: false,
+            exit_code: Cell::new(0),
+        }
+    }
+
+    /// Print error envelopes to stderr instead of stdout.
+    pub fn with_errors_to_stderr(mut self, errors_to_stderr: bool) -> Self {
+        self.errors_to_stderr = errors_to_stderr;
+        self
     }
 
     pub fn resolve_packs(&self) -> Result<PathBuf> {
@@ -21,4 +35,27 @@ impl AppContext {
                 .unwrap_or_else(|| PathBuf::from("packs")),
         )
     }
+
+    /// Print the envelope for `err` and remember its exit code.
+    pub fn emit_error(&self, err: &CoreError) {
+        let env = serde_json::to_string_pretty(&err.envelope(err.details())).unwrap();
+        if self.errors_to_stderr {
+            eprintln!("{env}");
+        } else {
+            println!("{env}");
+        }
+        self.fail(err.exit_code());
+    }
+
+    /// Record a failure exit code; the first failure wins.
+    pub fn fail(&self, code: i32) {
+        if self.exit_code.get() == 0 {
+            self.exit_code.set(code);
+        }
+    }
+
+    /// Exit code the process should end with once the command returns.
+    pub fn exit_code(&self) -> i32 {
+        self.exit_code.get()
+    }
 }
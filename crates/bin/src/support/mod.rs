@@ -1,5 +1,9 @@
 mod context;
 mod packs;
+mod selections;
+mod workspace;
 
 pub use context::AppContext;
-pub use packs::{emit_error, resolve_packs};
+pub use packs::resolve_packs;
+pub use selections::collect_selections;
+pub use workspace::workspace_dir;
@@ -0,0 +1,17 @@
+use entity_core::error::CoreResult;
+use entity_core::selections::Selections;
+
+use crate::cli::SelectionArgs;
+
+/// Merge `--selections <file>` with `--set key=value` flags; flags win.
+pub fn collect_selections(args: &SelectionArgs) -> CoreResult<Selections> {
+    let mut selections = match &args.file {
+        Some(path) => Selections::from_file(path)?,
+        None => Selections::new(),
+    };
+    for assignment in &args.set {
+        let (key, value) = Selections::parse_assignment(assignment)?;
+        selections.set(&key, value);
+    }
+    Ok(selections)
+}
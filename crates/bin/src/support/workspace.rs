@@ -0,0 +1,11 @@
+use std::path::PathBuf;
+
+use entity_core::error::CoreResult;
+
+/// `--workspace`, defaulting to the current directory.
+pub fn workspace_dir(workspace: Option<String>) -> CoreResult<PathBuf> {
+    match workspace {
+        Some(path) => Ok(PathBuf::from(path)),
+        None => Ok(std::env::current_dir()?),
+    }
+}
@@ -8,6 +8,7 @@ fn state_fixture() -> (tempfile::TempDir, tempfile::TempDir) {
     (tempfile::tempdir().unwrap(), tempfile::tempdir().unwrap())
 }
 
+#[allow(clippy::collapsible_if)]
 fn bin_cmd() -> Command {
     // Prefer an already built instrumented binary when available (set by coverage harness)
     if let Ok(path) = env::var("CARGO_BIN_EXE_entity-cli").or_else(|_| env::var("CARGO_BIN_EXE_entity_cli")) {
@@ -89,7 +90,17 @@ fn unit_docs_read_invokes_executor() {
 
     // Act: call command directly
     let ctx = crate::support::AppContext::new(Some(packs.path().to_path_buf()));
-    crate::commands::docs::run(&ctx, crate::cli::DocsCmd { command: crate::cli::DocsSubcommand::Read(crate::cli::DocsReadArgs { product: "entity-auth".into(), node: "entityauth:docs:getting-started".into() }) }).unwrap();
+    crate::commands::docs::run(
+        &ctx,
+        crate::cli::DocsCmd {
+            command: crate::cli::DocsSubcommand::Read(crate::cli::DocsReadArgs {
+                product: "entity-auth".into(),
+                node: "entityauth:docs:getting-started".into(),
+                selections: Default::default(),
+            }),
+        },
+    )
+    .unwrap();
 }
 
 #[test]
@@ -107,7 +118,11 @@ fn unit_ui_install_invokes_components() {
     let sign_in = ui_root.join("SignIn");
     fs::create_dir_all(sign_in.join("nested")).unwrap();
     fs::write(sign_in.join("index.tsx"), "export const A = 1;\n").unwrap();
-    fs::write(sign_in.join("nested").join("util.ts"), "export const U = 1;\n").unwrap();
+    fs::write(
+        sign_in.join("nested").join("util.ts"),
+        "export const U = 1;\n",
+    )
+    .unwrap();
     let nodes = serde_json::json!([
         {
             "id": "entityauth:components:install",
@@ -128,9 +143,21 @@ fn unit_ui_install_invokes_components() {
         product: "entity-auth".into(),
         mode: Some("single".into()),
         names: Some(vec!["SignIn".into()]),
+        framework: None,
         node: "entityauth:components:install".into(),
+        workspace: None,
+        selections: Default::default(),
+        with_deps: false,
+        dry_run: false,
+        conflict_policy: None,
     };
-    crate::commands::ui::run(&ctx, crate::cli::UiCmd { command: crate::cli::UiSubcommand::Install(args) }).unwrap();
+    crate::commands::ui::run(
+        &ctx,
+        crate::cli::UiCmd {
+            command: crate::cli::UiSubcommand::Install(args),
+        },
+    )
+    .unwrap();
 }
 #[test]
 fn packs_not_found_yields_json_error() {
@@ -139,7 +166,7 @@ fn packs_not_found_yields_json_error() {
         .arg("entity-auth")
         .arg("--packs")
         .arg("/definitely/not/found");
-    cmd.assert().success().stdout(
+    cmd.assert().code(11).stdout(
         predicate::str::contains("\"code\": \"PACKS_NOT_FOUND\"")
             .and(predicate::str::contains("/definitely/not/found")),
     );
@@ -165,7 +192,7 @@ fn ui_install_missing_mode_yields_missing_selections() {
         .arg("entity-auth")
         .arg("--packs")
         .arg(packs.path());
-    cmd.assert().success().stdout(
+    cmd.assert().code(6).stdout(
         predicate::str::contains("\"code\": \"MISSING_SELECTIONS\"")
             .and(predicate::str::contains("selection.mode"))
             .and(predicate::str::contains("selection.names")),
@@ -370,7 +397,7 @@ fn bridge_start_persists_state_and_status_reports() {
         .arg(packs.path());
     status_after_stop
         .assert()
-        .success()
+        .code(9)
         .stdout(predicate::str::contains("TARGET_NOT_FOUND"));
 }
 
@@ -437,3 +464,628 @@ fn ui_install_happy_path_cli_single() {
     assert!(dest.join("index.tsx").exists());
     assert!(dest.join("nested").join("util.ts").exists());
 }
+
+#[test]
+fn ui_install_writes_into_the_workspace_flag() {
+    let packs = tempfile::tempdir().unwrap();
+    let cwd = tempfile::tempdir().unwrap();
+    let workspace = tempfile::tempdir().unwrap();
+    let comps_dir = packs.path().join("entity-auth/components");
+    fs::create_dir_all(comps_dir.join("ui/SignIn")).unwrap();
+    fs::write(comps_dir.join("ui/SignIn/index.tsx"), "export const A = 1;\n").unwrap();
+    let nodes = serde_json::json!([
+        {
+            "id": "entityauth:components:install",
+            "kind": "component",
+            "title": "Install",
+            "meta": { "names": ["SignIn"] },
+            "payload": { "sourceRoot": "ui" }
+        }
+    ]);
+    fs::write(comps_dir.join("nodes.json"), nodes.to_string()).unwrap();
+
+    let mut cmd = bin_cmd();
+    cmd.current_dir(cwd.path());
+    cmd.args(["ui", "install", "entity-auth", "--mode", "all", "--workspace"])
+        .arg(workspace.path())
+        .arg("--packs")
+        .arg(packs.path());
+    cmd.assert().success();
+    assert!(workspace
+        .path()
+        .join("entity-auth/components/SignIn/index.tsx")
+        .exists());
+    assert!(!cwd.path().join("entity-auth").exists());
+}
+
+#[test]
+fn setup_run_accepts_set_and_selections_file() {
+    let packs = tempfile::tempdir().unwrap();
+    let workspace = tempfile::tempdir().unwrap();
+    let setup_dir = packs.path().join("entity-auth/setup");
+    let template = setup_dir.join("templates/basic/entity-auth");
+    fs::create_dir_all(&template).unwrap();
+    fs::write(template.join("client.ts"), "export {};\n").unwrap();
+    let nodes = serde_json::json!([
+        {
+            "id": "entityauth:setup:basic",
+            "kind": "setup",
+            "title": "Basic",
+            "meta": {},
+            "prerequisites": [
+                { "key": "setup.appName", "schema": { "type": "string", "minLength": 1 } },
+                { "key": "setup.region", "schema": { "enum": ["us", "eu"] } }
+            ],
+            "payload": { "templateRoot": "templates/basic/entity-auth" }
+        }
+    ]);
+    fs::write(setup_dir.join("nodes.json"), nodes.to_string()).unwrap();
+    let selections_file = workspace.path().join("selections.json");
+    fs::write(&selections_file, r#"{ "setup": { "appName": "demo" } }"#).unwrap();
+
+    let mut missing = bin_cmd();
+    missing
+        .arg("setup")
+        .arg("run")
+        .arg("entity-auth")
+        .arg("--node")
+        .arg("entityauth:setup:basic")
+        .arg("--workspace")
+        .arg(workspace.path())
+        .arg("--selections")
+        .arg(&selections_file)
+        .arg("--packs")
+        .arg(packs.path());
+    missing.assert().stdout(
+        predicate::str::contains("MISSING_SELECTIONS")
+            .and(predicate::str::contains("setup.region")),
+    );
+
+    let mut run = bin_cmd();
+    run.arg("setup")
+        .arg("run")
+        .arg("entity-auth")
+        .arg("--node")
+        .arg("entityauth:setup:basic")
+        .arg("--workspace")
+        .arg(workspace.path())
+        .arg("--selections")
+        .arg(&selections_file)
+        .arg("--set")
+        .arg("setup.region=eu")
+        .arg("--packs")
+        .arg(packs.path());
+    run.assert()
+        .success()
+        .stdout(predicate::str::contains("copied"));
+    assert!(workspace.path().join("entity-auth/client.ts").exists());
+}
+
+#[test]
+fn pack_lint_reports_findings_with_file_and_pointer() {
+    let packs = tempfile::tempdir().unwrap();
+    let docs_dir = packs.path().join("entity-auth/docs");
+    fs::create_dir_all(docs_dir.join("content")).unwrap();
+    fs::write(docs_dir.join("content/orphan.md"), "# Orphan\n").unwrap();
+    let nodes = serde_json::json!([
+        {
+            "id": "entityauth:docs:missing",
+            "kind": "doc",
+            "title": "Missing",
+            "meta": {},
+            "payload": { "contentPath": "content/missing.md" }
+        }
+    ]);
+    fs::write(docs_dir.join("nodes.json"), nodes.to_string()).unwrap();
+
+    let mut cmd = bin_cmd();
+    cmd.arg("pack")
+        .arg("lint")
+        .arg("entity-auth")
+        .arg("--packs")
+        .arg(packs.path());
+    let output = cmd.assert().code(3).get_output().stdout.clone();
+    let report: serde_json::Value = serde_json::from_slice(&output).unwrap();
+    assert_eq!(report["ok"], false);
+    let findings = report["findings"].as_array().unwrap();
+    assert!(findings.iter().any(|f| f["code"] == "MISSING_PATH"
+        && f["file"] == "docs/nodes.json"
+        && f["path"] == "/0/payload/contentPath"));
+    assert!(
+        findings
+            .iter()
+            .any(|f| f["code"] == "UNREFERENCED_CONTENT" && f["file"] == "docs/content/orphan.md")
+    );
+}
+
+#[test]
+fn setup_run_with_deps_applies_required_nodes_first() {
+    let packs = tempfile::tempdir().unwrap();
+    let workspace = tempfile::tempdir().unwrap();
+    let pack = packs.path().join("entity-auth");
+    fs::create_dir_all(pack.join("components/ui/SignIn")).unwrap();
+    fs::write(pack.join("components/ui/SignIn/index.tsx"), "export {};\n").unwrap();
+    fs::create_dir_all(pack.join("setup/templates/basic")).unwrap();
+    fs::write(pack.join("setup/templates/basic/client.ts"), "export {};\n").unwrap();
+    let components = serde_json::json!([
+        {
+            "id": "entityauth:components:install",
+            "kind": "component",
+            "title": "Components",
+            "meta": { "names": ["SignIn"] },
+            "payload": { "sourceRoot": "ui" }
+        }
+    ]);
+    fs::write(pack.join("components/nodes.json"), components.to_string()).unwrap();
+    let setup = serde_json::json!([
+        {
+            "id": "entityauth:setup:basic",
+            "kind": "setup",
+            "title": "Basic",
+            "meta": {},
+            "requires": ["entityauth:components:install"],
+            "payload": { "templateRoot": "templates/basic" }
+        }
+    ]);
+    fs::write(pack.join("setup/nodes.json"), setup.to_string()).unwrap();
+
+    let mut cmd = bin_cmd();
+    cmd.arg("setup")
+        .arg("run")
+        .arg("entity-auth")
+        .arg("--node")
+        .arg("entityauth:setup:basic")
+        .arg("--workspace")
+        .arg(workspace.path())
+        .arg("--with-deps")
+        .arg("--packs")
+        .arg(packs.path());
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let report: serde_json::Value = serde_json::from_slice(&output).unwrap();
+    assert_eq!(
+        report["order"],
+        serde_json::json!(["entityauth:components:install", "entityauth:setup:basic"])
+    );
+    assert!(
+        workspace
+            .path()
+            .join("entity-auth/components/SignIn/index.tsx")
+            .exists()
+    );
+    assert!(workspace.path().join("entity-auth/client.ts").exists());
+}
+
+#[test]
+fn graph_query_filters_by_kind_and_id_prefix() {
+    let packs = tempfile::tempdir().unwrap();
+    let pack = packs.path().join("entity-auth");
+    fs::create_dir_all(pack.join("docs/content")).unwrap();
+    fs::write(pack.join("docs/content/a.md"), "a").unwrap();
+    fs::create_dir_all(pack.join("components/ui")).unwrap();
+    let docs = serde_json::json!([
+        { "id": "entityauth:docs:replicator:a", "kind": "doc", "title": "A", "meta": {},
+          "payload": { "contentPath": "content/a.md" } },
+        { "id": "entityauth:docs:auth:b", "kind": "doc", "title": "B", "meta": {},
+          "payload": { "contentPath": "content/a.md" } }
+    ]);
+    fs::write(pack.join("docs/nodes.json"), docs.to_string()).unwrap();
+    let components = serde_json::json!([
+        { "id": "entityauth:components:install", "kind": "component", "title": "C",
+          "meta": {}, "payload": { "sourceRoot": "ui" } }
+    ]);
+    fs::write(pack.join("components/nodes.json"), components.to_string()).unwrap();
+
+    let mut cmd = bin_cmd();
+    cmd.arg("graph")
+        .arg("query")
+        .arg("entity-auth")
+        .arg("--kind")
+        .arg("doc")
+        .arg("--id-prefix")
+        .arg("entityauth:docs:replicator")
+        .arg("--packs")
+        .arg(packs.path());
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let graph: serde_json::Value = serde_json::from_slice(&output).unwrap();
+    let nodes = graph["nodes"].as_array().unwrap();
+    assert_eq!(nodes.len(), 1);
+    assert_eq!(nodes[0]["id"], "entityauth:docs:replicator:a");
+}
+
+#[test]
+fn init_graph_and_lint_report_unresolvable_packs_as_envelopes() {
+    let workspace = tempfile::tempdir().unwrap();
+    fs::write(workspace.path().join("entitycli.json"), "{ not json").unwrap();
+
+    for args in [
+        &["init", "entity-auth"][..],
+        &["graph", "query", "entity-auth"][..],
+        &["pack", "lint", "entity-auth"][..],
+    ] {
+        let mut cmd = bin_cmd();
+        cmd.current_dir(workspace.path())
+            .env_remove("ENTITY_CLI_PACKS")
+            .args(args);
+        let output = cmd.assert().code(3).get_output().stdout.clone();
+        let env: serde_json::Value = serde_json::from_slice(&output).unwrap();
+        assert_eq!(env["error"]["code"], "INVALID_DESCRIPTOR");
+    }
+}
+
+#[test]
+fn init_compact_respects_token_budget() {
+    let packs = tempfile::tempdir().unwrap();
+    let docs_dir = packs.path().join("entity-auth/docs");
+    fs::create_dir_all(docs_dir.join("content")).unwrap();
+    fs::write(docs_dir.join("content/a.md"), "a").unwrap();
+    let nodes: Vec<serde_json::Value> = (0..30)
+        .map(|i| {
+            serde_json::json!({
+                "id": format!("entityauth:docs:{i}"),
+                "kind": "doc",
+                "title": format!("A fairly long document title number {i}"),
+                "meta": { "section": "Guides", "tags": ["guide"] },
+                "payload": { "contentPath": "content/a.md" }
+            })
+        })
+        .collect();
+    fs::write(docs_dir.join("nodes.json"), serde_json::Value::from(nodes).to_string()).unwrap();
+
+    let mut cmd = bin_cmd();
+    cmd.arg("init")
+        .arg("entity-auth")
+        .arg("--compact")
+        .arg("--packs")
+        .arg(packs.path());
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let compact: serde_json::Value = serde_json::from_slice(&output).unwrap();
+    assert_eq!(compact["sections"][0]["section"], "Guides");
+    assert_eq!(compact["sections"][0]["meta"]["tags"], serde_json::json!(["guide"]));
+    assert!(!String::from_utf8_lossy(&output).contains("contentPath"));
+
+    let mut cmd = bin_cmd();
+    cmd.arg("init")
+        .arg("entity-auth")
+        .arg("--budget")
+        .arg("900")
+        .arg("--packs")
+        .arg(packs.path());
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let text = String::from_utf8_lossy(&output);
+    assert!(text.trim().len().div_ceil(4) <= 900);
+    let budgeted: serde_json::Value = serde_json::from_str(&text).unwrap();
+    assert!(budgeted["omitted"]["count"].as_u64().unwrap() > 0);
+}
+
+#[test]
+fn errors_to_stderr_keeps_stdout_for_payloads() {
+    let packs = tempfile::tempdir().unwrap();
+    let docs_dir = packs.path().join("entity-auth/docs");
+    fs::create_dir_all(&docs_dir).unwrap();
+    fs::write(docs_dir.join("nodes.json"), "[]").unwrap();
+
+    let mut cmd = bin_cmd();
+    cmd.arg("docs")
+        .arg("read")
+        .arg("entity-auth")
+        .arg("--node")
+        .arg("entityauth:docs:nope")
+        .arg("--errors-to-stderr")
+        .arg("--packs")
+        .arg(packs.path());
+    let assert = cmd.assert().code(4).stdout(predicate::str::is_empty());
+    let stderr = assert.get_output().stderr.clone();
+    let envelope: serde_json::Value = serde_json::from_slice(&stderr).unwrap();
+    assert_eq!(envelope["error"]["code"], "UNKNOWN_NODE");
+
+    let mut cmd = bin_cmd();
+    cmd.arg("init")
+        .arg("entity-auth")
+        .arg("--errors-to-stderr")
+        .arg("--packs")
+        .arg(packs.path());
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("executable"))
+        .stderr(predicate::str::is_empty());
+}
+
+#[test]
+fn ui_install_dry_run_prints_plan_and_writes_nothing() {
+    let packs = tempfile::tempdir().unwrap();
+    let workspace = tempfile::tempdir().unwrap();
+    let comps_dir = packs.path().join("entity-auth/components");
+    fs::create_dir_all(comps_dir.join("ui/SignIn")).unwrap();
+    fs::write(comps_dir.join("ui/SignIn/index.tsx"), "export const A = 1;\n").unwrap();
+    let nodes = serde_json::json!([
+        {
+            "id": "entityauth:components:install",
+            "kind": "component",
+            "title": "Install",
+            "meta": { "names": ["SignIn"] },
+            "payload": { "sourceRoot": "ui" }
+        }
+    ]);
+    fs::write(comps_dir.join("nodes.json"), nodes.to_string()).unwrap();
+
+    let mut cmd = bin_cmd();
+    cmd.current_dir(workspace.path());
+    cmd.arg("ui")
+        .arg("install")
+        .arg("entity-auth")
+        .arg("--mode")
+        .arg("all")
+        .arg("--dry-run")
+        .arg("--packs")
+        .arg(packs.path());
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let report: serde_json::Value = serde_json::from_slice(&output).unwrap();
+    let file = &report["plan"]["files"][0];
+    assert_eq!(file["action"], "create");
+    assert_eq!(file["source"]["size"], 20);
+    assert!(file["to"].as_str().unwrap().ends_with("components/SignIn/index.tsx"));
+    assert!(!workspace.path().join("entity-auth").exists());
+}
+
+#[test]
+fn ui_status_reports_locally_modified_components() {
+    let packs = tempfile::tempdir().unwrap();
+    let workspace = tempfile::tempdir().unwrap();
+    let comps_dir = packs.path().join("entity-auth/components");
+    fs::create_dir_all(comps_dir.join("ui/SignIn")).unwrap();
+    fs::write(comps_dir.join("ui/SignIn/index.tsx"), "export const A = 1;\n").unwrap();
+    let nodes = serde_json::json!([
+        {
+            "id": "entityauth:components:install",
+            "kind": "component",
+            "title": "Install",
+            "meta": { "names": ["SignIn"] },
+            "payload": { "sourceRoot": "ui" }
+        }
+    ]);
+    fs::write(comps_dir.join("nodes.json"), nodes.to_string()).unwrap();
+
+    let mut cmd = bin_cmd();
+    cmd.current_dir(workspace.path());
+    cmd.args(["ui", "install", "entity-auth", "--mode", "all", "--packs"])
+        .arg(packs.path());
+    cmd.assert().success();
+    fs::write(
+        workspace.path().join("entity-auth/components/SignIn/index.tsx"),
+        "export const A = 2;\n",
+    )
+    .unwrap();
+
+    let mut cmd = bin_cmd();
+    cmd.args(["ui", "status", "entity-auth", "--workspace"])
+        .arg(workspace.path())
+        .arg("--packs")
+        .arg(packs.path());
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let report: serde_json::Value = serde_json::from_slice(&output).unwrap();
+    let component = &report["components"][0];
+    assert_eq!(component["name"], "SignIn");
+    assert_eq!(component["modified"], true);
+    assert_eq!(component["outdated"], false);
+    assert_eq!(component["files"][0]["status"], "modified");
+}
+
+#[test]
+fn ui_update_reports_conflicts_and_exits_with_write_conflict() {
+    let packs = tempfile::tempdir().unwrap();
+    let workspace = tempfile::tempdir().unwrap();
+    let comps_dir = packs.path().join("entity-auth/components");
+    fs::create_dir_all(comps_dir.join("ui/SignIn")).unwrap();
+    fs::write(comps_dir.join("ui/SignIn/index.tsx"), "export const A = 1;\n").unwrap();
+    let nodes = serde_json::json!([
+        {
+            "id": "entityauth:components:install",
+            "kind": "component",
+            "title": "Install",
+            "meta": { "names": ["SignIn"] },
+            "payload": { "sourceRoot": "ui" }
+        }
+    ]);
+    fs::write(comps_dir.join("nodes.json"), nodes.to_string()).unwrap();
+
+    let mut cmd = bin_cmd();
+    cmd.current_dir(workspace.path());
+    cmd.args(["ui", "install", "entity-auth", "--mode", "all", "--packs"])
+        .arg(packs.path());
+    cmd.assert().success();
+    let installed = workspace.path().join("entity-auth/components/SignIn/index.tsx");
+    fs::write(&installed, "export const A = 2;\n").unwrap();
+    fs::write(comps_dir.join("ui/SignIn/index.tsx"), "export const A = 3;\n").unwrap();
+
+    let mut cmd = bin_cmd();
+    cmd.current_dir(workspace.path());
+    cmd.args(["ui", "update", "entity-auth", "--packs"])
+        .arg(packs.path());
+    let output = cmd.assert().code(16).get_output().stdout.clone();
+    let report: serde_json::Value = serde_json::from_slice(&output).unwrap();
+    assert_eq!(report["files"][0]["action"], "conflict");
+    assert_eq!(report["conflicts"][0]["ours"], "export const A = 2;\n");
+    assert_eq!(report["conflicts"][0]["theirs"], "export const A = 3;\n");
+    assert!(fs::read_to_string(&installed).unwrap().contains("<<<<<<< ours"));
+}
+
+#[test]
+fn ui_uninstall_refuses_modified_files_unless_forced() {
+    let packs = tempfile::tempdir().unwrap();
+    let workspace = tempfile::tempdir().unwrap();
+    let comps_dir = packs.path().join("entity-auth/components");
+    fs::create_dir_all(comps_dir.join("ui/SignIn")).unwrap();
+    fs::write(comps_dir.join("ui/SignIn/index.tsx"), "export const A = 1;\n").unwrap();
+    let nodes = serde_json::json!([
+        {
+            "id": "entityauth:components:install",
+            "kind": "component",
+            "title": "Install",
+            "meta": { "names": ["SignIn"] },
+            "payload": { "sourceRoot": "ui" }
+        }
+    ]);
+    fs::write(comps_dir.join("nodes.json"), nodes.to_string()).unwrap();
+
+    let mut cmd = bin_cmd();
+    cmd.current_dir(workspace.path());
+    cmd.args(["ui", "install", "entity-auth", "--mode", "all", "--packs"])
+        .arg(packs.path());
+    cmd.assert().success();
+    let installed = workspace.path().join("entity-auth/components/SignIn/index.tsx");
+    fs::write(&installed, "export const A = 2;\n").unwrap();
+
+    let mut cmd = bin_cmd();
+    cmd.current_dir(workspace.path());
+    cmd.args(["ui", "uninstall", "entity-auth", "--names", "SignIn", "--packs"])
+        .arg(packs.path());
+    let output = cmd.assert().code(16).get_output().stdout.clone();
+    let env: serde_json::Value = serde_json::from_slice(&output).unwrap();
+    assert_eq!(env["error"]["code"], "WRITE_CONFLICT");
+    let message = env["error"]["message"].as_str().unwrap();
+    assert!(message.contains("edited since install") && message.contains("--force"));
+    assert!(installed.exists());
+
+    let mut cmd = bin_cmd();
+    cmd.current_dir(workspace.path());
+    cmd.args(["ui", "uninstall", "entity-auth", "--force", "--packs"])
+        .arg(packs.path());
+    cmd.assert().success();
+    assert!(!installed.exists());
+    assert!(!workspace.path().join("entity-auth/components/SignIn").exists());
+}
+
+#[test]
+fn ui_uninstall_takes_the_declared_name_of_kebab_case_files() {
+    let packs = tempfile::tempdir().unwrap();
+    let workspace = tempfile::tempdir().unwrap();
+    let comps_dir = packs.path().join("entity-auth/components");
+    fs::create_dir_all(comps_dir.join("ui")).unwrap();
+    fs::write(comps_dir.join("ui/sign-in.tsx"), "export const A = 1;\n").unwrap();
+    let nodes = serde_json::json!([
+        {
+            "id": "entityauth:components:install",
+            "kind": "component",
+            "title": "Install",
+            "meta": { "names": ["SignIn"] },
+            "payload": { "sourceRoot": "ui" }
+        }
+    ]);
+    fs::write(comps_dir.join("nodes.json"), nodes.to_string()).unwrap();
+
+    let mut cmd = bin_cmd();
+    cmd.current_dir(workspace.path());
+    cmd.args(["ui", "install", "entity-auth", "--mode", "all", "--packs"])
+        .arg(packs.path());
+    cmd.assert().success();
+    let installed = workspace.path().join("entity-auth/components/sign-in.tsx");
+    assert!(installed.exists());
+
+    let mut cmd = bin_cmd();
+    cmd.current_dir(workspace.path());
+    cmd.args(["ui", "uninstall", "entity-auth", "--names", "SignIn", "--packs"])
+        .arg(packs.path());
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let report: serde_json::Value = serde_json::from_slice(&output).unwrap();
+    assert_eq!(report["removed"][0], "entity-auth/components/sign-in.tsx");
+    assert!(!installed.exists());
+}
+
+#[test]
+fn undo_restores_files_an_uninstall_removed() {
+    let packs = tempfile::tempdir().unwrap();
+    let workspace = tempfile::tempdir().unwrap();
+    let comps_dir = packs.path().join("entity-auth/components");
+    fs::create_dir_all(comps_dir.join("ui/SignIn")).unwrap();
+    fs::write(comps_dir.join("ui/SignIn/index.tsx"), "export const A = 1;\n").unwrap();
+    let nodes = serde_json::json!([
+        {
+            "id": "entityauth:components:install",
+            "kind": "component",
+            "title": "Install",
+            "meta": { "names": ["SignIn"] },
+            "payload": { "sourceRoot": "ui" }
+        }
+    ]);
+    fs::write(comps_dir.join("nodes.json"), nodes.to_string()).unwrap();
+
+    let mut cmd = bin_cmd();
+    cmd.current_dir(workspace.path());
+    cmd.args(["ui", "install", "entity-auth", "--mode", "all", "--packs"])
+        .arg(packs.path());
+    cmd.assert().success();
+    let mut cmd = bin_cmd();
+    cmd.current_dir(workspace.path());
+    cmd.args(["ui", "uninstall", "entity-auth", "--packs"])
+        .arg(packs.path());
+    cmd.assert().success();
+    let installed = workspace.path().join("entity-auth/components/SignIn/index.tsx");
+    assert!(!installed.exists());
+
+    let mut cmd = bin_cmd();
+    cmd.arg("undo").arg("--workspace").arg(workspace.path());
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let report: serde_json::Value = serde_json::from_slice(&output).unwrap();
+    assert_eq!(report["reverted"][0]["command"], "ui uninstall");
+    assert_eq!(fs::read_to_string(&installed).unwrap(), "export const A = 1;\n");
+
+    // Reverting the install as well leaves the workspace as it started
+    let mut cmd = bin_cmd();
+    cmd.arg("undo").arg("--workspace").arg(workspace.path());
+    cmd.assert().success();
+    assert!(!installed.exists());
+    assert!(!workspace.path().join(executors::LOCK_FILE).exists());
+}
+
+#[test]
+fn ui_diff_prints_json_hunks_or_a_patch() {
+    let packs = tempfile::tempdir().unwrap();
+    let workspace = tempfile::tempdir().unwrap();
+    let comps_dir = packs.path().join("entity-auth/components");
+    fs::create_dir_all(comps_dir.join("ui/SignIn")).unwrap();
+    fs::write(comps_dir.join("ui/SignIn/index.tsx"), "export const A = 1;\n").unwrap();
+    let nodes = serde_json::json!([
+        {
+            "id": "entityauth:components:install",
+            "kind": "component",
+            "title": "Install",
+            "meta": { "names": ["SignIn"] },
+            "payload": { "sourceRoot": "ui" }
+        }
+    ]);
+    fs::write(comps_dir.join("nodes.json"), nodes.to_string()).unwrap();
+
+    let mut cmd = bin_cmd();
+    cmd.current_dir(workspace.path());
+    cmd.args(["ui", "install", "entity-auth", "--mode", "all", "--packs"])
+        .arg(packs.path());
+    cmd.assert().success();
+    fs::write(comps_dir.join("ui/SignIn/index.tsx"), "export const A = 2;\n").unwrap();
+
+    let mut cmd = bin_cmd();
+    cmd.current_dir(workspace.path());
+    cmd.args(["ui", "diff", "entity-auth", "--names", "SignIn", "--packs"])
+        .arg(packs.path());
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let report: serde_json::Value = serde_json::from_slice(&output).unwrap();
+    assert_eq!(report["files"][0]["path"], "entity-auth/components/SignIn/index.tsx");
+    assert_eq!(report["files"][0]["status"], "modified");
+    assert_eq!(
+        report["files"][0]["hunks"][0]["lines"],
+        serde_json::json!(["-export const A = 1;", "+export const A = 2;"])
+    );
+
+    let mut cmd = bin_cmd();
+    cmd.current_dir(workspace.path());
+    cmd.args(["ui", "diff", "entity-auth", "--patch", "--packs"])
+        .arg(packs.path());
+    let output = cmd.assert().success().get_output().stdout.clone();
+    assert_eq!(
+        String::from_utf8(output).unwrap(),
+        "--- a/entity-auth/components/SignIn/index.tsx\n\
+         +++ b/entity-auth/components/SignIn/index.tsx\n\
+         @@ -1 +1 @@\n\
+         -export const A = 1;\n\
+         +export const A = 2;\n"
+    );
+}
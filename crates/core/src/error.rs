@@ -1,6 +1,8 @@
 use serde::Serialize;
 use thiserror::Error;
 
+use crate::schema::SchemaViolation;
+
 pub type CoreResult<T, E = CoreError> = Result<T, E>;
 
 #[derive(Debug, Error)]
@@ -11,6 +13,14 @@ pub enum CoreError {
     Json(#[from] serde_json::Error),
     #[error("Invalid descriptor: {0}")]
     InvalidDescriptor(String),
+    #[error("Invalid descriptor: {}", render_location(.file.as_deref(), .path, .message))]
+    InvalidNode {
+        /// nodes.json the node came from, when known
+        file: Option<String>,
+        /// JSON pointer of the failing field
+        path: String,
+        message: String,
+    },
     #[error("Unknown node id: {0}")]
     UnknownNode(String),
     #[error("Wrong node kind: expected {expected}, got {actual}")]
@@ -21,6 +31,8 @@ pub enum CoreError {
     InvalidSelection(String),
     #[error("Invalid selection names: {0:?}")]
     InvalidNames(Vec<String>),
+    #[error("Invalid selection: {}", render_violations(.0))]
+    SchemaViolations(Vec<SchemaViolation>),
     #[error("Missing source: {0}")]
     MissingSource(String),
     #[error("Target path not found: {0}")]
@@ -29,6 +41,42 @@ pub enum CoreError {
     TargetNotWritable(String),
     #[error("Packs path not found or unreadable: {0}")]
     PacksNotFound(String),
+    #[error("Existing files differ from the pack: {}", .0.join(", "))]
+    WriteConflict(Vec<String>),
+    #[error("Files were edited since install (use --force to remove them anyway): {}", .0.join(", "))]
+    EditedSinceInstall(Vec<String>),
+    #[error("Undefined template variables: {}", .variables.join(", "))]
+    UndefinedVariables {
+        variables: Vec<String>,
+        /// Templates (or template paths) referencing them
+        files: Vec<String>,
+    },
+    #[error("Write failed and was rolled back: {cause}")]
+    RolledBack {
+        cause: String,
+        /// Workspace files put back as they were
+        restored: Vec<String>,
+        /// Files the rollback could not put back
+        unrestored: Vec<String>,
+    },
+    #[error("{failed} failed after {} were applied: {source}", .applied.join(", "))]
+    PartiallyApplied {
+        /// Dependency step that failed
+        failed: String,
+        /// Steps applied before it, which stay in the workspace
+        applied: Vec<String>,
+        /// Report of the applied steps
+        report: serde_json::Value,
+        source: Box<CoreError>,
+    },
+    #[error("Dependency cycle: {}", .0.join(" -> "))]
+    DependencyCycle(Vec<String>),
+    #[error("Pack {pack} requires entity-cli {required}, running {engine}")]
+    IncompatiblePack {
+        pack: String,
+        required: String,
+        engine: String,
+    },
 }
 
 #[derive(Debug, Serialize)]
@@ -50,15 +98,81 @@ impl CoreError {
             CoreError::Io(_) => "IO_ERROR",
             CoreError::Json(_) => "JSON_ERROR",
             CoreError::InvalidDescriptor(_) => "INVALID_DESCRIPTOR",
+            CoreError::InvalidNode { .. } => "INVALID_DESCRIPTOR",
             CoreError::UnknownNode(_) => "UNKNOWN_NODE",
             CoreError::WrongKind { .. } => "WRONG_KIND",
             CoreError::MissingSelections(_) => "MISSING_SELECTIONS",
             CoreError::InvalidSelection(_) => "INVALID_SELECTION",
             CoreError::InvalidNames(_) => "INVALID_SELECTION",
+            CoreError::SchemaViolations(_) => "INVALID_SELECTION",
             CoreError::MissingSource(_) => "MISSING_SOURCE",
             CoreError::TargetNotFound(_) => "TARGET_NOT_FOUND",
             CoreError::TargetNotWritable(_) => "TARGET_NOT_WRITABLE",
             CoreError::PacksNotFound(_) => "PACKS_NOT_FOUND",
+            CoreError::IncompatiblePack { .. } => "INCOMPATIBLE_PACK",
+            CoreError::DependencyCycle(_) => "DEPENDENCY_CYCLE",
+            CoreError::WriteConflict(_) => "WRITE_CONFLICT",
+            CoreError::EditedSinceInstall(_) => "WRITE_CONFLICT",
+            CoreError::UndefinedVariables { .. } => "UNDEFINED_VARIABLE",
+            CoreError::RolledBack { .. } => "ROLLED_BACK",
+            CoreError::PartiallyApplied { source, .. } => source.code(),
+        }
+    }
+
+    /// Stable process exit code for this error (see the README table). Codes 1
+    /// and 2 stay reserved for unexpected failures and usage errors.
+    pub fn exit_code(&self) -> i32 {
+        exit_code_for(self.code())
+    }
+
+    /// Structured details that accompany the envelope for this error, if any.
+    pub fn details(&self) -> Option<serde_json::Value> {
+        match self {
+            CoreError::MissingSelections(keys) => Some(serde_json::json!({ "missing": keys })),
+            CoreError::InvalidSelection(msg) => Some(serde_json::json!({ "message": msg })),
+            CoreError::InvalidNames(list) => Some(serde_json::json!({ "invalidNames": list })),
+            CoreError::SchemaViolations(list) => Some(serde_json::json!({ "violations": list })),
+            CoreError::InvalidNode { file, path, .. } => {
+                Some(serde_json::json!({ "file": file, "path": path }))
+            }
+            CoreError::DependencyCycle(cycle) => Some(serde_json::json!({ "cycle": cycle })),
+            CoreError::WriteConflict(files) | CoreError::EditedSinceInstall(files) => {
+                Some(serde_json::json!({ "files": files }))
+            }
+            CoreError::UndefinedVariables { variables, files } => {
+                Some(serde_json::json!({ "variables": variables, "files": files }))
+            }
+            CoreError::RolledBack {
+                cause,
+                restored,
+                unrestored,
+            } => Some(serde_json::json!({
+                "transaction": "rolledBack",
+                "cause": cause,
+                "restored": restored,
+                "unrestored": unrestored,
+            })),
+            CoreError::PartiallyApplied {
+                failed,
+                report,
+                source,
+                ..
+            } => {
+                let mut details = match source.details() {
+                    Some(serde_json::Value::Object(map)) => map,
+                    _ => serde_json::Map::new(),
+                };
+                details.insert("failedNode".into(), serde_json::json!(failed));
+                details.insert("applied".into(), report.clone());
+                Some(serde_json::Value::Object(details))
+            }
+            CoreError::PacksNotFound(p) => Some(serde_json::json!({ "packsPath": p })),
+            CoreError::IncompatiblePack {
+                pack,
+                required,
+                engine,
+            } => Some(serde_json::json!({ "pack": pack, "required": required, "engine": engine })),
+            _ => None,
         }
     }
 
@@ -72,3 +186,40 @@ impl CoreError {
         }
     }
 }
+
+/// Exit code for an envelope `code`; unknown codes map to 1.
+pub fn exit_code_for(code: &str) -> i32 {
+    match code {
+        "INVALID_DESCRIPTOR" => 3,
+        "UNKNOWN_NODE" => 4,
+        "WRONG_KIND" => 5,
+        "MISSING_SELECTIONS" => 6,
+        "INVALID_SELECTION" => 7,
+        "MISSING_SOURCE" => 8,
+        "TARGET_NOT_FOUND" => 9,
+        "TARGET_NOT_WRITABLE" => 10,
+        "PACKS_NOT_FOUND" => 11,
+        "INCOMPATIBLE_PACK" => 12,
+        "DEPENDENCY_CYCLE" => 13,
+        "IO_ERROR" => 14,
+        "JSON_ERROR" => 15,
+        "WRITE_CONFLICT" => 16,
+        "UNDEFINED_VARIABLE" => 17,
+        "ROLLED_BACK" => 18,
+        _ => 1,
+    }
+}
+
+fn render_violations(list: &[SchemaViolation]) -> String {
+    list.iter()
+        .map(|v| format!("{} {}", v.path, v.message))
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+fn render_location(file: Option<&str>, path: &str, message: &str) -> String {
+    match file {
+        Some(file) => format!("{file} at {path}: {message}"),
+        None => format!("{path}: {message}"),
+    }
+}
@@ -1,12 +1,21 @@
 pub mod error;
 pub mod loader;
 pub mod model;
+pub mod pack;
+pub mod query;
 pub mod registry;
+pub mod schema;
+pub mod selections;
+pub mod workspace;
 
 pub use error::{CoreError, CoreResult};
-pub use loader::load_nodes_from_file;
+pub use loader::{load_node_results, load_nodes_from_file};
 pub use model::{
-    CommandShapes, DocsCommandShape, GraphPackage, Node, NodeKind, NodePayload, Prerequisite,
-    UiCommandShape,
+    CommandShapes, ComponentSpec, ConflictPolicy, DocsCommandShape, Edge, EdgeKind, GraphPackage,
+    Node, NodeKind, NodePayload, PackageJsonSpec, Prerequisite, UiCommandShape,
 };
+pub use pack::{Pack, PackManifest};
+pub use query::NodeQuery;
 pub use registry::Registry;
+pub use selections::Selections;
+pub use workspace::WorkspaceConfig;
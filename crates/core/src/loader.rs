@@ -5,83 +5,102 @@ use std::path::{Path, PathBuf};
 use serde_json::Value;
 
 use crate::error::{CoreError, CoreResult};
-use crate::model::{BridgeSpawnDescriptor, Node, NodePayload};
+use crate::model::{json_pointer, BridgeSpawnDescriptor, Node, NodePayload, RawNode};
 
 pub fn load_nodes_from_file(path: &Path) -> CoreResult<Vec<Node>> {
+    load_node_results(path)?.into_iter().collect()
+}
+
+/// Load a nodes.json file, keeping going past nodes that fail to parse so every
+/// problem can be reported. Only an unreadable or non-array file is a hard error.
+pub fn load_node_results(path: &Path) -> CoreResult<Vec<CoreResult<Node>>> {
     let file = File::open(path)?;
     let reader = BufReader::new(file);
     let raw_nodes: Vec<Value> = serde_json::from_reader(reader)?;
     let base_dir = path.parent().unwrap_or(Path::new("."));
-    let mut nodes: Vec<Node> = Vec::new();
-    for value in raw_nodes.into_iter() {
-        let mut node = Node::try_from(value)?;
-        // Rewrite payload paths to be relative to the nodes.json directory if they are relative
-        match &mut node.payload {
-            NodePayload::Doc { content_path } => {
-                let p = PathBuf::from(&*content_path);
+    Ok(raw_nodes
+        .into_iter()
+        .enumerate()
+        .map(|(index, value)| {
+            let mut node = Node::try_from(value).map_err(|err| locate(err, path, index))?;
+            resolve_payload_paths(&mut node, base_dir);
+            Ok(node)
+        })
+        .collect())
+}
+
+fn resolve_payload_paths(node: &mut Node, base_dir: &Path) {
+    // Rewrite payload paths to be relative to the nodes.json directory if they are relative
+    match &mut node.payload {
+        NodePayload::Doc { content_path } => {
+            let p = PathBuf::from(&*content_path);
+            if p.is_relative() {
+                let abs = base_dir.join(&p);
+                *content_path = abs.to_string_lossy().to_string();
+            }
+        }
+        NodePayload::Component {
+            source_root,
+            variants,
+            ..
+        } => {
+            for root in std::iter::once(source_root).chain(variants.values_mut()) {
+                let p = PathBuf::from(&*root);
                 if p.is_relative() {
                     let abs = base_dir.join(&p);
-                    *content_path = abs.to_string_lossy().to_string();
+                    *root = abs.to_string_lossy().to_string();
                 }
             }
-            NodePayload::Component { source_root } => {
-                let p = PathBuf::from(&*source_root);
+        }
+        NodePayload::Setup { template_root, .. } => {
+            let p = PathBuf::from(&*template_root);
+            if p.is_relative() {
+                let abs = base_dir.join(&p);
+                *template_root = abs.to_string_lossy().to_string();
+            }
+        }
+        NodePayload::Bridge {
+            template_root,
+            runner,
+            config_template,
+            spawn,
+            logs_path,
+            heartbeat_interval_ms: _,
+            exclude: _,
+        } => {
+            if let Some(root) = template_root {
+                let p = Path::new(root);
                 if p.is_relative() {
-                    let abs = base_dir.join(&p);
-                    *source_root = abs.to_string_lossy().to_string();
+                    let abs = base_dir.join(p);
+                    *template_root = Some(abs.to_string_lossy().to_string());
                 }
             }
-            NodePayload::Setup { template_root, .. } => {
-                let p = PathBuf::from(&*template_root);
+            if let Some(path) = runner {
+                let p = Path::new(path);
                 if p.is_relative() {
-                    let abs = base_dir.join(&p);
-                    *template_root = abs.to_string_lossy().to_string();
+                    let abs = base_dir.join(p);
+                    *runner = Some(abs.to_string_lossy().to_string());
                 }
             }
-            NodePayload::Bridge {
-                template_root,
-                runner,
-                config_template,
-                spawn,
-                logs_path,
-                heartbeat_interval_ms: _,
-            } => {
-                if let Some(root) = template_root {
-                    let p = Path::new(root);
-                    if p.is_relative() {
-                        let abs = base_dir.join(p);
-                        *template_root = Some(abs.to_string_lossy().to_string());
-                    }
-                }
-                if let Some(path) = runner {
-                    let p = Path::new(path);
-                    if p.is_relative() {
-                        let abs = base_dir.join(p);
-                        *runner = Some(abs.to_string_lossy().to_string());
-                    }
-                }
-                if let Some(path) = config_template {
-                    let p = Path::new(path);
-                    if p.is_relative() {
-                        let abs = base_dir.join(p);
-                        *config_template = Some(abs.to_string_lossy().to_string());
-                    }
-                }
-                if let Some(descriptor) = spawn {
-                    normalize_spawn_paths(descriptor, base_dir);
+            if let Some(path) = config_template {
+                let p = Path::new(path);
+                if p.is_relative() {
+                    let abs = base_dir.join(p);
+                    *config_template = Some(abs.to_string_lossy().to_string());
                 }
-                if let Some(path) = logs_path {
-                    let p = Path::new(path);
-                    if p.is_relative() {
-                        let abs = base_dir.join(p);
-                        *logs_path = Some(abs.to_string_lossy().to_string());
-                    }
+            }
+            if let Some(descriptor) = spawn {
+                normalize_spawn_paths(descriptor, base_dir);
+            }
+            if let Some(path) = logs_path {
+                let p = Path::new(path);
+                if p.is_relative() {
+                    let abs = base_dir.join(p);
+                    *logs_path = Some(abs.to_string_lossy().to_string());
                 }
             }
         }
-        nodes.push(node);
     }
-    Ok(nodes)
 }
 
 fn normalize_spawn_paths(descriptor: &mut BridgeSpawnDescriptor, base_dir: &Path) {
@@ -96,34 +115,43 @@ impl TryFrom<Value> for Node {
     type Error = CoreError;
 
     fn try_from(value: Value) -> CoreResult<Self> {
-        // Allow both snake_case and camelCase payloads by remapping keys for our struct
-        let node: Node = serde_json::from_value(value.clone()).map_err(|err| {
-            CoreError::InvalidDescriptor(format!("invalid node json: {err}: {value}"))
-        })?;
+        let raw: RawNode =
+            serde_path_to_error::deserialize(value).map_err(|err| CoreError::InvalidNode {
+                file: None,
+                path: json_pointer(err.path().iter()),
+                message: format!("invalid node json: {}", err.inner()),
+            })?;
+        // The payload is read as the variant named by `kind`
+        let node = Node::try_from(raw)?;
 
+        let empty = |field: &str, message: String| CoreError::InvalidNode {
+            file: None,
+            path: format!("/payload/{field}"),
+            message,
+        };
         match &node.payload {
             NodePayload::Doc { content_path } => {
                 if content_path.is_empty() {
-                    return Err(CoreError::InvalidDescriptor(format!(
-                        "doc node {} missing content_path",
-                        node.id
-                    )));
+                    return Err(empty(
+                        "contentPath",
+                        format!("doc node {} missing content_path", node.id),
+                    ));
                 }
             }
-            NodePayload::Component { source_root } => {
+            NodePayload::Component { source_root, .. } => {
                 if source_root.is_empty() {
-                    return Err(CoreError::InvalidDescriptor(format!(
-                        "component node {} missing source_root",
-                        node.id
-                    )));
+                    return Err(empty(
+                        "sourceRoot",
+                        format!("component node {} missing source_root", node.id),
+                    ));
                 }
             }
             NodePayload::Setup { template_root, .. } => {
                 if template_root.is_empty() {
-                    return Err(CoreError::InvalidDescriptor(format!(
-                        "setup node {} missing template_root",
-                        node.id
-                    )));
+                    return Err(empty(
+                        "templateRoot",
+                        format!("setup node {} missing template_root", node.id),
+                    ));
                 }
             }
             NodePayload::Bridge {
@@ -133,10 +161,14 @@ impl TryFrom<Value> for Node {
                 ..
             } => {
                 if template_root.is_none() && runner.is_none() && spawn.is_none() {
-                    return Err(CoreError::InvalidDescriptor(format!(
-                        "bridge node {} must specify templateRoot, runner, or spawn entry",
-                        node.id
-                    )));
+                    return Err(CoreError::InvalidNode {
+                        file: None,
+                        path: "/payload".to_string(),
+                        message: format!(
+                            "bridge node {} must specify templateRoot, runner, or spawn entry",
+                            node.id
+                        ),
+                    });
                 }
             }
         }
@@ -144,3 +176,102 @@ impl TryFrom<Value> for Node {
         Ok(node)
     }
 }
+
+/// Attach the nodes.json file and the node's array index to a node error.
+fn locate(err: CoreError, file: &Path, index: usize) -> CoreError {
+    match err {
+        CoreError::InvalidNode { path, message, .. } => CoreError::InvalidNode {
+            file: Some(file.display().to_string()),
+            path: format!("/{index}{path}"),
+            message,
+        },
+        other => other,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn located(err: CoreError) -> (Option<String>, String, String) {
+        match err {
+            CoreError::InvalidNode {
+                file,
+                path,
+                message,
+            } => (file, path, message),
+            other => panic!("expected InvalidNode, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn payload_follows_kind_and_rejects_foreign_fields() {
+        let doc = json!({
+            "id": "x:docs:a", "kind": "doc", "title": "A", "meta": {},
+            "payload": { "runner": "runner.mjs" }
+        });
+        let (file, path, message) = located(Node::try_from(doc).unwrap_err());
+        assert_eq!(file, None);
+        assert_eq!(path, "/payload/runner");
+        assert!(message.contains("unknown field `runner`"), "{message}");
+
+        let bridge = json!({
+            "id": "x:bridge:a", "kind": "bridge", "title": "B", "meta": {},
+            "payload": { "runner": "runner.mjs", "heartbeatIntervalMs": "soon" }
+        });
+        let (_, path, _) = located(Node::try_from(bridge).unwrap_err());
+        assert_eq!(path, "/payload/heartbeatIntervalMs");
+
+        let unknown_kind = json!({
+            "id": "x:a", "kind": "widget", "title": "W", "meta": {}, "payload": {}
+        });
+        let (_, path, _) = located(Node::try_from(unknown_kind).unwrap_err());
+        assert_eq!(path, "/kind");
+
+        // Keys are escaped as RFC 6901 pointer tokens
+        let scoped = json!({
+            "id": "x:setup:a", "kind": "setup", "title": "S", "meta": {},
+            "packageJson": { "dependencies": { "@scope/pkg~next": 5 } },
+            "payload": { "templateRoot": "templates" }
+        });
+        let (_, path, _) = located(Node::try_from(scoped).unwrap_err());
+        assert_eq!(path, "/packageJson/dependencies/@scope~1pkg~0next");
+    }
+
+    #[test]
+    fn payload_serializes_without_tag() {
+        let node = Node::try_from(json!({
+            "id": "x:docs:a", "kind": "doc", "title": "A", "meta": {},
+            "payload": { "contentPath": "a.md" }
+        }))
+        .unwrap();
+        let value = serde_json::to_value(&node).unwrap();
+        assert_eq!(value["payload"], json!({ "contentPath": "a.md" }));
+        let back: Node = serde_json::from_value(value).unwrap();
+        assert!(matches!(back.payload, NodePayload::Doc { .. }));
+    }
+
+    #[test]
+    fn load_reports_file_and_index() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("nodes.json");
+        let nodes = json!([
+            { "id": "x:docs:a", "kind": "doc", "title": "A", "meta": {},
+              "payload": { "contentPath": "a.md" } },
+            { "id": "x:docs:b", "kind": "doc", "title": "B", "meta": {},
+              "payload": { "contentPath": "b.md", "spawn": { "entry": "x.js" } } }
+        ]);
+        std::fs::write(&path, nodes.to_string()).unwrap();
+
+        let err = load_nodes_from_file(&path).unwrap_err();
+        let message = err.to_string();
+        let (file, pointer, _) = located(err);
+        assert_eq!(file.as_deref(), Some(path.display().to_string().as_str()));
+        assert_eq!(pointer, "/1/payload/spawn");
+        assert!(
+            message.contains("nodes.json at /1/payload/spawn"),
+            "{message}"
+        );
+    }
+}
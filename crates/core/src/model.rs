@@ -1,7 +1,15 @@
-use serde::{Deserialize, Serialize};
-use std::collections::BTreeMap;
+use serde::ser::SerializeMap;
+use serde::{Deserialize, Serialize, Serializer};
+use serde_path_to_error::Segment;
+use std::collections::{BTreeMap, HashSet};
+
+use crate::error::{CoreError, CoreResult};
+
+use crate::pack::Pack;
+use crate::workspace::WorkspaceConfig;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(try_from = "RawNode")]
 pub struct Node {
     pub id: String,
     pub kind: NodeKind,
@@ -9,9 +17,52 @@ pub struct Node {
     pub meta: BTreeMap<String, serde_json::Value>,
     #[serde(default)]
     pub prerequisites: Vec<Prerequisite>,
+    /// Nodes that must be applied before this one
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub requires: Vec<String>,
+    /// Nodes worth reading alongside this one; informational only
+    #[serde(rename = "relatedTo", default, skip_serializing_if = "Vec::is_empty")]
+    pub related_to: Vec<String>,
+    /// What writes do with files that already exist; overrides the pack default
+    #[serde(
+        rename = "conflictPolicy",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub conflict_policy: Option<ConflictPolicy>,
+    /// Entries merged into the workspace `package.json` when the node is applied
+    #[serde(
+        rename = "packageJson",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub package_json: Option<PackageJsonSpec>,
     pub payload: NodePayload,
 }
 
+/// `package.json` entries a node needs, name to version range (or command for
+/// `scripts`).
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PackageJsonSpec {
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub dependencies: BTreeMap<String, String>,
+    #[serde(
+        rename = "devDependencies",
+        default,
+        skip_serializing_if = "BTreeMap::is_empty"
+    )]
+    pub dev_dependencies: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub scripts: BTreeMap<String, String>,
+}
+
+impl PackageJsonSpec {
+    pub fn is_empty(&self) -> bool {
+        self.dependencies.is_empty() && self.dev_dependencies.is_empty() && self.scripts.is_empty()
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "lowercase")]
 pub enum NodeKind {
@@ -21,8 +72,128 @@ pub enum NodeKind {
     Bridge,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(untagged)]
+/// What a write does when the destination file exists with different content.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ConflictPolicy {
+    #[default]
+    Overwrite,
+    /// Keep the existing file
+    SkipExisting,
+    /// Move the existing file to `<file>.<unix seconds>.orig`, then write
+    Backup,
+    /// Refuse the whole write when any file conflicts
+    FailOnConflict,
+}
+
+impl ConflictPolicy {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ConflictPolicy::Overwrite => "overwrite",
+            ConflictPolicy::SkipExisting => "skip-existing",
+            ConflictPolicy::Backup => "backup",
+            ConflictPolicy::FailOnConflict => "fail-on-conflict",
+        }
+    }
+}
+
+impl std::str::FromStr for ConflictPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        serde_json::from_value(serde_json::Value::String(s.to_string())).map_err(|_| {
+            format!("expected overwrite, skip-existing, backup or fail-on-conflict, got {s}")
+        })
+    }
+}
+
+/// Typed link between two nodes, emitted alongside the nodes in the graph.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Edge {
+    pub from: String,
+    pub to: String,
+    pub kind: EdgeKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum EdgeKind {
+    /// `to` must be applied before `from`
+    Requires,
+    RelatedTo,
+}
+
+impl NodeKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NodeKind::Doc => "doc",
+            NodeKind::Component => "component",
+            NodeKind::Setup => "setup",
+            NodeKind::Bridge => "bridge",
+        }
+    }
+}
+
+/// Node as written in nodes.json, before its payload is read according to `kind`.
+#[derive(Debug, Clone, Deserialize)]
+pub(crate) struct RawNode {
+    pub id: String,
+    pub kind: NodeKind,
+    pub title: String,
+    pub meta: BTreeMap<String, serde_json::Value>,
+    #[serde(default)]
+    pub prerequisites: Vec<Prerequisite>,
+    #[serde(default)]
+    pub requires: Vec<String>,
+    #[serde(rename = "relatedTo", default)]
+    pub related_to: Vec<String>,
+    #[serde(rename = "conflictPolicy", default)]
+    pub conflict_policy: Option<ConflictPolicy>,
+    #[serde(rename = "packageJson", default)]
+    pub package_json: Option<PackageJsonSpec>,
+    pub payload: serde_json::Value,
+}
+
+impl TryFrom<RawNode> for Node {
+    type Error = CoreError;
+
+    fn try_from(raw: RawNode) -> CoreResult<Self> {
+        let payload = NodePayload::for_kind(&raw.kind, raw.payload)?;
+        Ok(Node {
+            id: raw.id,
+            kind: raw.kind,
+            title: raw.title,
+            meta: raw.meta,
+            prerequisites: raw.prerequisites,
+            requires: raw.requires,
+            related_to: raw.related_to,
+            conflict_policy: raw.conflict_policy,
+            package_json: raw.package_json,
+            payload,
+        })
+    }
+}
+
+/// What one component needs besides its own files (`payload.components.<Name>`).
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ComponentSpec {
+    /// Components of the same node installed along with this one
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub requires: Vec<String>,
+    /// npm packages the component imports, name to version range
+    #[serde(
+        rename = "npmDependencies",
+        default,
+        skip_serializing_if = "BTreeMap::is_empty"
+    )]
+    pub npm_dependencies: BTreeMap<String, String>,
+}
+
+/// Payload of a node. The variant is chosen by `Node.kind`, never guessed from the
+/// payload shape, and unknown payload fields are rejected. Serialized without a tag.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "lowercase", deny_unknown_fields)]
 pub enum NodePayload {
     Doc {
         #[serde(rename = "contentPath")]
@@ -31,15 +202,27 @@ pub enum NodePayload {
     Component {
         #[serde(rename = "sourceRoot")]
         source_root: String,
+        /// Per-component dependencies, keyed by component name
+        #[serde(default)]
+        components: BTreeMap<String, ComponentSpec>,
+        /// Framework-specific source roots (`react`, `vue`, `svelte`, ...); a
+        /// component missing from the chosen one comes from `sourceRoot`
+        #[serde(default)]
+        variants: BTreeMap<String, String>,
+        /// Gitignore-style patterns of source files never copied
+        #[serde(default)]
+        exclude: Vec<String>,
     },
     Setup {
         #[serde(rename = "templateRoot")]
         template_root: String,
         #[serde(default)]
         commands: Option<Vec<String>>,
+        #[serde(default)]
+        exclude: Vec<String>,
     },
     Bridge {
-        #[serde(rename = "templateRoot")]
+        #[serde(rename = "templateRoot", default)]
         template_root: Option<String>,
         #[serde(rename = "runner", default)]
         runner: Option<String>,
@@ -51,9 +234,159 @@ pub enum NodePayload {
         logs_path: Option<String>,
         #[serde(default, rename = "heartbeatIntervalMs")]
         heartbeat_interval_ms: Option<u64>,
+        #[serde(default)]
+        exclude: Vec<String>,
     },
 }
 
+impl NodePayload {
+    /// Read a payload as the variant matching `kind`. Errors carry the JSON pointer
+    /// of the failing field, starting at `/payload`.
+    pub fn for_kind(kind: &NodeKind, payload: serde_json::Value) -> CoreResult<Self> {
+        let mut tagged = serde_json::Map::new();
+        tagged.insert(kind.as_str().to_string(), payload);
+        serde_path_to_error::deserialize(serde_json::Value::Object(tagged)).map_err(|err| {
+            // The first segment is the variant name we inserted above
+            let pointer = json_pointer(err.path().iter().skip(1));
+            CoreError::InvalidNode {
+                file: None,
+                path: format!("/payload{pointer}"),
+                message: format!("{} payload: {}", kind.as_str(), err.inner()),
+            }
+        })
+    }
+
+    /// `exclude` patterns of payloads that copy files.
+    pub fn exclude(&self) -> &[String] {
+        match self {
+            NodePayload::Doc { .. } => &[],
+            NodePayload::Component { exclude, .. }
+            | NodePayload::Setup { exclude, .. }
+            | NodePayload::Bridge { exclude, .. } => exclude,
+        }
+    }
+
+    /// Filesystem paths referenced by the payload, keyed by their JSON pointer within the node.
+    pub fn paths(&self) -> Vec<(String, &str)> {
+        match self {
+            NodePayload::Doc { content_path } => {
+                vec![("/payload/contentPath".into(), content_path)]
+            }
+            NodePayload::Component {
+                source_root,
+                variants,
+                ..
+            } => std::iter::once(("/payload/sourceRoot".into(), source_root.as_str()))
+                .chain(variants.iter().map(|(name, root)| {
+                    (
+                        format!("/payload/variants/{}", pointer_token(name)),
+                        root.as_str(),
+                    )
+                }))
+                .collect(),
+            NodePayload::Setup { template_root, .. } => {
+                vec![("/payload/templateRoot".into(), template_root)]
+            }
+            NodePayload::Bridge {
+                template_root,
+                runner,
+                config_template,
+                spawn,
+                logs_path,
+                ..
+            } => [
+                ("/payload/templateRoot", template_root.as_deref()),
+                ("/payload/runner", runner.as_deref()),
+                ("/payload/configTemplate", config_template.as_deref()),
+                (
+                    "/payload/spawn/entry",
+                    spawn.as_ref().map(|s| s.entry.as_str()),
+                ),
+                ("/payload/logsPath", logs_path.as_deref()),
+            ]
+            .into_iter()
+            .filter_map(|(pointer, path)| path.map(|p| (pointer.to_string(), p)))
+            .collect(),
+        }
+    }
+}
+
+/// `key` as one JSON pointer token: `~` and `/` escaped as RFC 6901 requires.
+pub fn pointer_token(key: &str) -> String {
+    key.replace('~', "~0").replace('/', "~1")
+}
+
+/// Render serde_path_to_error segments as a JSON pointer (`/payload/spawn/args/0`).
+pub(crate) fn json_pointer<'a>(segments: impl Iterator<Item = &'a Segment>) -> String {
+    segments
+        .map(|segment| match segment {
+            Segment::Seq { index } => format!("/{index}"),
+            Segment::Map { key } => format!("/{}", pointer_token(key)),
+            Segment::Enum { variant } => format!("/{}", pointer_token(variant)),
+            Segment::Unknown => "/?".to_string(),
+        })
+        .collect()
+}
+
+impl Serialize for NodePayload {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(None)?;
+        match self {
+            NodePayload::Doc { content_path } => {
+                map.serialize_entry("contentPath", content_path)?;
+            }
+            NodePayload::Component {
+                source_root,
+                components,
+                variants,
+                exclude,
+            } => {
+                map.serialize_entry("sourceRoot", source_root)?;
+                if !components.is_empty() {
+                    map.serialize_entry("components", components)?;
+                }
+                if !variants.is_empty() {
+                    map.serialize_entry("variants", variants)?;
+                }
+                if !exclude.is_empty() {
+                    map.serialize_entry("exclude", exclude)?;
+                }
+            }
+            NodePayload::Setup {
+                template_root,
+                commands,
+                exclude,
+            } => {
+                map.serialize_entry("templateRoot", template_root)?;
+                map.serialize_entry("commands", commands)?;
+                if !exclude.is_empty() {
+                    map.serialize_entry("exclude", exclude)?;
+                }
+            }
+            NodePayload::Bridge {
+                template_root,
+                runner,
+                config_template,
+                spawn,
+                logs_path,
+                heartbeat_interval_ms,
+                exclude,
+            } => {
+                map.serialize_entry("templateRoot", template_root)?;
+                map.serialize_entry("runner", runner)?;
+                map.serialize_entry("configTemplate", config_template)?;
+                map.serialize_entry("spawn", spawn)?;
+                map.serialize_entry("logsPath", logs_path)?;
+                map.serialize_entry("heartbeatIntervalMs", heartbeat_interval_ms)?;
+                if !exclude.is_empty() {
+                    map.serialize_entry("exclude", exclude)?;
+                }
+            }
+        }
+        map.end()
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BridgeSpawnDescriptor {
     /// Node runner entry file relative to packs root
@@ -88,6 +421,24 @@ pub struct GraphPackage {
     pub executable: String,
     /// High-level semantics to avoid guessing in agents
     pub semantics: Semantics,
+    /// Version of the engine that produced this graph
+    #[serde(default)]
+    pub engine_version: String,
+    /// Packs loaded into this graph, with their manifests
+    #[serde(default)]
+    pub packs: Vec<Pack>,
+    /// `requires`/`relatedTo` edges between the nodes above
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub edges: Vec<Edge>,
+}
+
+impl GraphPackage {
+    /// Keep only the nodes in `ids`, and the edges between them.
+    pub fn retain_nodes(&mut self, ids: &HashSet<&str>) {
+        self.nodes.retain(|n| ids.contains(n.id.as_str()));
+        self.edges
+            .retain(|e| ids.contains(e.from.as_str()) && ids.contains(e.to.as_str()));
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -97,6 +448,12 @@ pub struct CommandShapes {
     pub ui: UiCommandShape,
     pub setup: SetupCommandShape,
     pub bridge: Option<BridgeCommandShape>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub graph: Option<GraphCommandShape>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub undo: Option<UndoCommandShape>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pack: Option<PackCommandShape>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -107,6 +464,14 @@ pub struct DocsCommandShape {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UiCommandShape {
     pub template: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status_template: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub update_template: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub diff_template: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub uninstall_template: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -119,6 +484,21 @@ pub struct SetupCommandShape {
     pub template: String,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GraphCommandShape {
+    pub query_template: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PackCommandShape {
+    pub lint_template: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UndoCommandShape {
+    pub template: String,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BridgeCommandShape {
     pub scaffold_template: String,
@@ -134,13 +514,44 @@ pub struct BridgeCommandShape {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Semantics {
     /// Where write operations target by default
-    pub writes_to: String, // e.g., "cwd"
+    pub writes_to: String, // e.g., "cwd/entity-auth"
+    /// Workspace-relative install root per loaded product
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub install_roots: BTreeMap<String, String>,
     /// Whether component installs overwrite existing files
     pub overwrite_on_write: bool,
+    /// Default conflict policy for writes; nodes, packs and commands may override it
+    #[serde(default)]
+    pub conflict_policy: ConflictPolicy,
     /// Supported platforms (informational)
     pub platforms: Platforms,
 }
 
+impl Semantics {
+    /// `cwd/<root>` when every loaded product shares one install root, `cwd` otherwise.
+    pub fn writes_to_for(install_roots: &BTreeMap<String, String>) -> String {
+        let mut roots = install_roots.values();
+        match roots.next() {
+            Some(first) if roots.all(|r| r == first) => format!("cwd/{first}"),
+            _ => "cwd".to_string(),
+        }
+    }
+
+    /// Apply `entitycli.json` install root overrides for the workspace the graph is emitted in.
+    pub fn apply_workspace_config(&mut self, config: &WorkspaceConfig) {
+        for (product, root) in self.install_roots.iter_mut() {
+            if let Some(over) = config.install_root_for(product) {
+                *root = over.to_string();
+            }
+        }
+        self.writes_to = Self::writes_to_for(&self.install_roots);
+        if let Some(policy) = config.conflict_policy {
+            self.conflict_policy = policy;
+            self.overwrite_on_write = policy == ConflictPolicy::Overwrite;
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Platforms {
     pub os: Vec<String>,
@@ -0,0 +1,266 @@
+use std::collections::BTreeMap;
+use std::path::{Component, Path};
+
+use semver::{Version, VersionReq};
+use serde::{Deserialize, Serialize};
+
+use crate::error::{CoreError, CoreResult};
+use crate::model::ConflictPolicy;
+
+/// Install root used for nodes that were not loaded through a pack.
+pub const DEFAULT_INSTALL_ROOT: &str = "entity-auth";
+
+/// File name of the optional per-pack manifest.
+pub const PACK_MANIFEST_FILE: &str = "pack.json";
+
+/// Key under `engines` that constrains the entity-cli version.
+pub const ENGINE_KEY: &str = "entity-cli";
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PackManifest {
+    /// Must match the pack directory name when given
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Semver version of the pack content
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Supported engine versions, e.g. `{ "entity-cli": ">=0.0.13, <0.1.0" }`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub engines: BTreeMap<String, String>,
+    /// Workspace-relative directory that write operations land in
+    #[serde(
+        rename = "installRoot",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub install_root: Option<String>,
+    /// Default conflict policy for the pack's nodes
+    #[serde(
+        rename = "conflictPolicy",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub conflict_policy: Option<ConflictPolicy>,
+    /// Template variable defaults, overridden by selections with the same key
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub variables: BTreeMap<String, String>,
+}
+
+impl PackManifest {
+    /// Load `pack.json` from a pack directory; a missing file yields the defaults.
+    pub fn load(pack_dir: &Path) -> CoreResult<Self> {
+        let path = pack_dir.join(PACK_MANIFEST_FILE);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = std::fs::read_to_string(&path)?;
+        let manifest: PackManifest = serde_json::from_str(&content).map_err(|err| {
+            CoreError::InvalidDescriptor(format!("invalid pack manifest {}: {err}", path.display()))
+        })?;
+        if let Some(root) = &manifest.install_root {
+            validate_install_root(root)?;
+        }
+        if let Some(version) = &manifest.version {
+            Version::parse(version).map_err(|err| {
+                CoreError::InvalidDescriptor(format!(
+                    "invalid pack version {version} in {}: {err}",
+                    path.display()
+                ))
+            })?;
+        }
+        if let Some(range) = manifest.engines.get(ENGINE_KEY) {
+            parse_version_req(range).map_err(|err| {
+                CoreError::InvalidDescriptor(format!(
+                    "invalid engine range {range} in {}: {err}",
+                    path.display()
+                ))
+            })?;
+        }
+        Ok(manifest)
+    }
+
+    /// Reject a declared `name` other than the pack directory name `pack`.
+    pub fn check_name(&self, pack: &str) -> CoreResult<()> {
+        match &self.name {
+            Some(name) if name != pack => Err(CoreError::InvalidDescriptor(format!(
+                "pack manifest name {name} does not match the pack directory {pack}"
+            ))),
+            _ => Ok(()),
+        }
+    }
+
+    /// Reject the pack when its `engines.entity-cli` range excludes `engine_version`.
+    pub fn check_engine(&self, pack: &str, engine_version: &str) -> CoreResult<()> {
+        let Some(range) = self.engines.get(ENGINE_KEY) else {
+            return Ok(());
+        };
+        let req = parse_version_req(range)
+            .map_err(|err| CoreError::InvalidDescriptor(format!("invalid engine range: {err}")))?;
+        let version = Version::parse(engine_version).map_err(|err| {
+            CoreError::InvalidDescriptor(format!("invalid engine version: {err}"))
+        })?;
+        if !req.matches(&version) {
+            return Err(CoreError::IncompatiblePack {
+                pack: pack.to_string(),
+                required: range.clone(),
+                engine: engine_version.to_string(),
+            });
+        }
+        Ok(())
+    }
+}
+
+/// A semver range as npm reads it: comparator sets joined by `||`, matching when
+/// any of them does.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VersionRange {
+    pub alternatives: Vec<VersionReq>,
+}
+
+impl VersionRange {
+    pub fn matches(&self, version: &Version) -> bool {
+        self.alternatives.iter().any(|req| req.matches(version))
+    }
+}
+
+/// Parse a semver range, accepting Cargo (`>=1.0, <2`) and npm syntax: space
+/// separated comparators, `||`, hyphen ranges (`1.2.3 - 2`), x-ranges (`1.x`)
+/// and bare versions, which pin exactly as in npm (`1.2.3` is `=1.2.3`, `1.2`
+/// is `1.2.x`).
+pub fn parse_version_req(range: &str) -> Result<VersionRange, semver::Error> {
+    let alternatives = range
+        .split("||")
+        .map(parse_comparator_set)
+        .collect::<Result<_, _>>()?;
+    Ok(VersionRange { alternatives })
+}
+
+fn parse_comparator_set(set: &str) -> Result<VersionReq, semver::Error> {
+    let set = set.trim();
+    if set.contains(',') {
+        return VersionReq::parse(set);
+    }
+    let tokens: Vec<&str> = set.split_whitespace().collect();
+    if let [from, "-", to] = tokens.as_slice() {
+        return VersionReq::parse(&format!(">={}, <={}", partial(from), partial(to)));
+    }
+    let mut comparators: Vec<String> = Vec::new();
+    let mut pending_op = String::new();
+    for token in tokens {
+        if token.chars().all(|c| "<>=~^".contains(c)) {
+            pending_op.push_str(token);
+            continue;
+        }
+        let split = token
+            .find(|c: char| !"<>=~^".contains(c))
+            .unwrap_or(token.len());
+        let op = format!("{pending_op}{}", &token[..split]);
+        pending_op.clear();
+        let version = partial(&token[split..]);
+        if version.is_empty() {
+            continue;
+        }
+        // Bare versions pin (with missing parts as wildcards) instead of Cargo's caret
+        let op = if op.is_empty() { "=".to_string() } else { op };
+        comparators.push(format!("{op}{version}"));
+    }
+    if comparators.is_empty() {
+        return VersionReq::parse("*");
+    }
+    VersionReq::parse(&comparators.join(", "))
+}
+
+/// `version` without a `v` prefix and with x-range parts (`x`, `X`, `*`) dropped,
+/// so `1.2.x` becomes the partial `1.2`; empty for a full wildcard.
+fn partial(version: &str) -> String {
+    let version = version.strip_prefix('v').unwrap_or(version);
+    version
+        .split('.')
+        .take_while(|part| !matches!(*part, "x" | "X" | "*"))
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Pack {
+    /// Pack directory name, which is also the product name on the command line
+    pub name: String,
+    /// Absolute path of the pack directory
+    pub root: String,
+    pub manifest: PackManifest,
+}
+
+impl Pack {
+    pub fn new(name: impl Into<String>, root: &Path, manifest: PackManifest) -> Self {
+        Self {
+            name: name.into(),
+            root: root.display().to_string(),
+            manifest,
+        }
+    }
+
+    /// Declared install root, falling back to the pack name.
+    pub fn install_root(&self) -> &str {
+        self.manifest
+            .install_root
+            .as_deref()
+            .unwrap_or(self.name.as_str())
+    }
+}
+
+/// Install roots must stay inside the workspace: relative, non-empty and without `..`.
+pub fn validate_install_root(root: &str) -> CoreResult<()> {
+    let path = Path::new(root);
+    let escapes = path
+        .components()
+        .any(|c| !matches!(c, Component::Normal(_) | Component::CurDir));
+    if root.trim().is_empty() || escapes {
+        return Err(CoreError::InvalidDescriptor(format!(
+            "install root must be a relative path inside the workspace: {root}"
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn allows(range: &str, version: &str) -> bool {
+        parse_version_req(range)
+            .unwrap()
+            .matches(&Version::parse(version).unwrap())
+    }
+
+    #[test]
+    fn parses_npm_ranges() {
+        // Space separated comparators, with and without a gap after the operator
+        assert!(allows(">=1.0.0 <2.0.0", "1.9.0"));
+        assert!(!allows(">=1.0.0 <2.0.0", "2.0.0"));
+        assert!(allows(">= 1.2 < 2", "1.2.0"));
+        // Cargo separators still work
+        assert!(allows(">=0.0.13, <0.1.0", "0.0.13"));
+        // Alternatives
+        assert!(allows("^1 || ^2", "2.3.0"));
+        assert!(!allows("^1 || ^2", "3.0.0"));
+        // Hyphen ranges include a partial upper bound as a whole
+        assert!(allows("1.2.3 - 2.3", "2.3.9"));
+        assert!(!allows("1.2.3 - 2.3", "2.4.0"));
+        assert!(!allows("1.2.3 - 2.3", "1.2.2"));
+        // X-ranges and bare versions
+        assert!(allows("1.x", "1.9.9"));
+        assert!(!allows("1.x", "2.0.0"));
+        assert!(allows("1.2.*", "1.2.7"));
+        assert!(!allows("1.2", "1.3.0"));
+        assert!(allows("*", "9.0.0"));
+        assert!(allows("", "9.0.0"));
+        assert!(allows("1.2.3", "1.2.3"));
+        assert!(!allows("1.2.3", "1.2.4"));
+        assert!(allows("v1.2.3", "1.2.3"));
+
+        assert!(parse_version_req("latest").is_err());
+        assert!(parse_version_req("workspace:*").is_err());
+    }
+}
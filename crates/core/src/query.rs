@@ -0,0 +1,58 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::model::{Node, NodeKind};
+
+/// Filters for narrowing the graph; every filter that is set must match.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NodeQuery {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kind: Option<NodeKind>,
+    /// Entry of `meta.tags`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tag: Option<String>,
+    /// Value of `meta.section`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub section: Option<String>,
+    #[serde(rename = "idPrefix", default, skip_serializing_if = "Option::is_none")]
+    pub id_prefix: Option<String>,
+    /// `meta` keys that must equal the given JSON values
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub meta: BTreeMap<String, Value>,
+}
+
+impl NodeQuery {
+    /// Whether a node passes the filters. `Registry::query` answers `tag` and
+    /// `section` from its indexes first; they are checked here as well so the
+    /// method stands on its own.
+    pub fn matches(&self, node: &Node) -> bool {
+        if self.kind.as_ref().is_some_and(|k| *k != node.kind) {
+            return false;
+        }
+        if let Some(prefix) = &self.id_prefix {
+            if !node.id.starts_with(prefix.as_str()) {
+                return false;
+            }
+        }
+        if let Some(tag) = &self.tag {
+            let tagged = node
+                .meta
+                .get("tags")
+                .and_then(|v| v.as_array())
+                .is_some_and(|tags| tags.iter().any(|t| t.as_str() == Some(tag.as_str())));
+            if !tagged {
+                return false;
+            }
+        }
+        if let Some(section) = &self.section {
+            if node.meta.get("section").and_then(|v| v.as_str()) != Some(section.as_str()) {
+                return false;
+            }
+        }
+        self.meta
+            .iter()
+            .all(|(key, value)| node.meta.get(key) == Some(value))
+    }
+}
@@ -1,9 +1,32 @@
 use std::collections::{HashMap, HashSet};
+use std::path::Path;
 
 use indexmap::IndexMap;
+use serde::Serialize;
 
 use crate::error::{CoreError, CoreResult};
-use crate::model::{Node, NodeKind, NodePayload};
+use crate::model::{pointer_token, ConflictPolicy, Edge, EdgeKind, Node, NodeKind, NodePayload};
+use crate::pack::{Pack, DEFAULT_INSTALL_ROOT};
+use crate::query::NodeQuery;
+use crate::workspace::WorkspaceConfig;
+
+/// A problem with a single node descriptor, located by a JSON pointer into the node.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DescriptorProblem {
+    pub code: &'static str,
+    pub path: String,
+    pub message: String,
+}
+
+impl DescriptorProblem {
+    fn new(code: &'static str, path: impl Into<String>, message: String) -> Self {
+        Self {
+            code,
+            path: path.into(),
+            message,
+        }
+    }
+}
 
 #[derive(Debug, Default, Clone)]
 pub struct Registry {
@@ -13,6 +36,8 @@ pub struct Registry {
     by_tag: HashMap<String, Vec<String>>,     // tag -> node ids
     by_section: HashMap<String, Vec<String>>, // section -> node ids
     bridges: Vec<String>,
+    packs: IndexMap<String, Pack>,
+    node_packs: HashMap<String, String>, // node id -> pack name
 }
 
 impl Registry {
@@ -32,41 +57,110 @@ impl Registry {
             )));
         }
 
+        if let Some(problem) = Self::node_problems(&node).into_iter().next() {
+            return Err(CoreError::InvalidDescriptor(problem.message));
+        }
+
+        // Index by tag and section if present
+        if let Some(tags) = node.meta.get("tags").and_then(|v| v.as_array()) {
+            for t in tags.iter().filter_map(|v| v.as_str()) {
+                self.by_tag
+                    .entry(t.to_string())
+                    .or_default()
+                    .push(node.id.clone());
+            }
+        }
+        if let Some(section) = node.meta.get("section").and_then(|v| v.as_str()) {
+            self.by_section
+                .entry(section.to_string())
+                .or_default()
+                .push(node.id.clone());
+        }
+
+        match node.kind {
+            NodeKind::Doc => self.docs.push(node.id.clone()),
+            NodeKind::Component => self.components.push(node.id.clone()),
+            NodeKind::Setup => { /* currently not indexed; may add later */ }
+            NodeKind::Bridge => self.bridges.push(node.id.clone()),
+        }
+
+        self.nodes.insert(node.id.clone(), node);
+        Ok(())
+    }
+
+    /// Every problem with a node that can be detected without the rest of the graph
+    /// (duplicate prerequisite keys, missing payload paths, invalid bridge settings).
+    pub fn node_problems(node: &Node) -> Vec<DescriptorProblem> {
+        let mut problems = Vec::new();
+
         // Validate prerequisites keys uniqueness
         let mut keys = HashSet::new();
-        for prereq in &node.prerequisites {
+        for (i, prereq) in node.prerequisites.iter().enumerate() {
             if !keys.insert(prereq.key.clone()) {
-                return Err(CoreError::InvalidDescriptor(format!(
-                    "duplicate prerequisite key {} on node {}",
-                    prereq.key, node.id
-                )));
+                problems.push(DescriptorProblem::new(
+                    "DUPLICATE_PREREQUISITE",
+                    format!("/prerequisites/{i}/key"),
+                    format!(
+                        "duplicate prerequisite key {} on node {}",
+                        prereq.key, node.id
+                    ),
+                ));
             }
         }
 
         // Validate payload paths exist
+        let missing = |path: &str| !Path::new(path).exists();
         match &node.payload {
             NodePayload::Doc { content_path } => {
-                if !std::path::Path::new(content_path).exists() {
-                    return Err(CoreError::InvalidDescriptor(format!(
-                        "doc content path not found for node {}: {}",
-                        node.id, content_path
-                    )));
+                if missing(content_path) {
+                    problems.push(DescriptorProblem::new(
+                        "MISSING_PATH",
+                        "/payload/contentPath",
+                        format!(
+                            "doc content path not found for node {}: {}",
+                            node.id, content_path
+                        ),
+                    ));
                 }
             }
-            NodePayload::Component { source_root } => {
-                if !std::path::Path::new(source_root).exists() {
-                    return Err(CoreError::InvalidDescriptor(format!(
-                        "component source root not found for node {}: {}",
-                        node.id, source_root
-                    )));
+            NodePayload::Component {
+                source_root,
+                variants,
+                ..
+            } => {
+                if missing(source_root) {
+                    problems.push(DescriptorProblem::new(
+                        "MISSING_PATH",
+                        "/payload/sourceRoot",
+                        format!(
+                            "component source root not found for node {}: {}",
+                            node.id, source_root
+                        ),
+                    ));
+                }
+                for (framework, root) in variants {
+                    if missing(root) {
+                        problems.push(DescriptorProblem::new(
+                            "MISSING_PATH",
+                            format!("/payload/variants/{}", pointer_token(framework)),
+                            format!(
+                                "{framework} variant root not found for node {}: {}",
+                                node.id, root
+                            ),
+                        ));
+                    }
                 }
             }
             NodePayload::Setup { template_root, .. } => {
-                if !std::path::Path::new(template_root).exists() {
-                    return Err(CoreError::InvalidDescriptor(format!(
-                        "setup template root not found for node {}: {}",
-                        node.id, template_root
-                    )));
+                if missing(template_root) {
+                    problems.push(DescriptorProblem::new(
+                        "MISSING_PATH",
+                        "/payload/templateRoot",
+                        format!(
+                            "setup template root not found for node {}: {}",
+                            node.id, template_root
+                        ),
+                    ));
                 }
             }
             NodePayload::Bridge {
@@ -76,89 +170,196 @@ impl Registry {
                 spawn,
                 logs_path,
                 heartbeat_interval_ms,
+                ..
             } => {
-                if let Some(root) = template_root {
-                    if !std::path::Path::new(root).exists() {
-                        return Err(CoreError::InvalidDescriptor(format!(
+                if let Some(root) = template_root.as_deref().filter(|p| missing(p)) {
+                    problems.push(DescriptorProblem::new(
+                        "MISSING_PATH",
+                        "/payload/templateRoot",
+                        format!(
                             "bridge template root not found for node {}: {}",
                             node.id, root
-                        )));
-                    }
+                        ),
+                    ));
                 }
-                if let Some(path) = runner {
-                    if !std::path::Path::new(path).exists() {
-                        return Err(CoreError::InvalidDescriptor(format!(
-                            "bridge runner not found for node {}: {}",
-                            node.id, path
-                        )));
-                    }
+                if let Some(path) = runner.as_deref().filter(|p| missing(p)) {
+                    problems.push(DescriptorProblem::new(
+                        "MISSING_PATH",
+                        "/payload/runner",
+                        format!("bridge runner not found for node {}: {}", node.id, path),
+                    ));
                 }
-                if let Some(path) = config_template {
-                    if !std::path::Path::new(path).exists() {
-                        return Err(CoreError::InvalidDescriptor(format!(
+                if let Some(path) = config_template.as_deref().filter(|p| missing(p)) {
+                    problems.push(DescriptorProblem::new(
+                        "MISSING_PATH",
+                        "/payload/configTemplate",
+                        format!(
                             "bridge config template not found for node {}: {}",
                             node.id, path
-                        )));
-                    }
+                        ),
+                    ));
                 }
-                if let Some(descriptor) = spawn {
-                    if !std::path::Path::new(&descriptor.entry).exists() {
-                        return Err(CoreError::InvalidDescriptor(format!(
+                if let Some(descriptor) = spawn.as_ref().filter(|d| missing(&d.entry)) {
+                    problems.push(DescriptorProblem::new(
+                        "MISSING_PATH",
+                        "/payload/spawn/entry",
+                        format!(
                             "bridge spawn entry not found for node {}: {}",
                             node.id, descriptor.entry
-                        )));
-                    }
+                        ),
+                    ));
                 }
                 if let Some(path) = logs_path {
-                    let parent = std::path::Path::new(path)
-                        .parent()
-                        .map(|p| p.exists())
-                        .unwrap_or(true);
+                    let parent = Path::new(path).parent().map(|p| p.exists()).unwrap_or(true);
                     if !parent {
-                        return Err(CoreError::InvalidDescriptor(format!(
-                            "bridge logs path parent missing for node {}: {}",
-                            node.id, path
-                        )));
+                        problems.push(DescriptorProblem::new(
+                            "MISSING_PATH",
+                            "/payload/logsPath",
+                            format!(
+                                "bridge logs path parent missing for node {}: {}",
+                                node.id, path
+                            ),
+                        ));
                     }
                 }
-                if let Some(interval) = heartbeat_interval_ms {
-                    if *interval == 0 {
-                        return Err(CoreError::InvalidDescriptor(format!(
-                            "bridge heartbeat interval must be > 0 for node {}",
-                            node.id
-                        )));
-                    }
+                if *heartbeat_interval_ms == Some(0) {
+                    problems.push(DescriptorProblem::new(
+                        "INVALID_HEARTBEAT",
+                        "/payload/heartbeatIntervalMs",
+                        format!("bridge heartbeat interval must be > 0 for node {}", node.id),
+                    ));
                 }
             }
         }
 
-        // Index by tag and section if present
-        if let Some(tags) = node.meta.get("tags").and_then(|v| v.as_array()) {
-            for t in tags.iter().filter_map(|v| v.as_str()) {
-                self.by_tag
-                    .entry(t.to_string())
-                    .or_default()
-                    .push(node.id.clone());
+        problems
+    }
+
+    /// Every `requires`/`relatedTo` edge, in node declaration order.
+    pub fn edges(&self) -> Vec<Edge> {
+        let mut edges = Vec::new();
+        for node in self.nodes.values() {
+            for (targets, kind) in [
+                (&node.requires, EdgeKind::Requires),
+                (&node.related_to, EdgeKind::RelatedTo),
+            ] {
+                edges.extend(targets.iter().map(|to| Edge {
+                    from: node.id.clone(),
+                    to: to.clone(),
+                    kind,
+                }));
             }
         }
-        if let Some(section) = node.meta.get("section").and_then(|v| v.as_str()) {
-            self.by_section
-                .entry(section.to_string())
-                .or_default()
-                .push(node.id.clone());
+        edges
+    }
+
+    /// Check that every edge points at a loaded node and that `requires` has no cycle.
+    /// Runs once all nodes are inserted, since edges may point across nodes.json files.
+    pub fn validate_edges(&self) -> CoreResult<()> {
+        for edge in self.edges() {
+            if !self.nodes.contains_key(&edge.to) {
+                let label = match edge.kind {
+                    EdgeKind::Requires => "requires",
+                    EdgeKind::RelatedTo => "relatedTo",
+                };
+                return Err(CoreError::InvalidDescriptor(format!(
+                    "node {} {} unknown node {}",
+                    edge.from, label, edge.to
+                )));
+            }
+        }
+        let requires: IndexMap<&str, &[String]> = self
+            .nodes
+            .values()
+            .map(|n| (n.id.as_str(), n.requires.as_slice()))
+            .collect();
+        match find_cycle(&requires) {
+            Some(cycle) => Err(CoreError::DependencyCycle(cycle)),
+            None => Ok(()),
         }
+    }
 
-        match node.kind {
-            NodeKind::Doc => self.docs.push(node.id.clone()),
-            NodeKind::Component => self.components.push(node.id.clone()),
-            NodeKind::Setup => { /* currently not indexed; may add later */ }
-            NodeKind::Bridge => self.bridges.push(node.id.clone()),
+    /// `node_id` and everything it transitively requires, dependencies first.
+    pub fn dependency_order(&self, node_id: &str) -> CoreResult<Vec<&Node>> {
+        fn visit<'r>(
+            registry: &'r Registry,
+            id: &str,
+            path: &mut Vec<String>,
+            order: &mut Vec<&'r Node>,
+        ) -> CoreResult<()> {
+            if order.iter().any(|n| n.id == id) {
+                return Ok(());
+            }
+            if let Some(start) = path.iter().position(|p| p == id) {
+                let mut cycle = path[start..].to_vec();
+                cycle.push(id.to_string());
+                return Err(CoreError::DependencyCycle(cycle));
+            }
+            let node = registry.get(id)?;
+            path.push(id.to_string());
+            for dep in &node.requires {
+                visit(registry, dep, path, order)?;
+            }
+            path.pop();
+            order.push(node);
+            Ok(())
         }
 
-        self.nodes.insert(node.id.clone(), node);
+        let mut order = Vec::new();
+        visit(self, node_id, &mut Vec::new(), &mut order)?;
+        Ok(order)
+    }
+
+    pub fn add_pack(&mut self, pack: Pack) {
+        self.packs.insert(pack.name.clone(), pack);
+    }
+
+    /// Insert a node and attribute it to a pack previously registered with `add_pack`.
+    pub fn insert_in_pack(&mut self, node: Node, pack: &str) -> CoreResult<()> {
+        if !self.packs.contains_key(pack) {
+            return Err(CoreError::InvalidDescriptor(format!(
+                "unknown pack {} for node {}",
+                pack, node.id
+            )));
+        }
+        let id = node.id.clone();
+        self.insert(node)?;
+        self.node_packs.insert(id, pack.to_string());
         Ok(())
     }
 
+    pub fn packs(&self) -> impl Iterator<Item = &Pack> {
+        self.packs.values()
+    }
+
+    pub fn pack_for(&self, node_id: &str) -> Option<&Pack> {
+        self.node_packs
+            .get(node_id)
+            .and_then(|name| self.packs.get(name))
+    }
+
+    /// Conflict policy for a node's writes: `entitycli.json` wins over the node,
+    /// which wins over its pack manifest.
+    pub fn conflict_policy(&self, node_id: &str, config: &WorkspaceConfig) -> ConflictPolicy {
+        config
+            .conflict_policy
+            .or_else(|| self.nodes.get(node_id)?.conflict_policy)
+            .or_else(|| self.pack_for(node_id)?.manifest.conflict_policy)
+            .unwrap_or_default()
+    }
+
+    /// Workspace-relative install root for a node: the workspace override from
+    /// `entitycli.json` wins over the pack declaration.
+    pub fn install_root(&self, node_id: &str, config: &WorkspaceConfig) -> String {
+        match self.pack_for(node_id) {
+            Some(pack) => config
+                .install_root_for(&pack.name)
+                .unwrap_or(pack.install_root())
+                .to_string(),
+            None => DEFAULT_INSTALL_ROOT.to_string(),
+        }
+    }
+
     pub fn get(&self, id: &str) -> CoreResult<&Node> {
         self.nodes
             .get(id)
@@ -184,4 +385,71 @@ impl Registry {
     pub fn nodes_by_section(&self, section: &str) -> Option<&[String]> {
         self.by_section.get(section).map(|v| v.as_slice())
     }
+
+    /// Nodes matching every filter of `query`, in declaration order. Tag and
+    /// section filters are answered from the indexes before scanning.
+    pub fn query(&self, query: &NodeQuery) -> Vec<&Node> {
+        let indexed = [
+            query
+                .tag
+                .as_deref()
+                .map(|t| self.nodes_by_tag(t).unwrap_or_default()),
+            query
+                .section
+                .as_deref()
+                .map(|s| self.nodes_by_section(s).unwrap_or_default()),
+        ];
+        let candidates: Option<HashSet<&str>> = indexed
+            .into_iter()
+            .flatten()
+            .map(|ids| ids.iter().map(|id| id.as_str()).collect::<HashSet<_>>())
+            .reduce(|a, b| a.intersection(&b).copied().collect());
+
+        self.nodes
+            .values()
+            .filter(|n| {
+                candidates
+                    .as_ref()
+                    .is_none_or(|ids| ids.contains(n.id.as_str()))
+            })
+            .filter(|n| query.matches(n))
+            .collect()
+    }
+}
+
+/// First cycle found in a `requires` graph (id -> required ids), closed with its
+/// starting id, e.g. `[a, b, a]`. Ids missing from the graph are treated as leaves.
+pub fn find_cycle(requires: &IndexMap<&str, &[String]>) -> Option<Vec<String>> {
+    fn visit<'a>(
+        id: &'a str,
+        requires: &IndexMap<&str, &'a [String]>,
+        done: &mut HashSet<&'a str>,
+        path: &mut Vec<&'a str>,
+    ) -> Option<Vec<String>> {
+        if done.contains(id) {
+            return None;
+        }
+        if let Some(start) = path.iter().position(|p| *p == id) {
+            let mut cycle: Vec<String> = path[start..].iter().map(|s| s.to_string()).collect();
+            cycle.push(id.to_string());
+            return Some(cycle);
+        }
+        path.push(id);
+        for dep in requires.get(id).copied().unwrap_or_default() {
+            if let Some(cycle) = visit(dep, requires, done, path) {
+                return Some(cycle);
+            }
+        }
+        path.pop();
+        done.insert(id);
+        None
+    }
+
+    let mut done = HashSet::new();
+    for id in requires.keys() {
+        if let Some(cycle) = visit(id, requires, &mut done, &mut Vec::new()) {
+            return Some(cycle);
+        }
+    }
+    None
 }
@@ -0,0 +1,519 @@
+//! Validation of selections against prerequisite schemas.
+//!
+//! Implements the subset of JSON Schema draft 2020-12 that pack authors need to
+//! describe user selections: `type`, `enum`, `const`, the numeric, string, array
+//! and object assertions, and the `allOf`/`anyOf`/`oneOf`/`not` applicators.
+//! References (`$ref`), `format` and conditional keywords are not supported.
+
+use regex::Regex;
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+use crate::error::{CoreError, CoreResult};
+use crate::model::{pointer_token, Prerequisite};
+use crate::selections::Selections;
+
+/// A single schema failure, located by a JSON pointer into the selections object.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SchemaViolation {
+    pub path: String,
+    pub keyword: String,
+    pub message: String,
+}
+
+/// Validate a selections object against every prerequisite of a node.
+///
+/// Prerequisite keys are dotted paths into the selections object
+/// (`selection.mode` addresses `{"selection": {"mode": ...}}`). Missing required
+/// keys are reported first as `MISSING_SELECTIONS`; schema failures of present
+/// keys are reported together as `INVALID_SELECTION`.
+pub fn validate_selections(prereqs: &[Prerequisite], selections: &Selections) -> CoreResult<()> {
+    let mut missing: Vec<String> = Vec::new();
+    let mut violations: Vec<SchemaViolation> = Vec::new();
+    for prereq in prereqs {
+        match selections.get(&prereq.key) {
+            Some(value) => validate_at(
+                &prereq.schema,
+                value,
+                &key_pointer(&prereq.key),
+                &mut violations,
+            ),
+            None if !prereq.optional => missing.push(prereq.key.clone()),
+            None => {}
+        }
+    }
+    if !missing.is_empty() {
+        return Err(CoreError::MissingSelections(missing));
+    }
+    if !violations.is_empty() {
+        return Err(CoreError::SchemaViolations(violations));
+    }
+    Ok(())
+}
+
+/// Validate a single instance against a schema, returning every violation found.
+pub fn validate(schema: &Value, instance: &Value) -> Vec<SchemaViolation> {
+    let mut violations = Vec::new();
+    validate_at(schema, instance, "", &mut violations);
+    violations
+}
+
+/// Resolve a dotted key (`selection.names`) inside a JSON object.
+pub fn lookup<'a>(selections: &'a Value, key: &str) -> Option<&'a Value> {
+    key.split('.')
+        .try_fold(selections, |current, segment| current.get(segment))
+        .filter(|v| !v.is_null())
+}
+
+/// JSON pointer for a dotted key, e.g. `selection.mode` -> `/selection/mode`.
+pub fn key_pointer(key: &str) -> String {
+    key.split('.')
+        .map(|s| format!("/{}", pointer_token(s)))
+        .collect()
+}
+
+fn push(out: &mut Vec<SchemaViolation>, path: &str, keyword: &str, message: String) {
+    out.push(SchemaViolation {
+        path: if path.is_empty() {
+            "/".into()
+        } else {
+            path.into()
+        },
+        keyword: keyword.into(),
+        message,
+    });
+}
+
+fn validate_at(schema: &Value, instance: &Value, path: &str, out: &mut Vec<SchemaViolation>) {
+    let obj = match schema {
+        Value::Bool(true) => return,
+        Value::Bool(false) => {
+            push(out, path, "false", "no value is allowed here".into());
+            return;
+        }
+        Value::Object(obj) => obj,
+        _ => return,
+    };
+
+    if let Some(expected) = obj.get("type") {
+        let allowed: Vec<&str> = match expected {
+            Value::String(t) => vec![t.as_str()],
+            Value::Array(list) => list.iter().filter_map(|v| v.as_str()).collect(),
+            _ => vec![],
+        };
+        if !allowed.is_empty() && !allowed.iter().any(|t| has_type(instance, t)) {
+            push(
+                out,
+                path,
+                "type",
+                format!(
+                    "expected {}, got {}",
+                    allowed.join(" or "),
+                    type_name(instance)
+                ),
+            );
+            // Remaining assertions are type specific and would only add noise.
+            return;
+        }
+    }
+
+    if let Some(Value::Array(options)) = obj.get("enum") {
+        if !options.iter().any(|o| json_eq(o, instance)) {
+            push(
+                out,
+                path,
+                "enum",
+                format!("must be one of {}", render_list(options)),
+            );
+        }
+    }
+    if let Some(expected) = obj.get("const") {
+        if !json_eq(expected, instance) {
+            push(out, path, "const", format!("must equal {expected}"));
+        }
+    }
+
+    match instance {
+        Value::Number(n) => validate_number(obj, n.as_f64().unwrap_or_default(), path, out),
+        Value::String(s) => validate_string(obj, s, path, out),
+        Value::Array(items) => validate_array(obj, items, path, out),
+        Value::Object(map) => validate_object(obj, map, path, out),
+        _ => {}
+    }
+
+    if let Some(Value::Array(all)) = obj.get("allOf") {
+        for sub in all {
+            validate_at(sub, instance, path, out);
+        }
+    }
+    if let Some(Value::Array(any)) = obj.get("anyOf") {
+        if !any.iter().any(|sub| passes(sub, instance)) {
+            push(out, path, "anyOf", "must match at least one schema".into());
+        }
+    }
+    if let Some(Value::Array(one)) = obj.get("oneOf") {
+        let matched = one.iter().filter(|sub| passes(sub, instance)).count();
+        if matched != 1 {
+            push(
+                out,
+                path,
+                "oneOf",
+                format!("must match exactly one schema, matched {matched}"),
+            );
+        }
+    }
+    if let Some(not) = obj.get("not") {
+        if passes(not, instance) {
+            push(out, path, "not", "must not match schema".into());
+        }
+    }
+}
+
+fn passes(schema: &Value, instance: &Value) -> bool {
+    let mut scratch = Vec::new();
+    validate_at(schema, instance, "", &mut scratch);
+    scratch.is_empty()
+}
+
+fn validate_number(obj: &Map<String, Value>, n: f64, path: &str, out: &mut Vec<SchemaViolation>) {
+    let bound = |key: &str| obj.get(key).and_then(|v| v.as_f64());
+    if let Some(min) = bound("minimum").filter(|min| n < *min) {
+        push(out, path, "minimum", format!("must be >= {min}"));
+    }
+    if let Some(max) = bound("maximum").filter(|max| n > *max) {
+        push(out, path, "maximum", format!("must be <= {max}"));
+    }
+    if let Some(min) = bound("exclusiveMinimum").filter(|min| n <= *min) {
+        push(out, path, "exclusiveMinimum", format!("must be > {min}"));
+    }
+    if let Some(max) = bound("exclusiveMaximum").filter(|max| n >= *max) {
+        push(out, path, "exclusiveMaximum", format!("must be < {max}"));
+    }
+    if let Some(step) = bound("multipleOf").filter(|step| *step > 0.0) {
+        let ratio = n / step;
+        if (ratio - ratio.round()).abs() > f64::EPSILON * ratio.abs().max(1.0) {
+            push(
+                out,
+                path,
+                "multipleOf",
+                format!("must be a multiple of {step}"),
+            );
+        }
+    }
+}
+
+fn validate_string(obj: &Map<String, Value>, s: &str, path: &str, out: &mut Vec<SchemaViolation>) {
+    let len = s.chars().count() as u64;
+    if let Some(min) = obj.get("minLength").and_then(|v| v.as_u64()) {
+        if len < min {
+            push(
+                out,
+                path,
+                "minLength",
+                format!("must be at least {min} characters"),
+            );
+        }
+    }
+    if let Some(max) = obj.get("maxLength").and_then(|v| v.as_u64()) {
+        if len > max {
+            push(
+                out,
+                path,
+                "maxLength",
+                format!("must be at most {max} characters"),
+            );
+        }
+    }
+    if let Some(pattern) = obj.get("pattern").and_then(|v| v.as_str()) {
+        match Regex::new(pattern) {
+            Ok(re) if !re.is_match(s) => {
+                push(
+                    out,
+                    path,
+                    "pattern",
+                    format!("must match pattern {pattern}"),
+                );
+            }
+            Ok(_) => {}
+            Err(err) => push(
+                out,
+                path,
+                "pattern",
+                format!("invalid pattern {pattern}: {err}"),
+            ),
+        }
+    }
+}
+
+fn validate_array(
+    obj: &Map<String, Value>,
+    items: &[Value],
+    path: &str,
+    out: &mut Vec<SchemaViolation>,
+) {
+    let len = items.len() as u64;
+    if let Some(min) = obj.get("minItems").and_then(|v| v.as_u64()) {
+        if len < min {
+            push(
+                out,
+                path,
+                "minItems",
+                format!("must contain at least {min} items"),
+            );
+        }
+    }
+    if let Some(max) = obj.get("maxItems").and_then(|v| v.as_u64()) {
+        if len > max {
+            push(
+                out,
+                path,
+                "maxItems",
+                format!("must contain at most {max} items"),
+            );
+        }
+    }
+    if obj.get("uniqueItems").and_then(|v| v.as_bool()) == Some(true) {
+        for (i, item) in items.iter().enumerate() {
+            if items[..i].iter().any(|prev| json_eq(prev, item)) {
+                push(
+                    out,
+                    &format!("{path}/{i}"),
+                    "uniqueItems",
+                    "duplicate item".into(),
+                );
+            }
+        }
+    }
+    let prefix = obj
+        .get("prefixItems")
+        .and_then(|v| v.as_array())
+        .map(|v| v.as_slice())
+        .unwrap_or_default();
+    for (i, item) in items.iter().enumerate() {
+        let item_path = format!("{path}/{i}");
+        if let Some(schema) = prefix.get(i) {
+            validate_at(schema, item, &item_path, out);
+        } else if let Some(schema) = obj.get("items") {
+            validate_at(schema, item, &item_path, out);
+        }
+    }
+    if let Some(contains) = obj.get("contains") {
+        if !items.iter().any(|item| passes(contains, item)) {
+            push(out, path, "contains", "must contain a matching item".into());
+        }
+    }
+}
+
+fn validate_object(
+    obj: &Map<String, Value>,
+    map: &Map<String, Value>,
+    path: &str,
+    out: &mut Vec<SchemaViolation>,
+) {
+    if let Some(Value::Array(required)) = obj.get("required") {
+        for key in required.iter().filter_map(|k| k.as_str()) {
+            if !map.contains_key(key) {
+                push(
+                    out,
+                    &format!("{path}/{}", pointer_token(key)),
+                    "required",
+                    format!("missing required property {key}"),
+                );
+            }
+        }
+    }
+    let len = map.len() as u64;
+    if let Some(min) = obj.get("minProperties").and_then(|v| v.as_u64()) {
+        if len < min {
+            push(
+                out,
+                path,
+                "minProperties",
+                format!("must have at least {min} properties"),
+            );
+        }
+    }
+    if let Some(max) = obj.get("maxProperties").and_then(|v| v.as_u64()) {
+        if len > max {
+            push(
+                out,
+                path,
+                "maxProperties",
+                format!("must have at most {max} properties"),
+            );
+        }
+    }
+    let properties = obj.get("properties").and_then(|v| v.as_object());
+    for (key, value) in map {
+        let child = format!("{path}/{}", pointer_token(key));
+        match properties.and_then(|p| p.get(key)) {
+            Some(schema) => validate_at(schema, value, &child, out),
+            None => match obj.get("additionalProperties") {
+                Some(Value::Bool(false)) => {
+                    push(
+                        out,
+                        &child,
+                        "additionalProperties",
+                        format!("unexpected property {key}"),
+                    );
+                }
+                Some(schema) => validate_at(schema, value, &child, out),
+                None => {}
+            },
+        }
+    }
+}
+
+fn has_type(instance: &Value, ty: &str) -> bool {
+    match ty {
+        "null" => instance.is_null(),
+        "boolean" => instance.is_boolean(),
+        "object" => instance.is_object(),
+        "array" => instance.is_array(),
+        "string" => instance.is_string(),
+        "number" => instance.is_number(),
+        "integer" => match instance {
+            Value::Number(n) => {
+                n.is_i64() || n.is_u64() || n.as_f64().is_some_and(|f| f.fract() == 0.0)
+            }
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+fn type_name(instance: &Value) -> &'static str {
+    match instance {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+/// JSON equality where `1` and `1.0` compare equal, as the spec requires.
+fn json_eq(a: &Value, b: &Value) -> bool {
+    match (a, b) {
+        (Value::Number(x), Value::Number(y)) => x.as_f64() == y.as_f64(),
+        (Value::Array(x), Value::Array(y)) => {
+            x.len() == y.len() && x.iter().zip(y).all(|(l, r)| json_eq(l, r))
+        }
+        (Value::Object(x), Value::Object(y)) => {
+            x.len() == y.len()
+                && x.iter()
+                    .all(|(k, v)| y.get(k).is_some_and(|o| json_eq(v, o)))
+        }
+        _ => a == b,
+    }
+}
+
+fn render_list(options: &[Value]) -> String {
+    options
+        .iter()
+        .map(|o| match o {
+            Value::String(s) => s.clone(),
+            other => other.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join("|")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn prereq(key: &str, schema: Value, optional: bool) -> Prerequisite {
+        Prerequisite {
+            key: key.into(),
+            schema,
+            optional,
+        }
+    }
+
+    fn sel(value: Value) -> Selections {
+        Selections::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn reports_nested_violations_with_pointers() {
+        let schema = json!({
+            "type": "object",
+            "required": ["name", "region"],
+            "additionalProperties": false,
+            "properties": {
+                "name": { "type": "string", "pattern": "^[a-z][a-z0-9-]*$", "maxLength": 8 },
+                "replicas": { "type": "integer", "minimum": 1, "maximum": 3 },
+                "tags": { "type": "array", "items": { "type": "string" }, "uniqueItems": true }
+            }
+        });
+        let instance = json!({
+            "name": "My App!",
+            "replicas": 5,
+            "tags": ["a", 1, "a"],
+            "extra": true
+        });
+        let found: Vec<(String, String)> = validate(&schema, &instance)
+            .into_iter()
+            .map(|v| (v.path, v.keyword))
+            .collect();
+        for expected in [
+            ("/region", "required"),
+            ("/name", "pattern"),
+            ("/replicas", "maximum"),
+            ("/tags/1", "type"),
+            ("/tags/2", "uniqueItems"),
+            ("/extra", "additionalProperties"),
+        ] {
+            assert!(
+                found.contains(&(expected.0.to_string(), expected.1.to_string())),
+                "missing {expected:?} in {found:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn applicators_and_boolean_schemas() {
+        let schema = json!({ "oneOf": [{ "type": "string" }, { "type": "integer" }] });
+        assert!(validate(&schema, &json!("x")).is_empty());
+        assert_eq!(validate(&schema, &json!(1.5))[0].keyword, "oneOf");
+        assert_eq!(validate(&json!(false), &json!(1))[0].keyword, "false");
+        assert!(validate(&json!({ "not": { "const": "no" } }), &json!("yes")).is_empty());
+        assert!(validate(&json!({ "enum": [1] }), &json!(1.0)).is_empty());
+    }
+
+    #[test]
+    fn validate_selections_reports_missing_before_invalid() {
+        let prereqs = vec![
+            prereq(
+                "setup.appName",
+                json!({ "type": "string", "minLength": 1 }),
+                false,
+            ),
+            prereq("setup.region", json!({ "enum": ["us", "eu"] }), false),
+            prereq("setup.debug", json!({ "type": "boolean" }), true),
+        ];
+        let err =
+            validate_selections(&prereqs, &sel(json!({ "setup": { "appName": "" } }))).unwrap_err();
+        assert!(matches!(err, CoreError::MissingSelections(ref keys) if keys == &["setup.region"]));
+
+        let err = validate_selections(
+            &prereqs,
+            &sel(json!({ "setup": { "appName": "", "region": "ap" } })),
+        )
+        .unwrap_err();
+        let CoreError::SchemaViolations(list) = err else {
+            panic!("expected violations");
+        };
+        let paths: Vec<&str> = list.iter().map(|v| v.path.as_str()).collect();
+        assert_eq!(paths, ["/setup/appName", "/setup/region"]);
+
+        validate_selections(
+            &prereqs,
+            &sel(json!({ "setup.appName": "a", "setup.region": "eu" })),
+        )
+        .unwrap();
+    }
+}
@@ -0,0 +1,150 @@
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::error::{CoreError, CoreResult};
+use crate::schema::lookup;
+
+/// User selections handed to executors, addressed by dotted prerequisite keys.
+///
+/// `selection.mode` lives at `{"selection": {"mode": ...}}`, so the object can be
+/// written either nested (JSON files, API bodies) or flat (`--set selection.mode=all`).
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "Value", into = "Value")]
+pub struct Selections(Map<String, Value>);
+
+impl Selections {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Build from a JSON value, which must be an object. Dotted top-level keys are expanded.
+    pub fn from_value(value: Value) -> CoreResult<Self> {
+        match value {
+            Value::Object(map) => {
+                let mut out = Self::new();
+                for (key, value) in map {
+                    out.set(&key, value);
+                }
+                Ok(out)
+            }
+            Value::Null => Ok(Self::new()),
+            other => Err(CoreError::InvalidSelection(format!(
+                "selections must be a JSON object, got {other}"
+            ))),
+        }
+    }
+
+    /// Read a selections object from a JSON file.
+    pub fn from_file(path: &Path) -> CoreResult<Self> {
+        let content = std::fs::read_to_string(path).map_err(|err| {
+            CoreError::InvalidSelection(format!(
+                "cannot read selections file {}: {err}",
+                path.display()
+            ))
+        })?;
+        let value: Value = serde_json::from_str(&content).map_err(|err| {
+            CoreError::InvalidSelection(format!(
+                "invalid selections file {}: {err}",
+                path.display()
+            ))
+        })?;
+        Self::from_value(value)
+    }
+
+    /// Parse a `key=value` assignment. The value is taken as JSON when it parses
+    /// (`true`, `3`, `["a","b"]`) and as a plain string otherwise.
+    pub fn parse_assignment(input: &str) -> CoreResult<(String, Value)> {
+        let Some((key, raw)) = input.split_once('=') else {
+            return Err(CoreError::InvalidSelection(format!(
+                "expected key=value, got {input}"
+            )));
+        };
+        let key = key.trim();
+        if key.is_empty() || key.split('.').any(|s| s.is_empty()) {
+            return Err(CoreError::InvalidSelection(format!(
+                "invalid selection key in {input}"
+            )));
+        }
+        let value = serde_json::from_str(raw).unwrap_or_else(|_| Value::String(raw.to_string()));
+        Ok((key.to_string(), value))
+    }
+
+    /// Set a value at a dotted key, creating intermediate objects as needed.
+    pub fn set(&mut self, key: &str, value: Value) {
+        let mut segments: Vec<&str> = key.split('.').collect();
+        let last = segments.pop().unwrap_or_default();
+        let mut current = &mut self.0;
+        for segment in segments {
+            let entry = current
+                .entry(segment.to_string())
+                .or_insert_with(|| Value::Object(Map::new()));
+            if !entry.is_object() {
+                *entry = Value::Object(Map::new());
+            }
+            current = entry.as_object_mut().unwrap();
+        }
+        current.insert(last.to_string(), value);
+    }
+
+    /// Builder-style `set`.
+    pub fn with(mut self, key: &str, value: impl Into<Value>) -> Self {
+        self.set(key, value.into());
+        self
+    }
+
+    /// Value at a dotted key; `null` counts as absent.
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        let (head, rest) = match key.split_once('.') {
+            Some((head, rest)) => (head, Some(rest)),
+            None => (key, None),
+        };
+        let value = self.0.get(head)?;
+        match rest {
+            Some(rest) => lookup(value, rest),
+            None => (!value.is_null()).then_some(value),
+        }
+    }
+
+    pub fn get_str(&self, key: &str) -> Option<&str> {
+        self.get(key).and_then(|v| v.as_str())
+    }
+
+    /// Deep-merge `other` into `self`; values from `other` win.
+    pub fn merge(&mut self, other: Selections) {
+        fn merge_into(target: &mut Map<String, Value>, source: Map<String, Value>) {
+            for (key, value) in source {
+                match (target.get_mut(&key), value) {
+                    (Some(Value::Object(t)), Value::Object(s)) => merge_into(t, s),
+                    (_, value) => {
+                        target.insert(key, value);
+                    }
+                }
+            }
+        }
+        merge_into(&mut self.0, other.0);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn to_value(&self) -> Value {
+        Value::Object(self.0.clone())
+    }
+}
+
+impl TryFrom<Value> for Selections {
+    type Error = CoreError;
+
+    fn try_from(value: Value) -> CoreResult<Self> {
+        Self::from_value(value)
+    }
+}
+
+impl From<Selections> for Value {
+    fn from(selections: Selections) -> Self {
+        Value::Object(selections.0)
+    }
+}
@@ -0,0 +1,57 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{CoreError, CoreResult};
+use crate::model::ConflictPolicy;
+use crate::pack::validate_install_root;
+
+/// File name of the per-workspace configuration.
+pub const WORKSPACE_CONFIG_FILE: &str = "entitycli.json";
+
+/// Contents of `entitycli.json`; every field is optional.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WorkspaceConfig {
+    #[serde(rename = "packsDir", default, skip_serializing_if = "Option::is_none")]
+    pub packs_dir: Option<String>,
+    /// Per-product install root overrides (product -> workspace-relative dir)
+    #[serde(
+        rename = "installRoots",
+        default,
+        skip_serializing_if = "BTreeMap::is_empty"
+    )]
+    pub install_roots: BTreeMap<String, String>,
+    /// Conflict policy for every write in this workspace
+    #[serde(
+        rename = "conflictPolicy",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub conflict_policy: Option<ConflictPolicy>,
+}
+
+impl WorkspaceConfig {
+    /// Load `entitycli.json` from a workspace directory; a missing file yields the defaults.
+    pub fn load(workspace: &Path) -> CoreResult<Self> {
+        let path = workspace.join(WORKSPACE_CONFIG_FILE);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = std::fs::read_to_string(&path)?;
+        let config: WorkspaceConfig = serde_json::from_str(&content).map_err(|err| {
+            CoreError::InvalidDescriptor(format!(
+                "invalid workspace config {}: {err}",
+                path.display()
+            ))
+        })?;
+        for root in config.install_roots.values() {
+            validate_install_root(root)?;
+        }
+        Ok(config)
+    }
+
+    pub fn install_root_for(&self, product: &str) -> Option<&str> {
+        self.install_roots.get(product).map(|s| s.as_str())
+    }
+}
@@ -0,0 +1,284 @@
+use std::collections::BTreeMap;
+
+use entity_core::model::{CommandShapes, GraphPackage, Node, NodeKind, Semantics};
+use indexmap::IndexMap;
+use serde::Serialize;
+use serde_json::Value;
+
+/// Graph for agents with a limited context window: payload internals (absolute
+/// paths, templates) are stripped, nodes are grouped by `meta.section`, meta shared
+/// by a whole section is listed once, and nodes may be dropped to fit a budget.
+#[derive(Debug, Clone, Serialize)]
+pub struct CompactGraph {
+    pub executable: String,
+    pub engine_version: String,
+    pub command_shapes: CommandShapes,
+    pub semantics: Semantics,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub packs: Vec<CompactPack>,
+    pub sections: Vec<CompactSection>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub omitted: Option<Omitted>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CompactPack {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CompactSection {
+    /// `meta.section` of the nodes below; absent for nodes without one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub section: Option<String>,
+    /// Meta entries every node of the section shares
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub meta: BTreeMap<String, Value>,
+    pub nodes: Vec<CompactNode>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CompactNode {
+    pub id: String,
+    pub kind: NodeKind,
+    pub title: String,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub meta: BTreeMap<String, Value>,
+    /// Selection keys the node expects
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub prerequisites: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub requires: Vec<String>,
+    #[serde(rename = "relatedTo", skip_serializing_if = "Vec::is_empty")]
+    pub related_to: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Omitted {
+    pub count: usize,
+    /// Omitted node count per section (`""` for nodes without one)
+    #[serde(rename = "bySection")]
+    pub by_section: BTreeMap<String, usize>,
+    pub note: String,
+}
+
+/// Rough token count of a rendered graph: about four characters per token.
+pub fn estimate_tokens(text: &str) -> usize {
+    text.len().div_ceil(4)
+}
+
+/// Build the compact graph. With a `budget` (approximate tokens), the nodes with
+/// the lowest `meta.priority` are dropped first, later-declared nodes before
+/// earlier ones, until the rendered graph fits.
+pub fn compact_graph(graph: &GraphPackage, budget: Option<usize>) -> CompactGraph {
+    let Some(budget) = budget else {
+        return build(graph, &[], None);
+    };
+
+    // Drop order: lowest priority first, then last declared first
+    let mut drop_order: Vec<usize> = (0..graph.nodes.len()).collect();
+    drop_order.sort_by(|a, b| {
+        priority(&graph.nodes[*a])
+            .total_cmp(&priority(&graph.nodes[*b]))
+            .then(b.cmp(a))
+    });
+
+    let fits = |dropped: usize| {
+        let compact = build(graph, &drop_order[..dropped], Some(budget));
+        let text = serde_json::to_string(&compact).unwrap_or_default();
+        (estimate_tokens(&text) <= budget, compact)
+    };
+
+    // Smallest number of dropped nodes that fits; rendering shrinks as nodes go
+    let (mut low, mut high) = (0, drop_order.len());
+    if let (true, compact) = fits(0) {
+        return compact;
+    }
+    while low + 1 < high {
+        let mid = (low + high) / 2;
+        if fits(mid).0 {
+            high = mid;
+        } else {
+            low = mid;
+        }
+    }
+    fits(high).1
+}
+
+fn priority(node: &Node) -> f64 {
+    node.meta
+        .get("priority")
+        .and_then(|v| v.as_f64())
+        .unwrap_or(0.0)
+}
+
+fn build(graph: &GraphPackage, dropped: &[usize], budget: Option<usize>) -> CompactGraph {
+    let mut groups: IndexMap<Option<String>, Vec<&Node>> = IndexMap::new();
+    let mut omitted: BTreeMap<String, usize> = BTreeMap::new();
+    let mut is_dropped = vec![false; graph.nodes.len()];
+    for &index in dropped {
+        is_dropped[index] = true;
+    }
+    for (index, node) in graph.nodes.iter().enumerate() {
+        let section = node
+            .meta
+            .get("section")
+            .and_then(|v| v.as_str())
+            .map(str::to_string);
+        if is_dropped[index] {
+            *omitted.entry(section.unwrap_or_default()).or_default() += 1;
+        } else {
+            groups.entry(section).or_default().push(node);
+        }
+    }
+
+    let sections = groups
+        .into_iter()
+        .map(|(section, nodes)| {
+            let shared = shared_meta(&nodes);
+            let nodes = nodes
+                .into_iter()
+                .map(|node| CompactNode {
+                    id: node.id.clone(),
+                    kind: node.kind.clone(),
+                    title: node.title.clone(),
+                    meta: node
+                        .meta
+                        .iter()
+                        .filter(|(k, _)| k.as_str() != "section" && !shared.contains_key(*k))
+                        .map(|(k, v)| (k.clone(), v.clone()))
+                        .collect(),
+                    prerequisites: node.prerequisites.iter().map(|p| p.key.clone()).collect(),
+                    requires: node.requires.clone(),
+                    related_to: node.related_to.clone(),
+                })
+                .collect();
+            CompactSection {
+                section,
+                meta: shared,
+                nodes,
+            }
+        })
+        .collect();
+
+    let omitted = (!dropped.is_empty()).then(|| Omitted {
+        count: dropped.len(),
+        note: format!(
+            "{} node(s) omitted to fit ~{} tokens; use `graph query` to list them",
+            dropped.len(),
+            budget.unwrap_or_default()
+        ),
+        by_section: omitted,
+    });
+
+    CompactGraph {
+        executable: graph.executable.clone(),
+        engine_version: graph.engine_version.clone(),
+        command_shapes: graph.command_shapes.clone(),
+        semantics: graph.semantics.clone(),
+        packs: graph
+            .packs
+            .iter()
+            .map(|p| CompactPack {
+                name: p.name.clone(),
+                version: p.manifest.version.clone(),
+            })
+            .collect(),
+        sections,
+        omitted,
+    }
+}
+
+/// Meta entries (other than `section`) equal across every node of a group of two or more.
+fn shared_meta(nodes: &[&Node]) -> BTreeMap<String, Value> {
+    let [first, rest @ ..] = nodes else {
+        return BTreeMap::new();
+    };
+    if rest.is_empty() {
+        return BTreeMap::new();
+    }
+    first
+        .meta
+        .iter()
+        .filter(|(k, v)| k.as_str() != "section" && rest.iter().all(|n| n.meta.get(*k) == Some(v)))
+        .map(|(k, v)| (k.clone(), v.clone()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Engine;
+    use tempfile::TempDir;
+
+    fn graph_with_docs(count: usize) -> (TempDir, GraphPackage) {
+        let packs = TempDir::new().unwrap();
+        let docs = packs.path().join("entity-auth/docs");
+        std::fs::create_dir_all(docs.join("content")).unwrap();
+        std::fs::write(docs.join("content/a.md"), "a").unwrap();
+        let nodes: Vec<Value> = (0..count)
+            .map(|i| {
+                serde_json::json!({
+                    "id": format!("entityauth:docs:{i}"),
+                    "kind": "doc",
+                    "title": format!("Doc {i}"),
+                    "meta": {
+                        "section": if i % 2 == 0 { "Even" } else { "Odd" },
+                        "tags": ["docs"],
+                        "priority": if i == count - 1 { 10 } else { 0 }
+                    },
+                    "payload": { "contentPath": "content/a.md" }
+                })
+            })
+            .collect();
+        std::fs::write(docs.join("nodes.json"), Value::from(nodes).to_string()).unwrap();
+        let (_engine, graph) =
+            Engine::bootstrap(packs.path().to_path_buf(), Some("entity-auth")).unwrap();
+        (packs, graph)
+    }
+
+    #[test]
+    fn compact_groups_by_section_and_strips_payloads() {
+        let (_packs, graph) = graph_with_docs(4);
+        let compact = compact_graph(&graph, None);
+        let value = serde_json::to_value(&compact).unwrap();
+        let text = value.to_string();
+        assert!(!text.contains("contentPath"));
+        assert!(!text.contains("a.md"));
+
+        assert_eq!(compact.sections.len(), 2);
+        let even = &compact.sections[0];
+        assert_eq!(even.section.as_deref(), Some("Even"));
+        // Shared tags move to the section; differing priority stays on the node
+        assert_eq!(even.meta["tags"], serde_json::json!(["docs"]));
+        assert!(!even.nodes[0].meta.contains_key("tags"));
+        assert!(compact.omitted.is_none());
+        assert!(estimate_tokens(&text) < estimate_tokens(&serde_json::to_string(&graph).unwrap()));
+    }
+
+    #[test]
+    fn compact_budget_drops_low_priority_nodes_with_note() {
+        let (_packs, graph) = graph_with_docs(40);
+        let full = estimate_tokens(&serde_json::to_string(&compact_graph(&graph, None)).unwrap());
+        let budget = full - 200;
+
+        let compact = compact_graph(&graph, Some(budget));
+        let text = serde_json::to_string(&compact).unwrap();
+        assert!(estimate_tokens(&text) <= budget);
+        let omitted = compact.omitted.as_ref().unwrap();
+        assert!(omitted.count > 0);
+        assert_eq!(omitted.by_section.values().sum::<usize>(), omitted.count);
+        assert!(omitted.note.contains("graph query"));
+        // The high-priority node and the first declared nodes survive
+        let kept: Vec<&str> = compact
+            .sections
+            .iter()
+            .flat_map(|s| s.nodes.iter().map(|n| n.id.as_str()))
+            .collect();
+        assert!(kept.contains(&"entityauth:docs:39"));
+        assert!(kept.contains(&"entityauth:docs:0"));
+        assert!(!kept.contains(&"entityauth:docs:38"));
+    }
+}
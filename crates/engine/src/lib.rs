@@ -1,15 +1,24 @@
+use std::collections::BTreeMap;
 use std::fs;
 use std::path::PathBuf;
 
 use entity_core::error::CoreError;
 use entity_core::loader::load_nodes_from_file;
 use entity_core::model::{
-    BridgeCommandShape, CommandShapes, DocsCommandShape, GraphPackage, InitCommandShape, Node,
-    Platforms, Semantics, SetupCommandShape, UiCommandShape,
+    BridgeCommandShape, CommandShapes, ConflictPolicy, DocsCommandShape, GraphCommandShape,
+    GraphPackage, InitCommandShape, Node, PackCommandShape, Platforms, Semantics,
+    SetupCommandShape, UiCommandShape, UndoCommandShape,
 };
+use entity_core::pack::{Pack, PackManifest};
 use entity_core::registry::Registry;
 use tracing::info;
 
+pub mod compact;
+pub mod lint;
+
+/// Version of the running engine, checked against pack `engines` ranges.
+pub const ENGINE_VERSION: &str = env!("CARGO_PKG_VERSION");
+
 #[derive(Clone)]
 pub struct Engine {
     registry: Registry,
@@ -39,31 +48,49 @@ impl Engine {
                 .collect()
         };
 
+        let mut registry = Registry::default();
+        let mut install_roots: BTreeMap<String, String> = BTreeMap::new();
         for pack in pack_dirs {
+            let name = pack
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_default();
+            let manifest = PackManifest::load(&pack)?;
+            manifest.check_name(&name)?;
+            manifest.check_engine(&name, ENGINE_VERSION)?;
+            let info = Pack::new(name.clone(), &pack, manifest);
+            install_roots.insert(name.clone(), info.install_root().to_string());
+            registry.add_pack(info);
+
             let docs_nodes = pack.join("docs").join("nodes.json");
             let comp_nodes = pack.join("components").join("nodes.json");
             let setup_nodes = pack.join("setup").join("nodes.json");
             let bridge_nodes = pack.join("bridge").join("nodes.json");
+            let mut pack_nodes: Vec<Node> = Vec::new();
             if docs_nodes.exists() {
-                nodes.extend(load_nodes_from_file(&docs_nodes)?);
+                pack_nodes.extend(load_nodes_from_file(&docs_nodes)?);
                 loaded += 1;
             }
             if comp_nodes.exists() {
-                nodes.extend(load_nodes_from_file(&comp_nodes)?);
+                pack_nodes.extend(load_nodes_from_file(&comp_nodes)?);
                 loaded += 1;
             }
             if setup_nodes.exists() {
-                nodes.extend(load_nodes_from_file(&setup_nodes)?);
+                pack_nodes.extend(load_nodes_from_file(&setup_nodes)?);
                 loaded += 1;
             }
             if bridge_nodes.exists() {
-                nodes.extend(load_nodes_from_file(&bridge_nodes)?);
+                pack_nodes.extend(load_nodes_from_file(&bridge_nodes)?);
                 loaded += 1;
             }
+            for node in &pack_nodes {
+                registry.insert_in_pack(node.clone(), &name)?;
+            }
+            nodes.extend(pack_nodes);
         }
+        registry.validate_edges()?;
         info!(packs = %packs_root.display(), product = ?product, loaded_sets = loaded, nodes_count = nodes.len(), "loaded packs nodes");
 
-        let registry = Registry::new(nodes.clone())?;
         // Determine executable from environment (shim sets ENTITY_CLI_EXECUTABLE), default to entity-cli
         let exe =
             std::env::var("ENTITY_CLI_EXECUTABLE").unwrap_or_else(|_| "entity-cli".to_string());
@@ -73,27 +100,43 @@ impl Engine {
                 template: format!("{} init <product>", exe),
             },
             docs: DocsCommandShape {
-                template: format!("{} docs read <product> --node <id>", exe),
+                template: format!(
+                    "{} docs read <product> --node <id> [--set <key=value>...] [--selections <file>]",
+                    exe
+                ),
             },
             ui: UiCommandShape {
                 template: format!(
-                    "{} ui install <product> --mode <single|multiple|all> [--names <Name...>]",
+                    "{} ui install <product> --mode <single|multiple|all> [--names <Name...>] [--workspace <path>] [--set <key=value>...] [--selections <file>] [--with-deps] [--dry-run] [--conflict-policy <overwrite|skip-existing|backup|fail-on-conflict>]",
                     exe
                 ),
+                status_template: Some(format!("{} ui status <product> [--workspace <path>]", exe)),
+                update_template: Some(format!(
+                    "{} ui update <product> [--names <Name...>] [--workspace <path>] [--dry-run]",
+                    exe
+                )),
+                diff_template: Some(format!(
+                    "{} ui diff <product> [--names <Name...>] [--framework <variant>] [--workspace <path>] [--patch]",
+                    exe
+                )),
+                uninstall_template: Some(format!(
+                    "{} ui uninstall <product> [--names <Name...>] [--workspace <path>] [--force] [--dry-run]",
+                    exe
+                )),
             },
             setup: SetupCommandShape {
                 template: format!(
-                    "{} setup run <product> --node <id> [--workspace <path>]",
+                    "{} setup run <product> --node <id> [--workspace <path>] [--set <key=value>...] [--selections <file>] [--with-deps] [--dry-run] [--conflict-policy <overwrite|skip-existing|backup|fail-on-conflict>]",
                     exe
                 ),
             },
             bridge: Some(BridgeCommandShape {
                 scaffold_template: format!(
-                    "{} bridge scaffold <product> --node <id> [--workspace <path>]",
+                    "{} bridge scaffold <product> --node <id> [--workspace <path>] [--set <key=value>...] [--selections <file>] [--with-deps] [--dry-run] [--conflict-policy <overwrite|skip-existing|backup|fail-on-conflict>]",
                     exe
                 ),
                 start_template: format!(
-                    "{} bridge start <product> --node <id> [--workspace <path>]",
+                    "{} bridge start <product> --node <id> [--workspace <path>] [--set <key=value>...] [--selections <file>]",
                     exe
                 ),
                 status_template: format!(
@@ -113,14 +156,31 @@ impl Engine {
                     exe
                 )),
             }),
+            graph: Some(GraphCommandShape {
+                query_template: format!(
+                    "{} graph query <product> [--kind <doc|component|setup|bridge>] [--tag <tag>] [--section <section>] [--id-prefix <prefix>] [--meta <key=value>...]",
+                    exe
+                ),
+            }),
+            undo: Some(UndoCommandShape {
+                template: format!("{} undo [--steps <n>] [--workspace <path>]", exe),
+            }),
+            pack: Some(PackCommandShape {
+                lint_template: format!("{} pack lint <product>", exe),
+            }),
         };
         let graph = GraphPackage {
             nodes,
             command_shapes,
             executable: exe,
+            engine_version: ENGINE_VERSION.to_string(),
+            packs: registry.packs().cloned().collect(),
+            edges: registry.edges(),
             semantics: Semantics {
-                writes_to: "cwd".to_string(),
+                writes_to: Semantics::writes_to_for(&install_roots),
+                install_roots,
                 overwrite_on_write: true,
+                conflict_policy: ConflictPolicy::Overwrite,
                 platforms: Platforms {
                     os: vec!["darwin".into()],
                     arch: vec!["arm64".into()],
@@ -188,5 +248,170 @@ mod tests {
                 .template
                 .contains("entity-cli ui install")
         );
+        assert!(graph.command_shapes.ui.template.contains("[--dry-run]"));
+        assert!(graph.command_shapes.ui.template.contains("[--with-deps]"));
+        assert!(graph.command_shapes.setup.template.contains("[--dry-run]"));
+        assert!(
+            graph
+                .command_shapes
+                .ui
+                .template
+                .contains("--conflict-policy")
+        );
+        assert!(
+            graph
+                .command_shapes
+                .setup
+                .template
+                .contains("--conflict-policy")
+        );
+        let status = graph.command_shapes.ui.status_template.as_deref().unwrap();
+        assert!(status.contains("entity-cli ui status"));
+        let update = graph.command_shapes.ui.update_template.as_deref().unwrap();
+        assert!(update.contains("entity-cli ui update"));
+        let diff = graph.command_shapes.ui.diff_template.as_deref().unwrap();
+        assert!(diff.contains("entity-cli ui diff"));
+        let uninstall = graph
+            .command_shapes
+            .ui
+            .uninstall_template
+            .as_deref()
+            .unwrap();
+        assert!(uninstall.contains("entity-cli ui uninstall"));
+        let graph_shape = graph.command_shapes.graph.as_ref().unwrap();
+        assert!(
+            graph_shape
+                .query_template
+                .contains("entity-cli graph query")
+        );
+        let undo = graph.command_shapes.undo.as_ref().unwrap();
+        assert!(undo.template.contains("entity-cli undo"));
+        let pack = graph.command_shapes.pack.as_ref().unwrap();
+        assert!(pack.lint_template.contains("entity-cli pack lint"));
+    }
+
+    #[test]
+    fn bootstrap_reports_install_roots_per_pack() {
+        let packs = TempDir::new().unwrap();
+        write_file(&packs.path().join("entity-auth/docs/nodes.json"), "[]");
+        write_file(&packs.path().join("microsoft/docs/nodes.json"), "[]");
+        write_file(
+            &packs.path().join("microsoft/pack.json"),
+            r#"{ "installRoot": "src/microsoft" }"#,
+        );
+
+        let (engine, graph) = Engine::bootstrap(packs.path().to_path_buf(), None).unwrap();
+        assert_eq!(graph.semantics.install_roots["entity-auth"], "entity-auth");
+        assert_eq!(graph.semantics.install_roots["microsoft"], "src/microsoft");
+        assert_eq!(graph.semantics.writes_to, "cwd");
+        assert_eq!(engine.registry().packs().count(), 2);
+        let semantics = serde_json::to_value(&graph.semantics).unwrap();
+        assert_eq!(semantics["conflict_policy"], "overwrite");
+        assert_eq!(semantics["overwrite_on_write"], true);
+
+        let (_engine, graph) =
+            Engine::bootstrap(packs.path().to_path_buf(), Some("microsoft")).unwrap();
+        assert_eq!(graph.semantics.writes_to, "cwd/src/microsoft");
+    }
+
+    #[test]
+    fn bootstrap_rejects_install_root_outside_workspace() {
+        let packs = TempDir::new().unwrap();
+        write_file(&packs.path().join("x/docs/nodes.json"), "[]");
+        write_file(
+            &packs.path().join("x/pack.json"),
+            r#"{ "installRoot": "../elsewhere" }"#,
+        );
+        let err = Engine::bootstrap(packs.path().to_path_buf(), Some("x"))
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("install root"));
+    }
+
+    #[test]
+    fn bootstrap_includes_pack_manifest_and_checks_engine_range() {
+        let packs = TempDir::new().unwrap();
+        write_file(&packs.path().join("entity-auth/docs/nodes.json"), "[]");
+        write_file(
+            &packs.path().join("entity-auth/pack.json"),
+            r#"{ "name": "entity-auth", "version": "1.2.0", "engines": { "entity-cli": ">=0.0.1 <1.0.0" } }"#,
+        );
+        let (_engine, graph) =
+            Engine::bootstrap(packs.path().to_path_buf(), Some("entity-auth")).unwrap();
+        assert_eq!(graph.engine_version, ENGINE_VERSION);
+        assert_eq!(graph.packs.len(), 1);
+        assert_eq!(graph.packs[0].manifest.version.as_deref(), Some("1.2.0"));
+
+        write_file(
+            &packs.path().join("entity-auth/pack.json"),
+            r#"{ "version": "1.2.0", "engines": { "entity-cli": ">=99.0.0" } }"#,
+        );
+        let err = Engine::bootstrap(packs.path().to_path_buf(), Some("entity-auth"))
+            .err()
+            .unwrap();
+        let core = err.downcast_ref::<CoreError>().unwrap();
+        assert_eq!(core.code(), "INCOMPATIBLE_PACK");
+
+        write_file(
+            &packs.path().join("entity-auth/pack.json"),
+            r#"{ "version": "not-semver" }"#,
+        );
+        let err = Engine::bootstrap(packs.path().to_path_buf(), Some("entity-auth"))
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("invalid pack version"));
+
+        write_file(
+            &packs.path().join("entity-auth/pack.json"),
+            r#"{ "name": "entity-billing" }"#,
+        );
+        let err = Engine::bootstrap(packs.path().to_path_buf(), Some("entity-auth"))
+            .err()
+            .unwrap();
+        assert!(
+            err.to_string()
+                .contains("does not match the pack directory")
+        );
+    }
+
+    #[test]
+    fn bootstrap_emits_edges_and_rejects_requires_cycles() {
+        let packs = TempDir::new().unwrap();
+        let root = packs.path().join("entity-auth");
+        write_file(&root.join("docs/content/a.md"), "a");
+        write_file(&root.join("setup/templates/basic/client.ts"), "export {};");
+        write_file(
+            &root.join("docs/nodes.json"),
+            r#"[{ "id": "entityauth:docs:a", "kind": "doc", "title": "A", "meta": {},
+                  "relatedTo": ["entityauth:setup:basic"],
+                  "payload": { "contentPath": "content/a.md" } }]"#,
+        );
+        write_file(
+            &root.join("setup/nodes.json"),
+            r#"[{ "id": "entityauth:setup:basic", "kind": "setup", "title": "S", "meta": {},
+                  "requires": ["entityauth:docs:a"],
+                  "payload": { "templateRoot": "templates/basic" } }]"#,
+        );
+        let (_engine, graph) =
+            Engine::bootstrap(packs.path().to_path_buf(), Some("entity-auth")).unwrap();
+        let edges = serde_json::to_value(&graph.edges).unwrap();
+        assert_eq!(
+            edges,
+            serde_json::json!([
+                { "from": "entityauth:docs:a", "to": "entityauth:setup:basic", "kind": "relatedTo" },
+                { "from": "entityauth:setup:basic", "to": "entityauth:docs:a", "kind": "requires" }
+            ])
+        );
+
+        let docs = std::fs::read_to_string(root.join("docs/nodes.json")).unwrap();
+        write_file(
+            &root.join("docs/nodes.json"),
+            &docs.replace("relatedTo", "requires"),
+        );
+        let err = Engine::bootstrap(packs.path().to_path_buf(), Some("entity-auth"))
+            .err()
+            .unwrap();
+        let core = err.downcast_ref::<CoreError>().unwrap();
+        assert_eq!(core.code(), "DEPENDENCY_CYCLE");
     }
 }
@@ -0,0 +1,565 @@
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Component, Path, PathBuf};
+
+use entity_core::error::{CoreError, CoreResult};
+use entity_core::loader::load_node_results;
+use entity_core::model::{Node, NodePayload, pointer_token};
+use entity_core::pack::{PACK_MANIFEST_FILE, PackManifest};
+use entity_core::registry::{Registry, find_cycle};
+use executors::{
+    IGNORE_FILE, IgnoreRules, check_exclude_pattern, check_ignore_file, resolve_component,
+};
+use indexmap::IndexMap;
+use serde::Serialize;
+use walkdir::WalkDir;
+
+use crate::ENGINE_VERSION;
+
+/// Node sets scanned in a pack, in the same order `Engine::bootstrap` loads them.
+const NODE_SETS: [&str; 4] = ["docs", "components", "setup", "bridge"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LintFinding {
+    pub severity: Severity,
+    pub code: String,
+    /// File relative to the pack root, e.g. `docs/nodes.json`
+    pub file: String,
+    /// JSON pointer into `file`, e.g. `/2/payload/contentPath`; empty for the whole file
+    pub path: String,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LintReport {
+    pub pack: String,
+    pub root: String,
+    pub ok: bool,
+    pub errors: usize,
+    pub warnings: usize,
+    pub findings: Vec<LintFinding>,
+}
+
+/// Lint a single pack, collecting every problem instead of stopping at the first one.
+///
+/// Beyond the checks `Registry::insert` enforces, this reports files under
+/// `docs/content` no doc references, component `meta.names` that do not resolve
+/// to a source, `payload.components` entries naming undeclared components, ids whose prefix does not match the product, payload paths
+/// that point outside the pack and invalid `.entityignore` files or `exclude` patterns.
+pub fn lint_pack(packs_root: &Path, product: &str) -> CoreResult<LintReport> {
+    let pack_root = packs_root.join(product);
+    if !pack_root.is_dir() {
+        return Err(CoreError::PacksNotFound(pack_root.display().to_string()));
+    }
+    let mut lint = Linter {
+        pack_root: normalize(&pack_root),
+        prefix: id_prefix(product),
+        findings: Vec::new(),
+    };
+
+    match PackManifest::load(&pack_root) {
+        Ok(manifest) => {
+            if let Err(err) = manifest.check_name(product) {
+                lint.error(
+                    "INVALID_MANIFEST",
+                    PACK_MANIFEST_FILE,
+                    "/name",
+                    err.to_string(),
+                );
+            }
+            if let Err(err) = manifest.check_engine(product, ENGINE_VERSION) {
+                lint.error(err.code(), PACK_MANIFEST_FILE, "/engines", err.to_string());
+            }
+        }
+        Err(err) => lint.error("INVALID_MANIFEST", PACK_MANIFEST_FILE, "", err.to_string()),
+    }
+
+    let mut seen: HashMap<String, (String, usize)> = HashMap::new();
+    let mut edges: Vec<(String, usize, Node)> = Vec::new();
+    let mut referenced: BTreeSet<PathBuf> = BTreeSet::new();
+    for set in NODE_SETS {
+        let file = format!("{set}/nodes.json");
+        let path = pack_root.join(&file);
+        if !path.exists() {
+            continue;
+        }
+        let results = match load_node_results(&path) {
+            Ok(results) => results,
+            Err(err) => {
+                lint.error("INVALID_JSON", &file, "", err.to_string());
+                continue;
+            }
+        };
+        for (index, result) in results.into_iter().enumerate() {
+            let node = match result {
+                Ok(node) => node,
+                Err(CoreError::InvalidNode { path, message, .. }) => {
+                    lint.error("INVALID_NODE", &file, &path, message);
+                    continue;
+                }
+                Err(err) => {
+                    lint.error("INVALID_NODE", &file, &format!("/{index}"), err.to_string());
+                    continue;
+                }
+            };
+            if let Some((first_file, first_index)) = seen.get(&node.id) {
+                lint.error(
+                    "DUPLICATE_ID",
+                    &file,
+                    &format!("/{index}/id"),
+                    format!(
+                        "duplicate node id {} (first declared in {first_file} at /{first_index})",
+                        node.id
+                    ),
+                );
+            } else {
+                seen.insert(node.id.clone(), (file.clone(), index));
+            }
+            if let NodePayload::Doc { content_path } = &node.payload {
+                referenced.insert(normalize(Path::new(content_path)));
+            }
+            lint.node(&file, index, &node);
+            edges.push((file.clone(), index, node));
+        }
+    }
+    lint.edges(&edges, &seen);
+
+    for entry in WalkDir::new(&pack_root)
+        .into_iter()
+        .filter_map(Result::ok)
+        .filter(|e| e.file_type().is_file() && e.file_name() == IGNORE_FILE)
+    {
+        if let Some(err) = check_ignore_file(entry.path()) {
+            let rel = lint.relative(entry.path());
+            lint.error(
+                "INVALID_IGNORE",
+                &rel,
+                "",
+                format!("{rel} is invalid: {err}"),
+            );
+        }
+    }
+
+    // Ignored content is never copied, so it needs no doc node; an invalid
+    // ignore file was reported above and skips this check
+    let content_root = pack_root.join("docs").join("content");
+    if content_root.is_dir()
+        && let Ok(files) = IgnoreRules::new(Some(pack_root.clone()), Vec::new())
+            .files(&content_root, &content_root)
+    {
+        for path in files {
+            if !referenced.contains(&normalize(&path)) {
+                let rel = lint.relative(&path);
+                lint.warning(
+                    "UNREFERENCED_CONTENT",
+                    &rel,
+                    "",
+                    format!("{rel} is not referenced by any doc node"),
+                );
+            }
+        }
+    }
+
+    let errors = lint
+        .findings
+        .iter()
+        .filter(|f| f.severity == Severity::Error)
+        .count();
+    let warnings = lint.findings.len() - errors;
+    Ok(LintReport {
+        pack: product.to_string(),
+        root: pack_root.display().to_string(),
+        ok: errors == 0,
+        errors,
+        warnings,
+        findings: lint.findings,
+    })
+}
+
+struct Linter {
+    pack_root: PathBuf,
+    prefix: String,
+    findings: Vec<LintFinding>,
+}
+
+impl Linter {
+    fn node(&mut self, file: &str, index: usize, node: &Node) {
+        for problem in Registry::node_problems(node) {
+            self.error(
+                problem.code,
+                file,
+                &format!("/{index}{}", problem.path),
+                problem.message,
+            );
+        }
+
+        let head = node.id.split(':').next().unwrap_or_default();
+        if head != self.prefix {
+            self.warning(
+                "ID_PREFIX",
+                file,
+                &format!("/{index}/id"),
+                format!(
+                    "node id {} should start with {}: to match the product",
+                    node.id, self.prefix
+                ),
+            );
+        }
+
+        for (pointer, path) in node.payload.paths() {
+            if !normalize(Path::new(path)).starts_with(&self.pack_root) {
+                self.error(
+                    "PATH_ESCAPES_PACK",
+                    file,
+                    &format!("/{index}{pointer}"),
+                    format!("path {path} of node {} is outside the pack", node.id),
+                );
+            }
+        }
+
+        for (i, pattern) in node.payload.exclude().iter().enumerate() {
+            if let Some(err) = check_exclude_pattern(pattern) {
+                self.error(
+                    "INVALID_IGNORE",
+                    file,
+                    &format!("/{index}/payload/exclude/{i}"),
+                    format!(
+                        "exclude pattern {pattern:?} of node {} is invalid: {err}",
+                        node.id
+                    ),
+                );
+            }
+        }
+
+        if let NodePayload::Component {
+            source_root,
+            components,
+            variants,
+            ..
+        } = &node.payload
+        {
+            let source_root = Path::new(source_root);
+            let names = node.meta.get("names").and_then(|v| v.as_array());
+            let declared = |name: &str| {
+                names.is_none_or(|names| names.iter().any(|n| n.as_str() == Some(name)))
+            };
+            for (component, spec) in components {
+                let token = pointer_token(component);
+                if !declared(component) {
+                    self.error(
+                        "UNKNOWN_COMPONENT",
+                        file,
+                        &format!("/{index}/payload/components/{token}"),
+                        format!(
+                            "component {component} of node {} is not in meta.names",
+                            node.id
+                        ),
+                    );
+                }
+                for (i, dependency) in spec.requires.iter().enumerate() {
+                    if !declared(dependency) {
+                        self.error(
+                            "UNKNOWN_COMPONENT",
+                            file,
+                            &format!("/{index}/payload/components/{token}/requires/{i}"),
+                            format!(
+                                "component {component} of node {} requires unknown component {dependency}",
+                                node.id
+                            ),
+                        );
+                    }
+                }
+            }
+            if source_root.is_dir()
+                && let Some(names) = names
+            {
+                for (i, name) in names.iter().enumerate() {
+                    let Some(name) = name.as_str() else { continue };
+                    let resolves = std::iter::once(source_root)
+                        .chain(variants.values().map(Path::new))
+                        .any(|root| resolve_component(root, name).is_some());
+                    if !resolves {
+                        self.error(
+                            "UNRESOLVED_COMPONENT",
+                            file,
+                            &format!("/{index}/meta/names/{i}"),
+                            format!(
+                                "component {name} of node {} has no directory or source file",
+                                node.id
+                            ),
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    /// `requires`/`relatedTo` targets must exist in the pack and `requires` must be acyclic.
+    fn edges(&mut self, nodes: &[(String, usize, Node)], seen: &HashMap<String, (String, usize)>) {
+        for (file, index, node) in nodes {
+            for (field, targets) in [
+                ("requires", &node.requires),
+                ("relatedTo", &node.related_to),
+            ] {
+                for (i, target) in targets.iter().enumerate() {
+                    if !seen.contains_key(target) {
+                        self.error(
+                            "UNKNOWN_DEPENDENCY",
+                            file,
+                            &format!("/{index}/{field}/{i}"),
+                            format!("node {} {field} unknown node {target}", node.id),
+                        );
+                    }
+                }
+            }
+        }
+        // Duplicate ids are reported already; the first declaration wins here
+        let mut requires: IndexMap<&str, &[String]> = IndexMap::new();
+        for (_, _, node) in nodes {
+            requires
+                .entry(node.id.as_str())
+                .or_insert(node.requires.as_slice());
+        }
+        if let Some(cycle) = find_cycle(&requires)
+            && let Some((file, index)) = seen.get(&cycle[0])
+        {
+            let message = CoreError::DependencyCycle(cycle).to_string();
+            self.error(
+                "DEPENDENCY_CYCLE",
+                &file.clone(),
+                &format!("/{index}/requires"),
+                message,
+            );
+        }
+    }
+
+    fn relative(&self, path: &Path) -> String {
+        let path = normalize(path);
+        path.strip_prefix(&self.pack_root)
+            .unwrap_or(&path)
+            .display()
+            .to_string()
+    }
+
+    fn error(&mut self, code: &str, file: &str, path: &str, message: String) {
+        self.push(Severity::Error, code, file, path, message);
+    }
+
+    fn warning(&mut self, code: &str, file: &str, path: &str, message: String) {
+        self.push(Severity::Warning, code, file, path, message);
+    }
+
+    fn push(&mut self, severity: Severity, code: &str, file: &str, path: &str, message: String) {
+        self.findings.push(LintFinding {
+            severity,
+            code: code.to_string(),
+            file: file.to_string(),
+            path: path.to_string(),
+            message,
+        });
+    }
+}
+
+/// Expected first id segment for a product: `entity-auth` -> `entityauth`.
+fn id_prefix(product: &str) -> String {
+    product
+        .chars()
+        .filter(|c| *c != '-' && *c != '_')
+        .collect::<String>()
+        .to_ascii_lowercase()
+}
+
+/// Resolve `.` and `..` lexically so paths that do not exist can still be compared.
+fn normalize(path: &Path) -> PathBuf {
+    let mut out = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !out.pop() {
+                    out.push("..");
+                }
+            }
+            other => out.push(other),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn write_file(path: &Path, content: &str) {
+        if let Some(parent) = path.parent() {
+            let _ = std::fs::create_dir_all(parent);
+        }
+        let _ = std::fs::write(path, content);
+    }
+
+    fn codes(report: &LintReport) -> Vec<(&str, &str, &str)> {
+        report
+            .findings
+            .iter()
+            .map(|f| (f.code.as_str(), f.file.as_str(), f.path.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn lint_clean_pack_has_no_findings() {
+        let packs = TempDir::new().unwrap();
+        let root = packs.path().join("entity-auth");
+        write_file(&root.join("docs/content/a.md"), "hello");
+        write_file(
+            &root.join("docs/nodes.json"),
+            r#"[{"id":"entityauth:docs:a","kind":"doc","title":"A","meta":{},
+                "payload":{"contentPath":"content/a.md"}}]"#,
+        );
+        write_file(&root.join("components/src/Button.tsx"), "export {}");
+        // Components may exist only in a framework variant
+        write_file(&root.join("components/svelte/Card.svelte"), "<div />");
+        write_file(
+            &root.join("components/nodes.json"),
+            r#"[{"id":"entityauth:components:install","kind":"component","title":"C",
+                "meta":{"names":["Button","Card"]},"payload":{"sourceRoot":"src",
+                "variants":{"svelte":"svelte"}}}]"#,
+        );
+
+        let report = lint_pack(packs.path(), "entity-auth").unwrap();
+        assert!(report.ok, "{:?}", report.findings);
+        assert!(report.findings.is_empty());
+    }
+
+    #[test]
+    fn lint_collects_every_problem_with_file_and_pointer() {
+        let packs = TempDir::new().unwrap();
+        let root = packs.path().join("entity-auth");
+        write_file(&root.join("docs/content/a.md"), "hello");
+        write_file(&root.join("docs/content/orphan.md"), "nobody links here");
+        write_file(
+            &root.join("docs/nodes.json"),
+            r#"[
+                {"id":"entityauth:docs:a","kind":"doc","title":"A","meta":{},
+                 "requires":["entityauth:bridge:b"],
+                 "payload":{"contentPath":"content/a.md"}},
+                {"id":"entityauth:docs:a","kind":"doc","title":"Dup","meta":{},
+                 "payload":{"contentPath":"content/a.md"}},
+                {"id":"other:docs:b","kind":"doc","title":"B","meta":{},
+                 "payload":{"contentPath":"content/missing.md"}},
+                {"id":"entityauth:docs:c","kind":"doc","title":"C","meta":{},
+                 "payload":{"contentPath":"../../outside.md"}},
+                {"id":"entityauth:docs:d","kind":"doc","title":"D","meta":{},
+                 "payload":{"contentPath":""}}
+            ]"#,
+        );
+        write_file(&root.join("components/src/Button.tsx"), "export {}");
+        write_file(
+            &root.join("components/nodes.json"),
+            r#"[{"id":"entityauth:components:install","kind":"component","title":"C",
+                "meta":{"names":["Button","Ghost"]},"payload":{"sourceRoot":"src",
+                "variants":{"vue/next":"vue-next"},
+                "components":{"Button":{"requires":["Icon"]}}}}]"#,
+        );
+        write_file(
+            &root.join("bridge/nodes.json"),
+            r#"[{"id":"entityauth:bridge:b","kind":"bridge","title":"B","meta":{},
+                "requires":["entityauth:docs:a","entityauth:docs:nope"],
+                "payload":{"runner":"runner.js","heartbeatIntervalMs":0}}]"#,
+        );
+
+        let report = lint_pack(packs.path(), "entity-auth").unwrap();
+        assert!(!report.ok);
+        let found = codes(&report);
+        for expected in [
+            ("DUPLICATE_ID", "docs/nodes.json", "/1/id"),
+            ("ID_PREFIX", "docs/nodes.json", "/2/id"),
+            ("MISSING_PATH", "docs/nodes.json", "/2/payload/contentPath"),
+            (
+                "PATH_ESCAPES_PACK",
+                "docs/nodes.json",
+                "/3/payload/contentPath",
+            ),
+            ("INVALID_NODE", "docs/nodes.json", "/4/payload/contentPath"),
+            (
+                "UNRESOLVED_COMPONENT",
+                "components/nodes.json",
+                "/0/meta/names/1",
+            ),
+            (
+                "UNKNOWN_COMPONENT",
+                "components/nodes.json",
+                "/0/payload/components/Button/requires/0",
+            ),
+            (
+                "MISSING_PATH",
+                "components/nodes.json",
+                "/0/payload/variants/vue~1next",
+            ),
+            ("MISSING_PATH", "bridge/nodes.json", "/0/payload/runner"),
+            (
+                "INVALID_HEARTBEAT",
+                "bridge/nodes.json",
+                "/0/payload/heartbeatIntervalMs",
+            ),
+            ("UNREFERENCED_CONTENT", "docs/content/orphan.md", ""),
+            ("UNKNOWN_DEPENDENCY", "bridge/nodes.json", "/0/requires/1"),
+            ("DEPENDENCY_CYCLE", "docs/nodes.json", "/0/requires"),
+        ] {
+            assert!(
+                found.contains(&expected),
+                "missing {expected:?} in {found:?}"
+            );
+        }
+        assert_eq!(report.errors + report.warnings, report.findings.len());
+        assert_eq!(report.warnings, 2);
+    }
+
+    #[test]
+    fn lint_reports_invalid_json_and_manifest() {
+        let packs = TempDir::new().unwrap();
+        let root = packs.path().join("entity-auth");
+        write_file(&root.join("pack.json"), r#"{"version":"not-semver"}"#);
+        write_file(&root.join("docs/nodes.json"), "{ not json");
+
+        let report = lint_pack(packs.path(), "entity-auth").unwrap();
+        let found = codes(&report);
+        assert!(found.contains(&("INVALID_MANIFEST", "pack.json", "")));
+        assert!(found.contains(&("INVALID_JSON", "docs/nodes.json", "")));
+        assert!(lint_pack(packs.path(), "missing").is_err());
+
+        write_file(&root.join("pack.json"), r#"{"name":"entity-billing"}"#);
+        let report = lint_pack(packs.path(), "entity-auth").unwrap();
+        assert!(codes(&report).contains(&("INVALID_MANIFEST", "pack.json", "/name")));
+    }
+
+    #[test]
+    fn lint_honors_ignore_files_and_reports_invalid_patterns() {
+        let packs = TempDir::new().unwrap();
+        let root = packs.path().join("entity-auth");
+        write_file(&root.join(".entityignore"), "drafts/\n");
+        write_file(&root.join("docs/content/drafts/wip.md"), "later");
+        write_file(&root.join("setup/templates/basic/.entityignore"), "*.{ts\n");
+        write_file(&root.join("setup/templates/basic/client.ts"), "export {}");
+        write_file(
+            &root.join("setup/nodes.json"),
+            r#"[{"id":"entityauth:setup:basic","kind":"setup","title":"S","meta":{},
+                "payload":{"templateRoot":"templates/basic","exclude":["dist/","*.{a,b"]}}]"#,
+        );
+
+        let report = lint_pack(packs.path(), "entity-auth").unwrap();
+        assert_eq!(
+            codes(&report),
+            [
+                ("INVALID_IGNORE", "setup/nodes.json", "/0/payload/exclude/1"),
+                ("INVALID_IGNORE", "setup/templates/basic/.entityignore", ""),
+            ]
+        );
+    }
+}
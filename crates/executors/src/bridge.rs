@@ -5,14 +5,29 @@ use std::time::{SystemTime, UNIX_EPOCH};
 use entity_core::error::{CoreError, CoreResult};
 use entity_core::model::{BridgeEnvVar, NodeKind, NodePayload};
 use entity_core::registry::Registry;
-use walkdir::WalkDir;
+use entity_core::schema::validate_selections;
+use entity_core::selections::Selections;
 
 use crate::components::CopyItemReport;
+use crate::entityignore::{IgnoreRules, SkippedFiles};
+use crate::template::template_vars;
+use crate::transaction::TransactionReport;
+use crate::util::install_root;
+use crate::write::{WriteOptions, WritePlan, Writer};
 
-#[derive(Debug)]
+#[derive(Debug, serde::Serialize)]
 pub struct BridgeScaffoldReport {
     pub copied: Vec<CopyItemReport>,
     pub notes: Vec<String>,
+    /// Files that would be written, on dry runs
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub plan: Option<WritePlan>,
+    /// Pack files left out by `.entityignore` files, `exclude` or the defaults
+    #[serde(skip_serializing_if = "SkippedFiles::is_empty")]
+    pub skipped: SkippedFiles,
+    /// Outcome of the writes, absent on dry runs
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub transaction: Option<TransactionReport>,
 }
 
 #[derive(Debug, Clone)]
@@ -78,14 +93,28 @@ pub struct BridgeStopResult {
 
 pub struct BridgeExecutor<'a> {
     registry: &'a Registry,
+    options: WriteOptions,
 }
 
 impl<'a> BridgeExecutor<'a> {
     pub fn new(registry: &'a Registry) -> Self {
-        Self { registry }
+        Self {
+            registry,
+            options: WriteOptions::default(),
+        }
+    }
+
+    pub fn with_options(mut self, options: WriteOptions) -> Self {
+        self.options = options;
+        self
     }
 
-    pub fn scaffold(&self, node_id: &str, workspace: &Path) -> CoreResult<BridgeScaffoldReport> {
+    pub fn scaffold(
+        &self,
+        node_id: &str,
+        selections: &Selections,
+        workspace: &Path,
+    ) -> CoreResult<BridgeScaffoldReport> {
         let node = self.registry.get(node_id)?;
         if node.kind != NodeKind::Bridge {
             return Err(CoreError::WrongKind {
@@ -93,6 +122,7 @@ impl<'a> BridgeExecutor<'a> {
                 actual: format!("{:?}", node.kind),
             });
         }
+        validate_selections(&node.prerequisites, selections)?;
         if !workspace.exists() {
             return Err(CoreError::TargetNotFound(workspace.display().to_string()));
         }
@@ -100,12 +130,12 @@ impl<'a> BridgeExecutor<'a> {
         let mut report = BridgeScaffoldReport {
             copied: Vec::new(),
             notes: vec![],
+            plan: None,
+            skipped: SkippedFiles::default(),
+            transaction: None,
         };
 
-        let NodePayload::Bridge {
-            template_root,
-            ..
-        } = &node.payload else {
+        let NodePayload::Bridge { template_root, .. } = &node.payload else {
             unreachable!();
         };
 
@@ -114,35 +144,30 @@ impl<'a> BridgeExecutor<'a> {
             if !template.exists() {
                 return Err(CoreError::MissingSource(template.display().to_string()));
             }
-            let to_root = workspace.join("entity-auth");
-            fs::create_dir_all(&to_root)?;
-            let mut files_copied = 0usize;
-            for entry in WalkDir::new(template).into_iter().filter_map(Result::ok) {
-                let path = entry.path();
-                if path.is_file() {
-                    let rel = path.strip_prefix(template).unwrap();
-                    let to_path = to_root.join(rel);
-                    if let Some(parent) = to_path.parent() {
-                        fs::create_dir_all(parent)?;
-                    }
-                    fs::copy(path, &to_path)?;
-                    files_copied += 1;
-                }
-            }
+            let to_root = install_root(self.registry, node_id, workspace)?;
+            let vars = template_vars(self.registry, node_id, selections, workspace)?;
+            let mut writer = Writer::for_node(self.registry, node_id, workspace, &self.options)?
+                .journaled("bridge scaffold", selections)
+                .with_vars(vars);
+            let mut ignore = IgnoreRules::for_node(self.registry, node_id)?;
+            let files_copied = writer.stage(ignore.tree_files(template, template, &to_root)?)?;
+            report.skipped = ignore.skipped();
             report.copied.push(CopyItemReport {
                 from: template.display().to_string(),
                 to: to_root.display().to_string(),
                 count: files_copied,
             });
-            report
-                .notes
-                .push("Overwrite-on-write by default".to_string());
+            (report.notes, report.plan, report.transaction) = writer.commit()?;
         }
 
         Ok(report)
     }
 
-    pub fn spawn_descriptor(&self, node_id: &str) -> CoreResult<BridgeProcessInfo> {
+    pub fn spawn_descriptor(
+        &self,
+        node_id: &str,
+        selections: &Selections,
+    ) -> CoreResult<BridgeProcessInfo> {
         let node = self.registry.get(node_id)?;
         if node.kind != NodeKind::Bridge {
             return Err(CoreError::WrongKind {
@@ -150,6 +175,7 @@ impl<'a> BridgeExecutor<'a> {
                 actual: format!("{:?}", node.kind),
             });
         }
+        validate_selections(&node.prerequisites, selections)?;
 
         let NodePayload::Bridge {
             runner,
@@ -1,41 +1,143 @@
-use std::fs;
+use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
 
 use entity_core::error::{CoreError, CoreResult};
-use entity_core::model::{NodeKind, NodePayload, Prerequisite};
+use entity_core::model::{ComponentSpec, Node, NodeKind, NodePayload, Prerequisite};
 use entity_core::registry::Registry;
+use entity_core::schema::validate_selections;
+use entity_core::selections::Selections;
+use entity_core::workspace::WorkspaceConfig;
 use tracing::info;
-use walkdir::WalkDir;
 
-use crate::util::{ensure_writable_dir, name_variants};
+use crate::diff::{DiffReport, diff_components};
+use crate::entityignore::{IgnoreRules, SkippedFiles};
+use crate::framework::detect_framework;
+use crate::imports::{ImportRewriter, TSCONFIG};
+use crate::lock::Lockfile;
+use crate::package_json::{PACKAGE_JSON, PackageJsonReport};
+use crate::status::{StatusReport, component_status, installed_components};
+use crate::template::template_vars;
+use crate::transaction::TransactionReport;
+use crate::uninstall::{UninstallReport, uninstall_components};
+use crate::update::{UpdateReport, update_components};
+use crate::util::{ensure_writable_dir, install_root, name_variants};
+use crate::write::{WriteOptions, WritePlan, Writer};
 
-#[derive(Debug)]
+#[derive(Debug, serde::Serialize)]
 pub struct CopyItemReport {
     pub from: String,
     pub to: String,
     pub count: usize,
 }
 
-#[derive(Debug)]
+#[derive(Debug, serde::Serialize)]
 pub struct CopyReport {
     pub copied: Vec<CopyItemReport>,
     pub notes: Vec<String>,
+    /// Files that would be written, on dry runs
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub plan: Option<WritePlan>,
+    /// Installed components, dependencies before the components requiring them
+    pub components: Vec<String>,
+    /// npm packages the installed components import, name to version range
+    #[serde(rename = "npmDependencies", skip_serializing_if = "BTreeMap::is_empty")]
+    pub npm_dependencies: BTreeMap<String, String>,
+    /// Entries merged into the workspace `package.json`
+    #[serde(rename = "packageJson", skip_serializing_if = "Option::is_none")]
+    pub package_json: Option<PackageJsonReport>,
+    /// Written files whose imports of other components were rewritten
+    #[serde(rename = "importsRewritten", skip_serializing_if = "Vec::is_empty")]
+    pub imports_rewritten: Vec<String>,
+    /// Framework whose variants were installed, for nodes that ship variants
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub framework: Option<String>,
+    /// Components installed from a framework variant, name to variant
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub variants: BTreeMap<String, String>,
+    /// Pack files left out by `.entityignore` files, `exclude` or the defaults
+    #[serde(skip_serializing_if = "SkippedFiles::is_empty")]
+    pub skipped: SkippedFiles,
+    /// Outcome of the writes, absent on dry runs
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub transaction: Option<TransactionReport>,
 }
 
+/// Selection key holding the install mode (`single|multiple|all`).
+pub const MODE_KEY: &str = "selection.mode";
+/// Selection key holding the component names to install.
+pub const NAMES_KEY: &str = "selection.names";
+/// Selection key naming the framework variant to install, overriding detection.
+pub const FRAMEWORK_KEY: &str = "selection.framework";
+
 pub struct ComponentsExecutor<'a> {
     registry: &'a Registry,
+    options: WriteOptions,
 }
 
 impl<'a> ComponentsExecutor<'a> {
     pub fn new(registry: &'a Registry) -> Self {
-        Self { registry }
+        Self {
+            registry,
+            options: WriteOptions::default(),
+        }
+    }
+
+    pub fn with_options(mut self, options: WriteOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Classify every installed component file in `workspace` against its
+    /// install record and the current pack source.
+    pub fn status(&self, workspace: &Path) -> CoreResult<StatusReport> {
+        component_status(self.registry, workspace)
+    }
+
+    /// Unified diffs between the pack sources of components (all, or those
+    /// `selection.names` picks) and the workspace: see [`DiffReport`].
+    pub fn diff(
+        &self,
+        node_id: &str,
+        selections: &Selections,
+        workspace: &Path,
+    ) -> CoreResult<DiffReport> {
+        diff_components(self.registry, node_id, selections, workspace)
+    }
+
+    /// Three-way merge installed components (all, or `names`) with the current
+    /// pack: see [`UpdateReport`].
+    pub fn update(
+        &self,
+        node_id: &str,
+        names: Option<&[String]>,
+        workspace: &Path,
+    ) -> CoreResult<UpdateReport> {
+        update_components(self.registry, node_id, names, workspace, &self.options)
+    }
+
+    /// Remove the files installed for components (all, or `names`); files edited
+    /// since install are only removed with `force`.
+    pub fn uninstall(
+        &self,
+        node_id: &str,
+        names: Option<&[String]>,
+        workspace: &Path,
+        force: bool,
+    ) -> CoreResult<UninstallReport> {
+        uninstall_components(
+            self.registry,
+            node_id,
+            names,
+            workspace,
+            force,
+            &self.options,
+        )
     }
 
     pub fn install(
         &self,
         node_id: &str,
-        mode: &str,
-        names: Option<Vec<String>>,
+        selections: &Selections,
         write_root: &Path,
     ) -> CoreResult<CopyReport> {
         let node = self.registry.get(node_id)?;
@@ -49,189 +151,152 @@ impl<'a> ComponentsExecutor<'a> {
             return Err(CoreError::TargetNotFound(write_root.display().to_string()));
         }
 
-        ensure_writable_dir(write_root)
-            .map_err(|_| CoreError::TargetNotWritable(write_root.display().to_string()))?;
-
-        // Generic prerequisite enforcement for any future flat keys beyond mode/names
-        let mut missing_generic: Vec<String> = Vec::new();
-        for p in &node.prerequisites {
-            if p.optional {
-                continue;
-            }
-            if p.key != "selection.mode" && p.key != "selection.names" {
-                missing_generic.push(p.key.clone());
-            }
-        }
-        if !missing_generic.is_empty() {
-            return Err(CoreError::MissingSelections(missing_generic));
+        let to_root = install_root(self.registry, node_id, write_root)?.join("components");
+        let vars = template_vars(self.registry, node_id, selections, write_root)?;
+        let imports = ImportRewriter::load(write_root, &to_root)?;
+        let mut writer = Writer::for_node(self.registry, node_id, write_root, &self.options)?
+            .journaled("ui install", selections)
+            .with_vars(vars)
+            .with_imports(imports.clone());
+        if !writer.dry_run() {
+            ensure_writable_dir(write_root)
+                .map_err(|_| CoreError::TargetNotWritable(write_root.display().to_string()))?;
         }
 
-        // validate mode and names against prerequisites schema and node meta
-        let (allowed_modes_from_schema, allowed_names_from_schema, names_optional) =
-            parse_prereqs(&node.prerequisites);
-        if allowed_modes_from_schema
-            .as_ref()
-            .is_some_and(|modes| !modes.contains(&mode.to_string()))
-        {
-            let allowed = allowed_modes_from_schema
-                .as_ref()
-                .map(|m| m.join("|"))
-                .unwrap_or_default();
-            return Err(CoreError::InvalidSelection(format!(
-                "mode must be one of {}",
-                allowed
-            )));
-        }
+        let mode = selections
+            .get(MODE_KEY)
+            .ok_or_else(|| CoreError::MissingSelections(vec![MODE_KEY.into()]))?
+            .as_str()
+            .ok_or_else(|| CoreError::InvalidSelection("mode must be a string".into()))?;
+        let names = selected_names(selections)?;
 
-        if names.is_none() && !names_optional {
-            // if prereqs mark names required, enforce presence except for mode=all which is handled later
-            if mode == "single" || mode == "multiple" {
-                return Err(CoreError::MissingSelections(vec!["selection.names".into()]));
-            }
-        }
-
-        let all_names: Vec<String> = node
-            .meta
-            .get("names")
-            .and_then(|v| v.as_array())
-            .map(|arr| {
-                arr.iter()
-                    .filter_map(|v| v.as_str().map(|s| s.to_string()))
-                    .collect()
+        let prereqs: Vec<Prerequisite> = node
+            .prerequisites
+            .iter()
+            .cloned()
+            .map(|mut p| {
+                // mode=all selects every name, so names are never required there
+                if mode == "all" && p.key == NAMES_KEY {
+                    p.optional = true;
+                }
+                p
             })
-            .unwrap_or_default();
-        let allowed_names: Vec<String> = if let Some(schema_names) = allowed_names_from_schema {
-            schema_names
-        } else {
-            all_names.clone()
-        };
+            .collect();
+        validate_selections(&prereqs, selections)
+            .map_err(|err| names_error(err, names.as_deref().unwrap_or_default()))?;
+        let selected = select_names(node, mode, names)?;
 
-        let selected: Vec<String> = match mode {
-            "single" => {
-                let list = names.unwrap_or_default();
-                if list.len() != 1 {
-                    return Err(CoreError::MissingSelections(vec!["selection.names".into()]));
-                }
-                let n = list[0].clone();
-                if !allowed_names.contains(&n) {
-                    return Err(CoreError::InvalidNames(vec![n]));
-                }
-                vec![n]
-            }
-            "multiple" => {
-                let list = names.unwrap_or_default();
-                if list.is_empty() {
-                    return Err(CoreError::MissingSelections(vec!["selection.names".into()]));
-                }
-                let invalid: Vec<String> = list
-                    .iter()
-                    .filter(|n| !allowed_names.contains(n))
-                    .cloned()
-                    .collect();
-                if !invalid.is_empty() {
-                    return Err(CoreError::InvalidNames(invalid));
-                }
-                list
+        let NodePayload::Component {
+            source_root,
+            components: specs,
+            variants,
+            ..
+        } = &node.payload
+        else {
+            unreachable!();
+        };
+        let source_root = PathBuf::from(source_root);
+        let mut framework_notes = Vec::new();
+        let framework = choose_framework(
+            node_id,
+            variants,
+            selections,
+            write_root,
+            &mut framework_notes,
+        )?;
+        let closure = component_closure(&selected, specs);
+        let mut dependency_notes = Vec::new();
+        let mut npm_dependencies: BTreeMap<String, String> = BTreeMap::new();
+        for (name, required_by) in &closure {
+            if let Some(parent) = required_by {
+                dependency_notes.push(format!("{name} installed as a dependency of {parent}"));
             }
-            "all" => {
-                if names.is_some() {
-                    return Err(CoreError::InvalidSelection(
-                        "names must be omitted for mode all".into(),
-                    ));
+            let Some(spec) = specs.get(name) else {
+                continue;
+            };
+            for (package, range) in &spec.npm_dependencies {
+                match npm_dependencies.get(package) {
+                    Some(kept) if kept != range => dependency_notes.push(format!(
+                        "{name} wants {package}@{range}; keeping {package}@{kept}"
+                    )),
+                    Some(_) => {}
+                    None => {
+                        npm_dependencies.insert(package.clone(), range.clone());
+                    }
                 }
-                all_names
-            }
-            _ => {
-                return Err(CoreError::InvalidSelection(
-                    "mode must be one of single|multiple|all".into(),
-                ));
             }
-        };
+        }
 
         let mut report = CopyReport {
             copied: Vec::new(),
-            notes: vec!["Overwrite-on-write by default".into()],
+            notes: Vec::new(),
+            plan: None,
+            components: closure.iter().map(|(name, _)| name.clone()).collect(),
+            npm_dependencies,
+            package_json: None,
+            imports_rewritten: Vec::new(),
+            framework: framework.clone().filter(|_| !variants.is_empty()),
+            variants: BTreeMap::new(),
+            skipped: SkippedFiles::default(),
+            transaction: None,
         };
-        let to_root = write_root.join("entity-auth").join("components");
-        fs::create_dir_all(&to_root)?;
-
-        for name in selected {
-            let source_root = match &node.payload {
-                NodePayload::Component { source_root } => PathBuf::from(source_root),
-                _ => unreachable!(),
-            };
+        let mut ignore = IgnoreRules::for_node(self.registry, node_id)?;
 
+        for (name, _) in closure {
             let dest_dir = to_root.join(&name);
 
-            // Case 1: Directory-based component (existing behavior) with name variants
-            let mut handled = false;
-            for base in name_variants(&name) {
-                let dir_candidate = source_root.join(&base);
-                if dir_candidate.is_dir() {
-                    fs::create_dir_all(&dest_dir)?;
-                    let mut files_copied = 0usize;
-                    for entry in WalkDir::new(&dir_candidate)
-                        .into_iter()
-                        .filter_map(Result::ok)
+            let resolved = resolve_variant(&source_root, variants, framework.as_deref(), &name);
+            match resolved.as_ref().map(|(_, variant)| variant) {
+                Some(Some(variant)) => {
+                    report.variants.insert(name.clone(), variant.clone());
+                }
+                Some(None) => {
+                    if let Some(framework) =
+                        framework.as_deref().filter(|f| variants.contains_key(*f))
                     {
-                        let path = entry.path();
-                        if path.is_file() {
-                            let rel = path.strip_prefix(&dir_candidate).unwrap();
-                            let to_path = dest_dir.join(rel);
-                            if let Some(parent) = to_path.parent() {
-                                fs::create_dir_all(parent)?;
-                            }
-                            fs::copy(path, &to_path)?;
-                            files_copied += 1;
-                        }
+                        framework_notes.push(format!(
+                            "{name} has no {framework} variant; installed the default"
+                        ));
                     }
+                }
+                None => {}
+            }
+            match resolved.map(|(source, _)| source) {
+                // Directory-based component, copied recursively into components/<name>
+                Some(ComponentSource::Dir(dir_candidate)) => {
+                    let root = dir_candidate.parent().unwrap_or(&source_root);
+                    let files = ignore.tree_files(root, &dir_candidate, &dest_dir)?;
+                    let files_copied = writer.stage_component(&name, files)?;
                     report.copied.push(CopyItemReport {
                         from: dir_candidate.display().to_string(),
                         to: dest_dir.display().to_string(),
                         count: files_copied,
                     });
                     info!(from = %dir_candidate.display(), to = %dest_dir.display(), count = files_copied, "component copied");
-                    handled = true;
-                    break;
-                }
-            }
-            if handled {
-                continue;
-            }
-
-            // Case 2: Single-file component: <Name>.tsx or <Name>.ts under source_root with name variants
-            let mut file_candidate: Option<PathBuf> = None;
-            'outer: for base in name_variants(&name) {
-                for ext in ["tsx", "ts", "jsx", "js"] {
-                    let f = source_root.join(format!("{base}.{ext}"));
-                    if f.is_file() {
-                        file_candidate = Some(f);
-                        break 'outer;
-                    }
+                    continue;
                 }
-            }
-
-            if let Some(file_path) = file_candidate {
-                let ext = file_path
-                    .extension()
-                    .and_then(|e| e.to_str())
-                    .unwrap_or("tsx");
-                let base = file_path
-                    .file_stem()
-                    .and_then(|s| s.to_str())
-                    .unwrap_or(&name);
-                let to_path = to_root.join(format!("{base}.{ext}"));
-                if let Some(parent) = to_path.parent() {
-                    fs::create_dir_all(parent)?;
+                // Single-file component: <Name>.tsx or <Name>.ts under source_root
+                Some(ComponentSource::File(file_path)) => {
+                    let ext = file_path
+                        .extension()
+                        .and_then(|e| e.to_str())
+                        .unwrap_or("tsx");
+                    let base = file_path
+                        .file_stem()
+                        .and_then(|s| s.to_str())
+                        .unwrap_or(&name);
+                    let to_path = to_root.join(format!("{base}.{ext}"));
+                    let files_copied = writer
+                        .stage_component(&name, vec![(file_path.clone(), to_path.clone())])?;
+                    report.copied.push(CopyItemReport {
+                        from: file_path.display().to_string(),
+                        to: to_path.display().to_string(),
+                        count: files_copied,
+                    });
+                    info!(from = %file_path.display(), to = %to_path.display(), count = files_copied, "single-file component copied");
+                    continue;
                 }
-                fs::copy(&file_path, &to_path)?;
-                report.copied.push(CopyItemReport {
-                    from: file_path.display().to_string(),
-                    to: to_path.display().to_string(),
-                    count: 1,
-                });
-                info!(from = %file_path.display(), to = %to_path.display(), count = 1, "single-file component copied");
-                continue;
+                None => {}
             }
 
             // Neither directory nor single-file found
@@ -239,42 +304,292 @@ impl<'a> ComponentsExecutor<'a> {
                 source_root.join(&name).display().to_string(),
             ));
         }
+        let lock = Lockfile::load(write_root)?;
+        let config = WorkspaceConfig::load(write_root)?;
+        let installed = installed_components(
+            self.registry,
+            &lock,
+            node_id,
+            &self.registry.install_root(node_id, &config),
+            None,
+        )?;
+        for name in &report.components {
+            if let Some(keys) = installed.get(name) {
+                writer.prune(keys);
+            }
+        }
+        report.imports_rewritten = writer.rewritten_imports();
+        report.skipped = ignore.skipped();
+
+        // npm packages of the installed components join the node's own entries
+        let mut package = node.package_json.clone().unwrap_or_default();
+        for (name, range) in &report.npm_dependencies {
+            package
+                .dependencies
+                .entry(name.clone())
+                .or_insert_with(|| range.clone());
+        }
+        if !package.is_empty() {
+            report.package_json = Some(writer.merge_package_json(&package)?);
+        }
+        (report.notes, report.plan, report.transaction) = writer.commit()?;
+        report.notes.extend(framework_notes);
+        report.notes.extend(dependency_notes);
+        if imports.alias().is_none() && !report.imports_rewritten.is_empty() {
+            report.notes.push(format!(
+                "No {TSCONFIG} path alias covers {}; component imports were written as relative paths",
+                to_root.display()
+            ));
+        }
         Ok(report)
     }
 }
 
-fn parse_prereqs(prereqs: &Vec<Prerequisite>) -> (Option<Vec<String>>, Option<Vec<String>>, bool) {
-    let mut modes: Option<Vec<String>> = None;
-    let mut names: Option<Vec<String>> = None;
-    let mut names_optional = true;
-    for p in prereqs {
-        if p.key == "selection.mode" {
-            if let Some(arr) = p.schema.get("enum").and_then(|v| v.as_array()) {
-                let vals = arr
-                    .iter()
-                    .filter_map(|v| v.as_str().map(|s| s.to_string()))
-                    .collect::<Vec<_>>();
-                if !vals.is_empty() {
-                    modes = Some(vals);
-                }
+/// Parse `selection.names`, if given.
+pub(crate) fn selected_names(selections: &Selections) -> CoreResult<Option<Vec<String>>> {
+    selections
+        .get(NAMES_KEY)
+        .map(|value| {
+            serde_json::from_value(value.clone()).map_err(|_| {
+                CoreError::InvalidSelection("names must be an array of strings".into())
+            })
+        })
+        .transpose()
+}
+
+/// Component names `mode` (`single|multiple|all`) selects among those the node
+/// declares (its `selection.names` schema, else `meta.names`).
+pub(crate) fn select_names(
+    node: &Node,
+    mode: &str,
+    names: Option<Vec<String>>,
+) -> CoreResult<Vec<String>> {
+    let allowed_names_from_schema = schema_names(&node.prerequisites);
+
+    let all_names: Vec<String> = node
+        .meta
+        .get("names")
+        .and_then(|v| v.as_array())
+        .map(|arr| {
+            arr.iter()
+                .filter_map(|v| v.as_str().map(|s| s.to_string()))
+                .collect()
+        })
+        .unwrap_or_default();
+    let allowed_names: Vec<String> = if let Some(schema_names) = allowed_names_from_schema {
+        schema_names
+    } else {
+        all_names.clone()
+    };
+
+    Ok(match mode {
+        "single" => {
+            let list = names.unwrap_or_default();
+            if list.len() != 1 {
+                return Err(CoreError::MissingSelections(vec![NAMES_KEY.into()]));
             }
-        } else if p.key == "selection.names" {
-            names_optional = p.optional;
-            if let Some(arr) = p
-                .schema
-                .get("items")
-                .and_then(|it| it.get("enum"))
-                .and_then(|v| v.as_array())
-            {
-                let vals = arr
-                    .iter()
-                    .filter_map(|v| v.as_str().map(|s| s.to_string()))
-                    .collect::<Vec<_>>();
-                if !vals.is_empty() {
-                    names = Some(vals);
-                }
+            let n = list[0].clone();
+            if !allowed_names.contains(&n) {
+                return Err(CoreError::InvalidNames(vec![n]));
+            }
+            vec![n]
+        }
+        "multiple" => {
+            let list = names.unwrap_or_default();
+            if list.is_empty() {
+                return Err(CoreError::MissingSelections(vec![NAMES_KEY.into()]));
+            }
+            let invalid: Vec<String> = list
+                .iter()
+                .filter(|n| !allowed_names.contains(n))
+                .cloned()
+                .collect();
+            if !invalid.is_empty() {
+                return Err(CoreError::InvalidNames(invalid));
+            }
+            list
+        }
+        "all" => {
+            if names.is_some() {
+                return Err(CoreError::InvalidSelection(
+                    "names must be omitted for mode all".into(),
+                ));
+            }
+            all_names
+        }
+        _ => {
+            return Err(CoreError::InvalidSelection(
+                "mode must be one of single|multiple|all".into(),
+            ));
+        }
+    })
+}
+
+/// The variant to install: the `selection.framework` override, which must name a
+/// declared variant, else the framework detected in the workspace. Nodes without
+/// variants ignore detection.
+pub(crate) fn choose_framework(
+    node_id: &str,
+    variants: &BTreeMap<String, String>,
+    selections: &Selections,
+    workspace: &Path,
+    notes: &mut Vec<String>,
+) -> CoreResult<Option<String>> {
+    Ok(match selections.get(FRAMEWORK_KEY) {
+        Some(value) => {
+            let framework = value
+                .as_str()
+                .ok_or_else(|| CoreError::InvalidSelection("framework must be a string".into()))?;
+            if variants.is_empty() {
+                return Err(CoreError::InvalidSelection(format!(
+                    "{node_id} has no framework variants"
+                )));
             }
+            if !variants.contains_key(framework) {
+                let known: Vec<&str> = variants.keys().map(String::as_str).collect();
+                return Err(CoreError::InvalidSelection(format!(
+                    "framework must be one of {}",
+                    known.join("|")
+                )));
+            }
+            Some(framework.to_string())
+        }
+        None if variants.is_empty() => None,
+        None => {
+            let detected = detect_framework(workspace);
+            match detected {
+                Some(framework) if variants.contains_key(framework) => notes
+                    .push(format!("Framework {framework} detected from {PACKAGE_JSON}")),
+                Some(framework) => notes.push(format!(
+                    "Framework {framework} detected from {PACKAGE_JSON}; no {framework} variants, installed the default sources"
+                )),
+                None => notes.push(format!(
+                    "No framework detected from {PACKAGE_JSON}; installed the default sources"
+                )),
+            }
+            detected.map(str::to_string)
+        }
+    })
+}
+
+/// `selected` plus everything it requires through `payload.components`, each
+/// component after its dependencies, paired with the component that pulled it in
+/// (`None` for selected ones). Cycles are cut where they close.
+pub(crate) fn component_closure(
+    selected: &[String],
+    specs: &BTreeMap<String, ComponentSpec>,
+) -> Vec<(String, Option<String>)> {
+    fn visit(
+        name: &str,
+        required_by: Option<&str>,
+        specs: &BTreeMap<String, ComponentSpec>,
+        visiting: &mut Vec<String>,
+        out: &mut Vec<(String, Option<String>)>,
+    ) {
+        if visiting.iter().any(|n| n == name) || out.iter().any(|(n, _)| n == name) {
+            return;
+        }
+        visiting.push(name.to_string());
+        for dependency in specs
+            .get(name)
+            .map(|s| s.requires.as_slice())
+            .unwrap_or_default()
+        {
+            visit(dependency, Some(name), specs, visiting, out);
         }
+        visiting.pop();
+        out.push((name.to_string(), required_by.map(str::to_string)));
     }
-    (modes, names, names_optional)
+
+    let mut out = Vec::new();
+    let mut visiting = Vec::new();
+    for name in selected {
+        visit(name, None, specs, &mut visiting, &mut out);
+    }
+    // Selected components stay selected even when another one requires them
+    for (name, required_by) in &mut out {
+        if selected.contains(name) {
+            *required_by = None;
+        }
+    }
+    out
+}
+
+/// Where a component's source lives under a node's `sourceRoot`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ComponentSource {
+    Dir(PathBuf),
+    File(PathBuf),
+}
+
+/// Resolve `name` in the `framework` variant root when the node ships that
+/// variant and it has the component, otherwise under `sourceRoot`. Returns the
+/// variant used, `None` for the default sources.
+pub(crate) fn resolve_variant(
+    source_root: &Path,
+    variants: &BTreeMap<String, String>,
+    framework: Option<&str>,
+    name: &str,
+) -> Option<(ComponentSource, Option<String>)> {
+    if let Some((framework, root)) = framework.and_then(|f| variants.get_key_value(f))
+        && let Some(source) = resolve_component(Path::new(root), name)
+    {
+        return Some((source, Some(framework.clone())));
+    }
+    resolve_component(source_root, name).map(|source| (source, None))
+}
+
+/// Resolve a component name through `name_variants`: a directory wins over a
+/// single `.tsx`/`.ts`/`.jsx`/`.js`/`.vue`/`.svelte` file.
+pub fn resolve_component(source_root: &Path, name: &str) -> Option<ComponentSource> {
+    let variants = name_variants(name);
+    for base in &variants {
+        let dir = source_root.join(base);
+        if dir.is_dir() {
+            return Some(ComponentSource::Dir(dir));
+        }
+    }
+    for base in &variants {
+        for ext in ["tsx", "ts", "jsx", "js", "vue", "svelte"] {
+            let file = source_root.join(format!("{base}.{ext}"));
+            if file.is_file() {
+                return Some(ComponentSource::File(file));
+            }
+        }
+    }
+    None
+}
+
+/// Unknown component names keep their dedicated `invalidNames` report.
+fn names_error(err: CoreError, names: &[String]) -> CoreError {
+    let CoreError::SchemaViolations(violations) = &err else {
+        return err;
+    };
+    let names_only = violations
+        .iter()
+        .all(|v| v.keyword == "enum" && v.path.starts_with("/selection/names/"));
+    if !names_only {
+        return err;
+    }
+    CoreError::InvalidNames(
+        violations
+            .iter()
+            .filter_map(|v| v.path.rsplit('/').next()?.parse::<usize>().ok())
+            .filter_map(|i| names.get(i).cloned())
+            .collect(),
+    )
+}
+
+/// Names allowed by the `selection.names` schema (`items.enum`), if it declares any.
+fn schema_names(prereqs: &[Prerequisite]) -> Option<Vec<String>> {
+    let p = prereqs.iter().find(|p| p.key == NAMES_KEY)?;
+    let vals = p
+        .schema
+        .get("items")
+        .and_then(|it| it.get("enum"))
+        .and_then(|v| v.as_array())?
+        .iter()
+        .filter_map(|v| v.as_str().map(|s| s.to_string()))
+        .collect::<Vec<_>>();
+    (!vals.is_empty()).then_some(vals)
 }
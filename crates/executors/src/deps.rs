@@ -0,0 +1,142 @@
+use std::path::Path;
+
+use entity_core::error::{CoreError, CoreResult};
+use entity_core::model::{Node, NodeKind};
+use entity_core::registry::Registry;
+use entity_core::selections::Selections;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::bridge::{BridgeExecutor, BridgeScaffoldReport};
+use crate::components::{ComponentsExecutor, CopyReport, MODE_KEY, NAMES_KEY};
+use crate::docs::DocsExecutor;
+use crate::setup::{SetupExecutor, SetupReport};
+use crate::write::WriteOptions;
+
+#[derive(Debug, Serialize)]
+pub struct DependencyReport {
+    /// Node ids in the order they were applied, dependencies first
+    pub order: Vec<String>,
+    pub steps: Vec<StepReport>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct StepReport {
+    pub node: String,
+    pub kind: NodeKind,
+    pub result: StepResult,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+pub enum StepResult {
+    Doc { content: String },
+    Components(CopyReport),
+    Setup(SetupReport),
+    Bridge(BridgeScaffoldReport),
+}
+
+/// Applies a node after everything it `requires`, in topological order.
+pub struct DependencyExecutor<'a> {
+    registry: &'a Registry,
+    options: WriteOptions,
+}
+
+impl<'a> DependencyExecutor<'a> {
+    pub fn new(registry: &'a Registry) -> Self {
+        Self {
+            registry,
+            options: WriteOptions::default(),
+        }
+    }
+
+    /// Write options passed to every step.
+    pub fn with_options(mut self, options: WriteOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Run `node_id` and its dependency closure against `workspace`, stopping at
+    /// the first failure. Doc nodes are read, component nodes installed, setup
+    /// nodes run and bridge nodes scaffolded. Steps are applied one by one, so a
+    /// failure after some were written fails with `PartiallyApplied`, carrying
+    /// the report of those steps and the error of the failing one.
+    ///
+    /// `selections` apply to every node; component dependencies other than the
+    /// target install all of their components.
+    pub fn run(
+        &self,
+        node_id: &str,
+        selections: &Selections,
+        workspace: &Path,
+    ) -> CoreResult<DependencyReport> {
+        let order = self.registry.dependency_order(node_id)?;
+        let mut report = DependencyReport {
+            order: order.iter().map(|n| n.id.clone()).collect(),
+            steps: Vec::with_capacity(order.len()),
+        };
+        for node in &order {
+            let result = match self.step(node, node_id, selections, workspace) {
+                Ok(result) => result,
+                Err(err) if self.options.dry_run || !report.wrote() => return Err(err),
+                Err(err) => {
+                    return Err(CoreError::PartiallyApplied {
+                        failed: node.id.clone(),
+                        applied: report.steps.iter().map(|s| s.node.clone()).collect(),
+                        report: serde_json::to_value(&report)?,
+                        source: Box::new(err),
+                    });
+                }
+            };
+            report.steps.push(StepReport {
+                node: node.id.clone(),
+                kind: node.kind.clone(),
+                result,
+            });
+        }
+        Ok(report)
+    }
+
+    fn step(
+        &self,
+        node: &Node,
+        target: &str,
+        selections: &Selections,
+        workspace: &Path,
+    ) -> CoreResult<StepResult> {
+        Ok(match node.kind {
+            NodeKind::Doc => StepResult::Doc {
+                content: DocsExecutor::new(self.registry).read(&node.id, selections)?,
+            },
+            NodeKind::Component => {
+                let exec =
+                    ComponentsExecutor::new(self.registry).with_options(self.options.clone());
+                let report = if node.id == target {
+                    exec.install(&node.id, selections, workspace)?
+                } else {
+                    let mut all = selections.clone().with(MODE_KEY, "all");
+                    all.set(NAMES_KEY, Value::Null);
+                    exec.install(&node.id, &all, workspace)?
+                };
+                StepResult::Components(report)
+            }
+            NodeKind::Setup => StepResult::Setup(
+                SetupExecutor::new(self.registry)
+                    .with_options(self.options.clone())
+                    .run(&node.id, selections, workspace)?,
+            ),
+            NodeKind::Bridge => StepResult::Bridge(
+                BridgeExecutor::new(self.registry)
+                    .with_options(self.options.clone())
+                    .scaffold(&node.id, selections, workspace)?,
+            ),
+        })
+    }
+}
+
+impl DependencyReport {
+    /// Some step so far wrote to the workspace (docs are only read).
+    fn wrote(&self) -> bool {
+        self.steps.iter().any(|s| s.kind != NodeKind::Doc)
+    }
+}
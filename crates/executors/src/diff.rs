@@ -0,0 +1,288 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use diffy::{Line, create_patch};
+use entity_core::error::{CoreError, CoreResult};
+use entity_core::model::NodePayload;
+use entity_core::registry::Registry;
+use entity_core::selections::Selections;
+use serde::Serialize;
+
+use crate::components::{
+    ComponentSource, choose_framework, component_closure, resolve_variant, select_names,
+    selected_names,
+};
+use crate::entityignore::IgnoreRules;
+use crate::imports::ImportRewriter;
+use crate::lock::{Lockfile, relative_key};
+use crate::template::{render_file, selection_vars, template_vars};
+use crate::util::install_root;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DiffStatus {
+    /// Differs between the workspace and the pack
+    Modified,
+    /// In the pack only; an install would create it
+    Added,
+    /// In the workspace only
+    Removed,
+}
+
+/// One unified diff hunk, the workspace copy being the old side.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiffHunk {
+    /// 1-based; 0 when the old side is empty
+    pub old_start: usize,
+    pub old_lines: usize,
+    pub new_start: usize,
+    pub new_lines: usize,
+    /// Lines prefixed with ` `, `-` or `+`, without their newline. A line that
+    /// lacks one in its file is followed by `\ No newline at end of file`, as in
+    /// unified diffs.
+    pub lines: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FileDiff {
+    /// Workspace-relative path
+    pub path: String,
+    pub status: DiffStatus,
+    /// Either side is not UTF-8; no hunks are listed
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub binary: bool,
+    pub hunks: Vec<DiffHunk>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DiffReport {
+    /// Compared components, dependencies before the components requiring them
+    pub components: Vec<String>,
+    /// Files that differ, sorted by path
+    pub files: Vec<FileDiff>,
+    /// Files identical on both sides
+    pub unchanged: usize,
+    pub notes: Vec<String>,
+}
+
+impl DiffReport {
+    /// Every file diff as one plain-text unified patch, applicable to the
+    /// workspace with `patch -p1` or `git apply`.
+    pub fn patch(&self) -> String {
+        let mut out = String::new();
+        for file in &self.files {
+            let old = match file.status {
+                DiffStatus::Added => "/dev/null".to_string(),
+                _ => format!("a/{}", file.path),
+            };
+            let new = match file.status {
+                DiffStatus::Removed => "/dev/null".to_string(),
+                _ => format!("b/{}", file.path),
+            };
+            if file.binary {
+                let _ = writeln!(out, "Binary files {old} and {new} differ");
+                continue;
+            }
+            let _ = writeln!(out, "--- {old}\n+++ {new}");
+            for hunk in &file.hunks {
+                let _ = writeln!(
+                    out,
+                    "@@ -{} +{} @@",
+                    range(hunk.old_start, hunk.old_lines),
+                    range(hunk.new_start, hunk.new_lines)
+                );
+                for line in &hunk.lines {
+                    let _ = writeln!(out, "{line}");
+                }
+            }
+        }
+        out
+    }
+}
+
+/// `start,len`, leaving out a length of 1 as unified diffs do.
+fn range(start: usize, len: usize) -> String {
+    if len == 1 {
+        start.to_string()
+    } else {
+        format!("{start},{len}")
+    }
+}
+
+/// Compare the pack sources of the selected components (all without
+/// `selection.names`, plus the components they require) with the workspace
+/// components directory. Pack files are rendered as an install would write them:
+/// templates with the variables recorded at install (the selections given here
+/// win over them), imports rewritten. Files the ignore rules leave out are not compared.
+pub(crate) fn diff_components(
+    registry: &Registry,
+    node_id: &str,
+    selections: &Selections,
+    workspace: &Path,
+) -> CoreResult<DiffReport> {
+    let node = registry.get(node_id)?;
+    let NodePayload::Component {
+        source_root,
+        components: specs,
+        variants,
+        ..
+    } = &node.payload
+    else {
+        return Err(CoreError::WrongKind {
+            expected: "component".into(),
+            actual: format!("{:?}", node.kind),
+        });
+    };
+    let names = selected_names(selections)?;
+    let mode = if names.is_some() { "multiple" } else { "all" };
+    let selected = select_names(node, mode, names)?;
+
+    let mut report = DiffReport {
+        components: Vec::new(),
+        files: Vec::new(),
+        unchanged: 0,
+        notes: Vec::new(),
+    };
+    let framework = choose_framework(node_id, variants, selections, workspace, &mut report.notes)?;
+    let to_root = install_root(registry, node_id, workspace)?.join("components");
+    // Recorded variables win over pack defaults and workspace facts, not over
+    // the selections of this call
+    let mut vars = template_vars(registry, node_id, selections, workspace)?;
+    for (_, entry) in Lockfile::load(workspace)?.node_files(node_id) {
+        vars.extend(entry.vars.clone());
+    }
+    vars.extend(selection_vars(selections));
+    let imports = ImportRewriter::load(workspace, &to_root)?;
+    let mut ignore = IgnoreRules::for_node(registry, node_id)?;
+    // The workspace side only knows the node's `exclude` patterns and the defaults
+    let mut workspace_ignore = IgnoreRules::new(None, node.payload.exclude().to_vec());
+
+    let mut pairs: BTreeMap<String, Sides> = BTreeMap::new();
+    let mut uncompared = BTreeSet::new();
+    for (name, _) in component_closure(&selected, specs) {
+        let resolved = resolve_variant(
+            Path::new(source_root),
+            variants,
+            framework.as_deref(),
+            &name,
+        );
+        let (pack, installed): (Vec<(PathBuf, PathBuf)>, Vec<PathBuf>) =
+            match resolved.map(|(source, _)| source) {
+                Some(ComponentSource::Dir(dir)) => {
+                    let root = dir.parent().unwrap_or(Path::new(source_root));
+                    let dest = to_root.join(&name);
+                    let installed = if dest.is_dir() {
+                        workspace_ignore.files(&to_root, &dest)?
+                    } else {
+                        Vec::new()
+                    };
+                    (ignore.tree_files(root, &dir, &dest)?, installed)
+                }
+                Some(ComponentSource::File(file)) => {
+                    let to = to_root.join(file.file_name().unwrap_or_default());
+                    let installed = to.is_file().then(|| to.clone()).into_iter().collect();
+                    (vec![(file, to)], installed)
+                }
+                None => {
+                    return Err(CoreError::MissingSource(
+                        Path::new(source_root).join(&name).display().to_string(),
+                    ));
+                }
+            };
+        for (from, to) in pack {
+            let mut file = render_file(&from, &to, workspace, &vars)?;
+            imports.apply(&from, &mut file)?;
+            let key = relative_key(&file.to, workspace);
+            if !file.undefined.is_empty() {
+                let names: Vec<String> = file.undefined.into_iter().collect();
+                report.notes.push(format!(
+                    "{key} uses undefined template variables ({}); not compared",
+                    names.join(", ")
+                ));
+                uncompared.insert(key);
+                continue;
+            }
+            let content = match file.content {
+                Some(content) => content,
+                None => fs::read(&from)?,
+            };
+            pairs.entry(key).or_default().pack = Some(content);
+        }
+        for path in installed {
+            let key = relative_key(&path, workspace);
+            pairs.entry(key).or_default().installed = Some(fs::read(&path)?);
+        }
+        report.components.push(name);
+    }
+
+    for (path, Sides { pack, installed }) in pairs {
+        if uncompared.contains(&path) {
+            continue;
+        }
+        let status = match (&pack, &installed) {
+            (Some(pack), Some(installed)) if pack == installed => {
+                report.unchanged += 1;
+                continue;
+            }
+            (Some(_), Some(_)) => DiffStatus::Modified,
+            (Some(_), None) => DiffStatus::Added,
+            _ => DiffStatus::Removed,
+        };
+        let old = installed.unwrap_or_default();
+        let new = pack.unwrap_or_default();
+        let (Ok(old), Ok(new)) = (String::from_utf8(old), String::from_utf8(new)) else {
+            report.files.push(FileDiff {
+                path,
+                status,
+                binary: true,
+                hunks: Vec::new(),
+            });
+            continue;
+        };
+        report.files.push(FileDiff {
+            path,
+            status,
+            binary: false,
+            hunks: hunks(&old, &new),
+        });
+    }
+    Ok(report)
+}
+
+/// Content of one workspace-relative path on each side, where it exists.
+#[derive(Default)]
+struct Sides {
+    pack: Option<Vec<u8>>,
+    installed: Option<Vec<u8>>,
+}
+
+fn hunks(old: &str, new: &str) -> Vec<DiffHunk> {
+    create_patch(old, new)
+        .hunks()
+        .iter()
+        .map(|hunk| {
+            let mut lines = Vec::new();
+            for line in hunk.lines() {
+                let (sign, text) = match line {
+                    Line::Context(text) => (' ', *text),
+                    Line::Delete(text) => ('-', *text),
+                    Line::Insert(text) => ('+', *text),
+                };
+                lines.push(format!("{sign}{}", text.strip_suffix('\n').unwrap_or(text)));
+                if !text.ends_with('\n') {
+                    lines.push("\\ No newline at end of file".into());
+                }
+            }
+            DiffHunk {
+                old_start: hunk.old_range().start(),
+                old_lines: hunk.old_range().len(),
+                new_start: hunk.new_range().start(),
+                new_lines: hunk.new_range().len(),
+                lines,
+            }
+        })
+        .collect()
+}
@@ -4,6 +4,8 @@ use std::io::Read;
 use entity_core::error::{CoreError, CoreResult};
 use entity_core::model::{NodeKind, NodePayload};
 use entity_core::registry::Registry;
+use entity_core::schema::validate_selections;
+use entity_core::selections::Selections;
 
 pub struct DocsExecutor<'a> {
     registry: &'a Registry,
@@ -14,7 +16,7 @@ impl<'a> DocsExecutor<'a> {
         Self { registry }
     }
 
-    pub fn read(&self, node_id: &str) -> CoreResult<String> {
+    pub fn read(&self, node_id: &str, selections: &Selections) -> CoreResult<String> {
         let node = self.registry.get(node_id)?;
         if node.kind != NodeKind::Doc {
             return Err(CoreError::WrongKind {
@@ -22,6 +24,7 @@ impl<'a> DocsExecutor<'a> {
                 actual: format!("{:?}", node.kind),
             });
         }
+        validate_selections(&node.prerequisites, selections)?;
         let path = match &node.payload {
             NodePayload::Doc { content_path } => content_path,
             _ => unreachable!(),
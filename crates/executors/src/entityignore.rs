@@ -0,0 +1,252 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+
+use entity_core::error::{CoreError, CoreResult};
+use entity_core::registry::Registry;
+use ignore::Match;
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use serde::Serialize;
+use walkdir::WalkDir;
+
+use crate::lock::relative_key;
+
+/// Gitignore-syntax file excluding pack files from every copy. Each one applies
+/// to its directory and below, from the pack root down.
+pub const IGNORE_FILE: &str = ".entityignore";
+
+/// Never copied, unless an ignore file re-includes them with `!`.
+const DEFAULT_IGNORES: [&str; 5] = [
+    ".DS_Store",
+    "Thumbs.db",
+    ".git/",
+    "node_modules/",
+    IGNORE_FILE,
+];
+
+/// One rule that skipped pack files.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SkipRule {
+    /// Ignore file declaring the rule (relative to the pack root), `exclude` for
+    /// the node payload, or `default`
+    pub source: String,
+    pub pattern: String,
+    /// Files and directories skipped; a directory counts once
+    pub count: usize,
+}
+
+/// What ignore rules kept out of a copy.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct SkippedFiles {
+    pub count: usize,
+    pub rules: Vec<SkipRule>,
+}
+
+impl SkippedFiles {
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+}
+
+/// Ignore files, `exclude` patterns and defaults of one node, tallying what they skip.
+#[derive(Debug)]
+pub struct IgnoreRules {
+    /// Outermost directory whose ignore file applies (the pack root)
+    top: Option<PathBuf>,
+    exclude: Vec<String>,
+    /// Parsed ignore file per directory, `None` where there is none
+    files: HashMap<PathBuf, Option<Gitignore>>,
+    /// `exclude` and default matchers per node root
+    roots: HashMap<PathBuf, (Gitignore, Gitignore)>,
+    skipped: BTreeMap<(String, String), usize>,
+}
+
+impl IgnoreRules {
+    /// Rules reading ignore files up to `top` (else only within the copied root)
+    /// plus the node's `exclude` patterns.
+    pub fn new(top: Option<PathBuf>, exclude: Vec<String>) -> Self {
+        Self {
+            top,
+            exclude,
+            files: HashMap::new(),
+            roots: HashMap::new(),
+            skipped: BTreeMap::new(),
+        }
+    }
+
+    pub(crate) fn for_node(registry: &Registry, node_id: &str) -> CoreResult<Self> {
+        let node = registry.get(node_id)?;
+        let top = registry.pack_for(node_id).map(|p| PathBuf::from(&p.root));
+        Ok(Self::new(top, node.payload.exclude().to_vec()))
+    }
+
+    /// Every file under `from_root` that no rule ignores, paired with its
+    /// destination under `to_root`. `root` is the node directory `exclude`
+    /// patterns are relative to.
+    pub(crate) fn tree_files(
+        &mut self,
+        root: &Path,
+        from_root: &Path,
+        to_root: &Path,
+    ) -> CoreResult<Vec<(PathBuf, PathBuf)>> {
+        Ok(self
+            .files(root, from_root)?
+            .into_iter()
+            .map(|path| {
+                let to = to_root.join(path.strip_prefix(from_root).unwrap());
+                (path, to)
+            })
+            .collect())
+    }
+
+    /// Every file under `dir` that no rule ignores, sorted by name. Ignored
+    /// directories are not descended into.
+    pub fn files(&mut self, root: &Path, dir: &Path) -> CoreResult<Vec<PathBuf>> {
+        let mut files = Vec::new();
+        let mut entries = WalkDir::new(dir).sort_by_file_name().into_iter();
+        while let Some(entry) = entries.next() {
+            let Ok(entry) = entry else { continue };
+            if entry.depth() == 0 {
+                continue;
+            }
+            let is_dir = entry.file_type().is_dir();
+            if let Some(rule) = self.matched(root, entry.path(), is_dir)? {
+                *self.skipped.entry(rule).or_default() += 1;
+                if is_dir {
+                    entries.skip_current_dir();
+                }
+                continue;
+            }
+            if entry.path().is_file() {
+                files.push(entry.into_path());
+            }
+        }
+        Ok(files)
+    }
+
+    /// Summary of everything skipped so far.
+    pub fn skipped(&self) -> SkippedFiles {
+        SkippedFiles {
+            count: self.skipped.values().sum(),
+            rules: self
+                .skipped
+                .iter()
+                .map(|((source, pattern), count)| SkipRule {
+                    source: source.clone(),
+                    pattern: pattern.clone(),
+                    count: *count,
+                })
+                .collect(),
+        }
+    }
+
+    /// `(source, pattern)` of the rule ignoring `path`. The nearest ignore file
+    /// with a matching line decides (a `!` line re-includes), then `exclude`,
+    /// then the defaults.
+    fn matched(
+        &mut self,
+        root: &Path,
+        path: &Path,
+        is_dir: bool,
+    ) -> CoreResult<Option<(String, String)>> {
+        let top = self
+            .top
+            .clone()
+            .filter(|top| path.starts_with(top))
+            .unwrap_or_else(|| root.to_path_buf());
+        let dirs: Vec<PathBuf> = path
+            .ancestors()
+            .skip(1)
+            .take_while(|dir| dir.starts_with(&top))
+            .map(Path::to_path_buf)
+            .collect();
+        for dir in dirs {
+            let Some(ignore) = self.ignore_file(&dir)? else {
+                continue;
+            };
+            match ignore.matched(path, is_dir) {
+                Match::Ignore(glob) => {
+                    let source = relative_key(&dir.join(IGNORE_FILE), &top);
+                    return Ok(Some((source, glob.original().to_string())));
+                }
+                Match::Whitelist(_) => return Ok(None),
+                Match::None => {}
+            }
+        }
+
+        let (exclude, defaults) = self.root_rules(root)?;
+        for (source, matcher) in [("exclude", exclude), ("default", defaults)] {
+            match matcher.matched(path, is_dir) {
+                Match::Ignore(glob) => {
+                    return Ok(Some((source.to_string(), glob.original().to_string())));
+                }
+                Match::Whitelist(_) => return Ok(None),
+                Match::None => {}
+            }
+        }
+        Ok(None)
+    }
+
+    fn ignore_file(&mut self, dir: &Path) -> CoreResult<&Option<Gitignore>> {
+        if !self.files.contains_key(dir) {
+            let path = dir.join(IGNORE_FILE);
+            let ignore = if path.is_file() {
+                let mut builder = GitignoreBuilder::new(dir);
+                if let Some(err) = builder.add(&path) {
+                    return Err(invalid(&path.display().to_string(), err));
+                }
+                Some(
+                    builder
+                        .build()
+                        .map_err(|err| invalid(&path.display().to_string(), err))?,
+                )
+            } else {
+                None
+            };
+            self.files.insert(dir.to_path_buf(), ignore);
+        }
+        Ok(&self.files[dir])
+    }
+
+    fn root_rules(&mut self, root: &Path) -> CoreResult<(&Gitignore, &Gitignore)> {
+        if !self.roots.contains_key(root) {
+            let exclude = matcher(root, self.exclude.iter().map(String::as_str), "exclude")?;
+            let defaults = matcher(root, DEFAULT_IGNORES, "default ignore")?;
+            self.roots.insert(root.to_path_buf(), (exclude, defaults));
+        }
+        let (exclude, defaults) = &self.roots[root];
+        Ok((exclude, defaults))
+    }
+}
+
+/// Why an ignore file is invalid, if it is (naming the offending lines).
+pub fn check_ignore_file(path: &Path) -> Option<String> {
+    GitignoreBuilder::new(path.parent().unwrap_or(Path::new("")))
+        .add(path)
+        .map(|err| err.to_string())
+}
+
+/// Why an `exclude` pattern is invalid, if it is.
+pub fn check_exclude_pattern(pattern: &str) -> Option<String> {
+    GitignoreBuilder::new("")
+        .add_line(None, pattern)
+        .err()
+        .map(|err| err.to_string())
+}
+
+fn matcher<'a>(
+    root: &Path,
+    patterns: impl IntoIterator<Item = &'a str>,
+    what: &str,
+) -> CoreResult<Gitignore> {
+    let mut builder = GitignoreBuilder::new(root);
+    for pattern in patterns {
+        builder
+            .add_line(None, pattern)
+            .map_err(|err| invalid(&format!("{what} pattern {pattern:?}"), err))?;
+    }
+    builder.build().map_err(|err| invalid(what, err))
+}
+
+fn invalid(what: &str, err: ignore::Error) -> CoreError {
+    CoreError::InvalidDescriptor(format!("invalid {what}: {err}"))
+}
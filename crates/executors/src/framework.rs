@@ -0,0 +1,37 @@
+use std::fs;
+use std::path::Path;
+
+use serde_json::Value;
+
+use crate::package_json::PACKAGE_JSON;
+
+/// Packages that identify a framework, meta-frameworks before the libraries they
+/// build on, so a Next.js app is `react` and a SvelteKit app `svelte`.
+const FRAMEWORK_MARKERS: [(&str, &str); 10] = [
+    ("next", "react"),
+    ("@sveltejs/kit", "svelte"),
+    ("nuxt", "vue"),
+    ("@solidjs/start", "solid"),
+    ("svelte", "svelte"),
+    ("vue", "vue"),
+    ("solid-js", "solid"),
+    ("react", "react"),
+    ("preact", "preact"),
+    ("typescript", "ts"),
+];
+
+/// Framework of the workspace, from the `dependencies` and `devDependencies` of
+/// its `package.json`; `None` without one or when nothing is recognized.
+pub(crate) fn detect_framework(workspace: &Path) -> Option<&'static str> {
+    let text = fs::read_to_string(workspace.join(PACKAGE_JSON)).ok()?;
+    let package: Value = serde_json::from_str(&text).ok()?;
+    let declared = |name: &str| {
+        ["dependencies", "devDependencies"]
+            .iter()
+            .any(|section| package.get(section).and_then(|s| s.get(name)).is_some())
+    };
+    FRAMEWORK_MARKERS
+        .iter()
+        .find(|(marker, _)| declared(marker))
+        .map(|(_, framework)| *framework)
+}
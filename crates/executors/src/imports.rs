@@ -0,0 +1,300 @@
+use std::fs;
+use std::iter::Peekable;
+use std::path::{Path, PathBuf};
+use std::str::Chars;
+use std::sync::LazyLock;
+
+use entity_core::error::{CoreError, CoreResult};
+use regex::{Captures, Regex};
+use serde_json::{Map, Value};
+
+use crate::lock::relative_key;
+use crate::template::RenderedFile;
+use crate::util::normalize;
+
+/// Import prefix pack sources may use for another component, e.g.
+/// `$components/Button`; it is always rewritten on install.
+pub const COMPONENTS_PLACEHOLDER: &str = "$components/";
+
+/// Workspace-relative TypeScript config read for path aliases.
+pub const TSCONFIG: &str = "tsconfig.json";
+
+const SCRIPT_EXTENSIONS: [&str; 4] = ["ts", "tsx", "js", "jsx"];
+
+/// `extends` chains longer than this are treated as cycles.
+const MAX_EXTENDS_DEPTH: usize = 16;
+
+/// Module specifiers of `import … from`, `export … from`, `import "…"`,
+/// `import("…")` and `require("…")`.
+static SPECIFIER: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"(\bfrom\s*|\bimport\s*\(?\s*|\brequire\s*\(\s*)(["'])([^"'\n]+)(["'])"#).unwrap()
+});
+
+/// Rewrites imports between installed components to the workspace's alias.
+#[derive(Debug, Clone)]
+pub(crate) struct ImportRewriter {
+    components_root: PathBuf,
+    /// Alias prefix and the directory it maps to, e.g. `@/` for `<workspace>/src`
+    alias: Option<(String, PathBuf)>,
+}
+
+impl ImportRewriter {
+    /// Rewriter for components installed under `components_root`, using the
+    /// `compilerOptions.paths` entry of the workspace `tsconfig.json` (following
+    /// `extends`) that covers it.
+    pub(crate) fn load(workspace: &Path, components_root: &Path) -> CoreResult<Self> {
+        let components_root = normalize(components_root);
+        let path = workspace.join(TSCONFIG);
+        let alias = if path.is_file() {
+            let options = CompilerOptions::load(&path, 0)?;
+            options.alias_for(&components_root)
+        } else {
+            None
+        };
+        Ok(Self {
+            components_root,
+            alias,
+        })
+    }
+
+    pub(crate) fn alias(&self) -> Option<&str> {
+        self.alias.as_ref().map(|(prefix, _)| prefix.as_str())
+    }
+
+    /// Rewrite the imports of a script file about to be written; returns whether
+    /// any import changed.
+    pub(crate) fn apply(&self, from: &Path, file: &mut RenderedFile) -> CoreResult<bool> {
+        let is_script = file
+            .to
+            .extension()
+            .and_then(|e| e.to_str())
+            .is_some_and(|ext| SCRIPT_EXTENSIONS.contains(&ext));
+        if !is_script || !file.to.starts_with(&self.components_root) {
+            return Ok(false);
+        }
+        let bytes = match &file.content {
+            Some(content) => content.clone(),
+            None => fs::read(from)?,
+        };
+        let Ok(text) = String::from_utf8(bytes) else {
+            return Ok(false);
+        };
+        match self.rewrite(&file.to, &text) {
+            Some(rewritten) => {
+                file.content = Some(rewritten.into_bytes());
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// `source` (the content of `to`) with placeholder and relative imports of
+    /// other components replaced, or `None` when nothing changed. Imports within
+    /// the same component stay relative.
+    fn rewrite(&self, to: &Path, source: &str) -> Option<String> {
+        let dir = to.parent()?;
+        let own = self.component_of(to);
+        let rewritten = SPECIFIER.replace_all(source, |caps: &Captures| {
+            let specifier = &caps[3];
+            let target = if let Some(rest) = specifier.strip_prefix(COMPONENTS_PLACEHOLDER) {
+                Some(self.components_root.join(rest))
+            } else if specifier.starts_with("./") || specifier.starts_with("../") {
+                let target = normalize(&dir.join(specifier));
+                (self.alias.is_some() && self.component_of(&target).is_some_and(|c| Some(c) != own))
+                    .then_some(target)
+            } else {
+                None
+            };
+            match target {
+                Some(target) => {
+                    let specifier = self.specifier(dir, &target);
+                    format!("{}{}{specifier}{}", &caps[1], &caps[2], &caps[4])
+                }
+                None => caps[0].to_string(),
+            }
+        });
+        (rewritten != source).then(|| rewritten.into_owned())
+    }
+
+    /// Alias path of `target` when the alias covers it, otherwise a path relative to `dir`.
+    fn specifier(&self, dir: &Path, target: &Path) -> String {
+        match &self.alias {
+            Some((prefix, alias_dir)) if target.starts_with(alias_dir) => {
+                format!("{prefix}{}", relative_key(target, alias_dir))
+            }
+            _ => relative_path(dir, target),
+        }
+    }
+
+    /// First segment below the components root, without extensions for files.
+    fn component_of<'p>(&self, path: &'p Path) -> Option<&'p str> {
+        let rest = path.strip_prefix(&self.components_root).ok()?;
+        let first = rest.components().next()?.as_os_str().to_str()?;
+        Some(first.split('.').next().unwrap_or(first))
+    }
+}
+
+/// The `baseUrl` and `paths` in effect for a tsconfig, with the directories they
+/// resolve against.
+#[derive(Debug, Default)]
+struct CompilerOptions {
+    base_url: Option<PathBuf>,
+    paths: Option<(Map<String, Value>, PathBuf)>,
+}
+
+impl CompilerOptions {
+    /// Options of the config at `path`, over those it `extends` (later entries
+    /// of an `extends` array win).
+    fn load(path: &Path, depth: usize) -> CoreResult<Self> {
+        if depth > MAX_EXTENDS_DEPTH {
+            return Err(CoreError::InvalidDescriptor(format!(
+                "{} extends itself",
+                path.display()
+            )));
+        }
+        let text = fs::read_to_string(path)?;
+        let config: Value = serde_json::from_str(&strip_jsonc(&text)).map_err(|err| {
+            CoreError::InvalidDescriptor(format!("invalid {}: {err}", path.display()))
+        })?;
+        let dir = path.parent().unwrap_or(Path::new("."));
+
+        let mut options = Self::default();
+        let parents: Vec<&str> = match config.get("extends") {
+            Some(Value::String(parent)) => vec![parent.as_str()],
+            Some(Value::Array(parents)) => parents.iter().filter_map(Value::as_str).collect(),
+            _ => Vec::new(),
+        };
+        for parent in parents {
+            if let Some(parent) = resolve_extends(dir, parent) {
+                let inherited = Self::load(&parent, depth + 1)?;
+                options.base_url = inherited.base_url.or(options.base_url);
+                options.paths = inherited.paths.or(options.paths);
+            }
+        }
+
+        let compiler = config.get("compilerOptions");
+        if let Some(base_url) = compiler
+            .and_then(|c| c.get("baseUrl"))
+            .and_then(Value::as_str)
+        {
+            options.base_url = Some(normalize(&dir.join(base_url)));
+        }
+        if let Some(paths) = compiler
+            .and_then(|c| c.get("paths"))
+            .and_then(Value::as_object)
+        {
+            options.paths = Some((paths.clone(), normalize(dir)));
+        }
+        Ok(options)
+    }
+
+    /// The wildcard alias whose target directory most closely contains `dir`.
+    fn alias_for(&self, dir: &Path) -> Option<(String, PathBuf)> {
+        let (paths, paths_dir) = self.paths.as_ref()?;
+        // Without `baseUrl`, paths resolve against the config that declares them
+        let base = self.base_url.as_ref().unwrap_or(paths_dir);
+        paths
+            .iter()
+            .filter_map(|(key, targets)| {
+                let prefix = key.strip_suffix('*')?;
+                let target = targets.as_array()?.first()?.as_str()?.strip_suffix('*')?;
+                let target_dir = normalize(&base.join(target));
+                dir.starts_with(&target_dir)
+                    .then(|| (prefix.to_string(), target_dir))
+            })
+            .max_by_key(|(_, target_dir)| target_dir.components().count())
+    }
+}
+
+/// Config file an `extends` entry names: a path relative to `dir`, or a package
+/// under the workspace's `node_modules`.
+fn resolve_extends(dir: &Path, parent: &str) -> Option<PathBuf> {
+    let base = if parent.starts_with('.') || Path::new(parent).is_absolute() {
+        dir.join(parent)
+    } else {
+        dir.join("node_modules").join(parent)
+    };
+    [
+        base.clone(),
+        base.with_extension("json"),
+        base.join(TSCONFIG),
+    ]
+    .into_iter()
+    .find(|candidate| candidate.is_file())
+}
+
+/// JSON with comments and trailing commas (as tsconfig allows) turned into plain JSON.
+fn strip_jsonc(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    let mut in_string = false;
+    while let Some(c) = chars.next() {
+        if in_string {
+            out.push(c);
+            match c {
+                '\\' => out.extend(chars.next()),
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match (c, chars.peek()) {
+            ('"', _) => {
+                in_string = true;
+                out.push(c);
+            }
+            ('/', Some('/' | '*')) => skip_comment(&mut chars),
+            (',', _) => {
+                if !matches!(next_token(chars.clone()), Some('}' | ']')) {
+                    out.push(c);
+                }
+            }
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+/// First character of `chars` outside whitespace and comments.
+fn next_token(mut chars: Peekable<Chars>) -> Option<char> {
+    while let Some(c) = chars.next() {
+        match (c, chars.peek()) {
+            (c, _) if c.is_whitespace() => {}
+            ('/', Some('/' | '*')) => skip_comment(&mut chars),
+            _ => return Some(c),
+        }
+    }
+    None
+}
+
+/// Skip the rest of a comment whose leading `/` was just read.
+fn skip_comment(chars: &mut Peekable<Chars>) {
+    if chars.next() == Some('/') {
+        while chars.next_if(|c| *c != '\n').is_some() {}
+        return;
+    }
+    let mut previous = ' ';
+    for c in chars.by_ref() {
+        if previous == '*' && c == '/' {
+            break;
+        }
+        previous = c;
+    }
+}
+
+/// `./`- or `../`-prefixed path from `dir` to `target`, `/`-separated.
+fn relative_path(dir: &Path, target: &Path) -> String {
+    let dir: Vec<_> = dir.components().collect();
+    let target: Vec<_> = target.components().collect();
+    let common = dir.iter().zip(&target).take_while(|(a, b)| a == b).count();
+    let mut segments: Vec<String> = vec!["..".to_string(); dir.len() - common];
+    if segments.is_empty() {
+        segments.push(".".to_string());
+    }
+    segments.extend(
+        target[common..]
+            .iter()
+            .map(|c| c.as_os_str().to_string_lossy().into_owned()),
+    );
+    segments.join("/")
+}
@@ -0,0 +1,323 @@
+use std::collections::{BTreeSet, HashMap};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+
+use entity_core::error::{CoreError, CoreResult};
+use entity_core::selections::Selections;
+use serde::{Deserialize, Serialize};
+
+use crate::components::NAMES_KEY;
+use crate::transaction::{Transaction, TransactionReport};
+use crate::uninstall::prune_empty_dirs;
+use crate::write::digest;
+
+/// Append-only record of the operations applied to a workspace, one JSON object
+/// per line.
+pub const JOURNAL_FILE: &str = ".entitycli/journal.jsonl";
+
+/// Content that journaled operations replaced or removed, by sha256, so `undo`
+/// can put it back.
+pub const JOURNAL_BACKUP_DIR: &str = ".entitycli/journal";
+
+/// Command recorded for the operations `undo` itself applies; they are never undone.
+pub const UNDO_COMMAND: &str = "undo";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JournalAction {
+    Created,
+    Overwritten,
+    Removed,
+    /// Moved to `to`, as the `backup` conflict policy does
+    Moved,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JournalFile {
+    /// Workspace-relative path
+    pub path: String,
+    pub action: JournalAction,
+    /// sha256 of the content before the operation, kept in the journal backups
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub before: Option<String>,
+    /// sha256 of the content the operation wrote
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub after: Option<String>,
+    /// Where a moved file went
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub to: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JournalEntry {
+    pub id: u64,
+    /// Unix seconds
+    pub at: u64,
+    /// CLI command that applied the operation, e.g. `ui install`
+    pub command: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub node: Option<String>,
+    #[serde(default, skip_serializing_if = "Selections::is_empty")]
+    pub selections: Selections,
+    /// In the order they were applied
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub files: Vec<JournalFile>,
+    /// Setup commands that ran
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub commands: Vec<String>,
+    /// Entries an `undo` reverted
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub reverts: Vec<u64>,
+}
+
+/// What a transaction applies, recorded in the journal once it commits.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Operation {
+    pub(crate) command: String,
+    pub(crate) node: Option<String>,
+    pub(crate) selections: Selections,
+    pub(crate) commands: Vec<String>,
+    pub(crate) reverts: Vec<u64>,
+}
+
+impl Operation {
+    pub(crate) fn new(command: &str, node: Option<&str>, selections: &Selections) -> Self {
+        Self {
+            command: command.to_string(),
+            node: node.map(str::to_string),
+            selections: selections.clone(),
+            ..Self::default()
+        }
+    }
+}
+
+/// Selections recorded for commands that take `--names` instead of selections.
+pub(crate) fn names_selection(names: Option<&[String]>) -> Selections {
+    match names {
+        Some(names) => Selections::new().with(NAMES_KEY, names.to_vec()),
+        None => Selections::new(),
+    }
+}
+
+/// Every journal entry of `workspace`, oldest first; none without a journal.
+pub fn load_journal(workspace: &Path) -> CoreResult<Vec<JournalEntry>> {
+    let path = workspace.join(JOURNAL_FILE);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    fs::read_to_string(&path)?
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            serde_json::from_str(line).map_err(|err| {
+                CoreError::InvalidDescriptor(format!("invalid journal {}: {err}", path.display()))
+            })
+        })
+        .collect()
+}
+
+/// Append `operation` with the files it touched, numbered after the last entry.
+pub(crate) fn append(
+    workspace: &Path,
+    operation: Operation,
+    files: Vec<JournalFile>,
+    at: u64,
+) -> CoreResult<JournalEntry> {
+    let id = load_journal(workspace)?
+        .last()
+        .map_or(1, |last| last.id + 1);
+    let entry = JournalEntry {
+        id,
+        at,
+        command: operation.command,
+        node: operation.node,
+        selections: operation.selections,
+        files,
+        commands: operation.commands,
+        reverts: operation.reverts,
+    };
+    let path = workspace.join(JOURNAL_FILE);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut line = serde_json::to_string(&entry)?;
+    line.push('\n');
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)?
+        .write_all(line.as_bytes())?;
+    Ok(entry)
+}
+
+/// Move a replaced file's content into the journal backups, unless already kept.
+pub(crate) fn keep_backup(workspace: &Path, sha256: &str, from: &Path) -> CoreResult<()> {
+    let dir = workspace.join(JOURNAL_BACKUP_DIR);
+    let path = dir.join(sha256);
+    if path.exists() {
+        return Ok(());
+    }
+    fs::create_dir_all(&dir)?;
+    fs::rename(from, path)?;
+    Ok(())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UndoAction {
+    /// Put back as it was before the operation
+    Restored,
+    /// Created by the operation, deleted again
+    Removed,
+}
+
+#[derive(Debug, Serialize)]
+pub struct UndoFile {
+    pub path: String,
+    pub action: UndoAction,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RevertedOperation {
+    pub id: u64,
+    pub command: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub node: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct UndoReport {
+    /// Operations reverted, newest first
+    pub reverted: Vec<RevertedOperation>,
+    pub files: Vec<UndoFile>,
+    pub notes: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub transaction: Option<TransactionReport>,
+}
+
+/// Revert the last `steps` journaled operations that were not undone yet, newest
+/// first, as one transaction. Fails with `WRITE_CONFLICT` when a file changed
+/// since, and with `MISSING_SOURCE` when a replaced file's backup is gone.
+/// Commands that ran are not reverted.
+pub fn undo(workspace: &Path, steps: usize) -> CoreResult<UndoReport> {
+    let entries = load_journal(workspace)?;
+    let undone: BTreeSet<u64> = entries.iter().flat_map(|e| e.reverts.clone()).collect();
+    let targets: Vec<&JournalEntry> = entries
+        .iter()
+        .rev()
+        .filter(|e| e.command != UNDO_COMMAND && !undone.contains(&e.id))
+        .take(steps)
+        .collect();
+    let mut report = UndoReport {
+        reverted: Vec::new(),
+        files: Vec::new(),
+        notes: Vec::new(),
+        transaction: None,
+    };
+    if targets.is_empty() {
+        report.notes.push("Nothing to undo".into());
+        return Ok(report);
+    }
+
+    // Follow each file back through the operations, checking it still holds what
+    // the newest one left before anything is reverted
+    let backups = workspace.join(JOURNAL_BACKUP_DIR);
+    let mut expected: HashMap<String, Option<String>> = HashMap::new();
+    let mut modified = BTreeSet::new();
+    let mut missing = BTreeSet::new();
+    for entry in &targets {
+        for file in entry.files.iter().rev() {
+            let (path, after) = match file.action {
+                JournalAction::Moved => (file.to.clone().unwrap_or_default(), &file.before),
+                JournalAction::Removed => (file.path.clone(), &None),
+                _ => (file.path.clone(), &file.after),
+            };
+            if state(workspace, &path, &expected)? != *after {
+                modified.insert(path.clone());
+            }
+            expected.insert(path, None);
+            if file.action != JournalAction::Moved
+                && let Some(before) = &file.before
+                && !backups.join(before).is_file()
+            {
+                missing.insert(file.path.clone());
+            }
+            expected.insert(file.path.clone(), file.before.clone());
+        }
+    }
+    if !modified.is_empty() {
+        return Err(CoreError::WriteConflict(modified.into_iter().collect()));
+    }
+    if !missing.is_empty() {
+        return Err(CoreError::MissingSource(format!(
+            "journal backups of {}",
+            missing.into_iter().collect::<Vec<_>>().join(", ")
+        )));
+    }
+
+    let mut operation = Operation::new(UNDO_COMMAND, None, &Selections::new());
+    operation.reverts = targets.iter().map(|e| e.id).collect();
+    let mut tx = Transaction::new(workspace).journaled(operation);
+    let mut removed = Vec::new();
+    for entry in &targets {
+        for file in entry.files.iter().rev() {
+            let path = workspace.join(&file.path);
+            let action = match (&file.action, &file.before) {
+                (JournalAction::Moved, _) => {
+                    tx.rename(
+                        &workspace.join(file.to.as_deref().unwrap_or_default()),
+                        &path,
+                    );
+                    UndoAction::Restored
+                }
+                (_, Some(before)) => {
+                    tx.write(&path, &fs::read(backups.join(before))?)?;
+                    UndoAction::Restored
+                }
+                (_, None) => {
+                    tx.remove(&path);
+                    removed.push(path);
+                    UndoAction::Removed
+                }
+            };
+            report.files.push(UndoFile {
+                path: file.path.clone(),
+                action,
+            });
+        }
+        report.reverted.push(RevertedOperation {
+            id: entry.id,
+            command: entry.command.clone(),
+            node: entry.node.clone(),
+        });
+        report.notes.extend(entry.commands.iter().map(|command| {
+            format!(
+                "`{command}` ran during {} and was not reverted",
+                entry.command
+            )
+        }));
+    }
+    report.transaction = Some(tx.commit()?);
+    for path in removed {
+        prune_empty_dirs(&path, workspace, workspace);
+    }
+    Ok(report)
+}
+
+/// What `path` holds once the reverts checked so far are applied.
+fn state(
+    workspace: &Path,
+    path: &str,
+    expected: &HashMap<String, Option<String>>,
+) -> CoreResult<Option<String>> {
+    if let Some(sha) = expected.get(path) {
+        return Ok(sha.clone());
+    }
+    let file = workspace.join(path);
+    Ok(file
+        .is_file()
+        .then(|| digest(&file))
+        .transpose()?
+        .map(|d| d.sha256))
+}
@@ -1,17 +1,52 @@
 mod bridge;
 mod components;
+mod deps;
+mod diff;
 mod docs;
+mod entityignore;
+mod framework;
+mod imports;
+mod journal;
+mod lock;
+mod package_json;
 mod setup;
+mod status;
+mod template;
+mod transaction;
+mod uninstall;
+mod update;
 
 mod util;
+mod write;
 
 pub use bridge::{
     BridgeExecutor, BridgeProcessInfo, BridgeProcessState, BridgeProcessStateProcess,
     BridgeScaffoldReport, BridgeStopResult,
 };
-pub use components::{ComponentsExecutor, CopyItemReport, CopyReport};
+pub use components::{
+    ComponentSource, ComponentsExecutor, CopyItemReport, CopyReport, FRAMEWORK_KEY, MODE_KEY,
+    NAMES_KEY, resolve_component,
+};
+pub use deps::{DependencyExecutor, DependencyReport, StepReport, StepResult};
+pub use diff::{DiffHunk, DiffReport, DiffStatus, FileDiff};
 pub use docs::DocsExecutor;
+pub use entityignore::{
+    IGNORE_FILE, IgnoreRules, SkipRule, SkippedFiles, check_exclude_pattern, check_ignore_file,
+};
+pub use imports::{COMPONENTS_PLACEHOLDER, TSCONFIG};
+pub use journal::{
+    JOURNAL_BACKUP_DIR, JOURNAL_FILE, JournalAction, JournalEntry, JournalFile, RevertedOperation,
+    UNDO_COMMAND, UndoAction, UndoFile, UndoReport, load_journal, undo,
+};
+pub use lock::{BASE_DIR, LOCK_FILE, LockEntry, Lockfile};
+pub use package_json::{PACKAGE_JSON, PackageChange, PackageChangeAction, PackageJsonReport};
 pub use setup::{SetupExecutor, SetupReport};
+pub use status::{ComponentStatus, FileStatus, FileStatusReport, StatusReport};
+pub use template::TEMPLATE_SUFFIX;
+pub use transaction::{TRANSACTION_DIR, TransactionReport, TransactionStatus};
+pub use uninstall::UninstallReport;
+pub use update::{FileUpdate, MergeConflict, UpdateAction, UpdateReport};
+pub use write::{FileAction, FileDigest, PlannedFile, WriteOptions, WritePlan, digest};
 
 #[cfg(test)]
 mod tests;
@@ -0,0 +1,185 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use entity_core::error::{CoreError, CoreResult};
+use entity_core::registry::Registry;
+use serde::{Deserialize, Serialize};
+
+use crate::transaction::Transaction;
+use crate::write::FileDigest;
+
+/// Workspace-relative path of the install lockfile.
+pub const LOCK_FILE: &str = ".entitycli/lock.json";
+
+/// Workspace-relative directory holding the installed content of every locked
+/// file, keyed by sha256; `ui update` merges against it.
+pub const BASE_DIR: &str = ".entitycli/base";
+
+const LOCK_VERSION: u32 = 1;
+
+/// Every file the executors wrote into a workspace, keyed by its
+/// workspace-relative destination (`/`-separated).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Lockfile {
+    pub version: u32,
+    #[serde(default)]
+    pub files: BTreeMap<String, LockEntry>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LockEntry {
+    /// Node whose write produced the file
+    pub node: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pack: Option<String>,
+    #[serde(
+        rename = "packVersion",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub pack_version: Option<String>,
+    /// Source file, relative to the pack root when it lives inside the pack
+    pub source: String,
+    /// Declared component name, for files installed by component nodes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub component: Option<String>,
+    pub size: u64,
+    /// sha256 of the content as written
+    pub sha256: String,
+    /// sha256 of the source, when the written content differs from it
+    /// (rendered templates, rewritten imports)
+    #[serde(
+        rename = "sourceSha256",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub source_sha256: Option<String>,
+    /// Template variables the file was rendered with
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub vars: BTreeMap<String, String>,
+}
+
+/// Node (and pack) a write is recorded under; fills in per-file lock entries.
+#[derive(Debug, Clone)]
+pub(crate) struct LockOwner {
+    node: String,
+    pack: Option<String>,
+    pack_version: Option<String>,
+    pack_root: Option<PathBuf>,
+}
+
+impl LockOwner {
+    pub(crate) fn new(registry: &Registry, node_id: &str) -> Self {
+        let pack = registry.pack_for(node_id);
+        Self {
+            node: node_id.to_string(),
+            pack: pack.map(|p| p.name.clone()),
+            pack_version: pack.and_then(|p| p.manifest.version.clone()),
+            pack_root: pack.map(|p| PathBuf::from(&p.root)),
+        }
+    }
+
+    /// Entry for a file written from `from` with content `digest`.
+    pub(crate) fn entry(&self, from: &Path, digest: &FileDigest) -> LockEntry {
+        let source = match &self.pack_root {
+            Some(root) if from.starts_with(root) => relative_key(from, root),
+            _ => from.display().to_string(),
+        };
+        LockEntry {
+            node: self.node.clone(),
+            pack: self.pack.clone(),
+            pack_version: self.pack_version.clone(),
+            source,
+            component: None,
+            size: digest.size,
+            sha256: digest.sha256.clone(),
+            source_sha256: None,
+            vars: BTreeMap::new(),
+        }
+    }
+}
+
+impl Default for Lockfile {
+    fn default() -> Self {
+        Self {
+            version: LOCK_VERSION,
+            files: BTreeMap::new(),
+        }
+    }
+}
+
+impl Lockfile {
+    pub fn path(workspace: &Path) -> PathBuf {
+        workspace.join(LOCK_FILE)
+    }
+
+    /// Load the workspace lockfile; a missing file yields an empty one.
+    pub fn load(workspace: &Path) -> CoreResult<Self> {
+        let path = Self::path(workspace);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = fs::read_to_string(&path)?;
+        serde_json::from_str(&content).map_err(|err| {
+            CoreError::InvalidDescriptor(format!("invalid lockfile {}: {err}", path.display()))
+        })
+    }
+
+    /// Stage the lockfile into `tx`, to be written with the files it records.
+    pub(crate) fn stage(&self, workspace: &Path, tx: &mut Transaction) -> CoreResult<()> {
+        tx.write(
+            &Self::path(workspace),
+            serde_json::to_string_pretty(self)?.as_bytes(),
+        )
+    }
+
+    /// Entries written by `node_id`.
+    pub fn node_files<'a>(
+        &'a self,
+        node_id: &'a str,
+    ) -> impl Iterator<Item = (&'a String, &'a LockEntry)> + 'a {
+        self.files.iter().filter(move |(_, e)| e.node == node_id)
+    }
+}
+
+/// Keep each `(sha256, content)` as the installed version of files with that
+/// sha256, once their transaction committed. Bases only make later merges finer,
+/// so one that can't be stored is noted rather than failing the call.
+pub(crate) fn store_bases(
+    workspace: &Path,
+    bases: Vec<(String, Vec<u8>)>,
+    notes: &mut Vec<String>,
+) {
+    for (sha256, content) in bases {
+        if let Err(err) = store_base(workspace, &sha256, &content) {
+            notes.push(format!("Merge base {sha256} not stored: {err}"));
+        }
+    }
+}
+
+fn store_base(workspace: &Path, sha256: &str, content: &[u8]) -> CoreResult<()> {
+    let path = workspace.join(BASE_DIR).join(sha256);
+    if !path.exists() {
+        fs::create_dir_all(workspace.join(BASE_DIR))?;
+        fs::write(path, content)?;
+    }
+    Ok(())
+}
+
+/// Installed content recorded for `sha256`, if the workspace still has it.
+pub(crate) fn load_base(workspace: &Path, sha256: &str) -> Option<Vec<u8>> {
+    fs::read(workspace.join(BASE_DIR).join(sha256)).ok()
+}
+
+/// `/`-separated form of `path` relative to `root`, or the full path outside it.
+pub(crate) fn relative_key(path: &Path, root: &Path) -> String {
+    match path.strip_prefix(root) {
+        Ok(rel) => rel
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/"),
+        Err(_) => path.display().to_string(),
+    }
+}
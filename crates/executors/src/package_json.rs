@@ -0,0 +1,252 @@
+use std::fs;
+use std::path::Path;
+
+use entity_core::error::CoreResult;
+use entity_core::model::PackageJsonSpec;
+use entity_core::pack::{VersionRange, parse_version_req};
+use indexmap::IndexMap;
+use semver::{Comparator, Op, Version};
+use serde::Serialize;
+use serde_json::value::RawValue;
+
+/// Workspace-relative path of the file dependencies are merged into.
+pub const PACKAGE_JSON: &str = "package.json";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PackageChangeAction {
+    Added,
+    /// Raised to the declared range
+    Updated,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PackageChange {
+    /// `dependencies`, `devDependencies` or `scripts`
+    pub section: String,
+    pub name: String,
+    pub action: PackageChangeAction,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub from: Option<String>,
+    pub to: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PackageJsonReport {
+    pub path: String,
+    pub changes: Vec<PackageChange>,
+    /// Declared entries left as they are, and why
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub notes: Vec<String>,
+}
+
+/// Merge `spec` into `<workspace>/package.json`, returning the report and the new
+/// content when anything changed; the caller writes it. Existing entries keep
+/// their position and every untouched value keeps its exact text; a dependency
+/// range is only replaced by a higher one, and existing scripts are never replaced.
+pub(crate) fn merge_package_json(
+    workspace: &Path,
+    spec: &PackageJsonSpec,
+) -> CoreResult<(PackageJsonReport, Option<Vec<u8>>)> {
+    let path = workspace.join(PACKAGE_JSON);
+    let mut report = PackageJsonReport {
+        path: path.display().to_string(),
+        changes: Vec::new(),
+        notes: Vec::new(),
+    };
+    if !path.is_file() {
+        report.notes.push(format!(
+            "No {PACKAGE_JSON} in the workspace; add the declared entries yourself"
+        ));
+        return Ok((report, None));
+    }
+
+    let text = fs::read_to_string(&path)?;
+    let indent = detect_indent(&text);
+    let mut top: IndexMap<String, Box<RawValue>> = serde_json::from_str(&text)?;
+    for (section, declared) in [
+        ("dependencies", &spec.dependencies),
+        ("devDependencies", &spec.dev_dependencies),
+        ("scripts", &spec.scripts),
+    ] {
+        if declared.is_empty() {
+            continue;
+        }
+        let mut entries: IndexMap<String, String> = match top.get(section) {
+            Some(raw) => serde_json::from_str(raw.get())?,
+            None => IndexMap::new(),
+        };
+        let before = report.changes.len();
+        for (name, wanted) in declared {
+            let Some(current) = entries.get(name) else {
+                insert_entry(&mut entries, name, wanted, section != "scripts");
+                report.changes.push(PackageChange {
+                    section: section.into(),
+                    name: name.clone(),
+                    action: PackageChangeAction::Added,
+                    from: None,
+                    to: wanted.clone(),
+                });
+                continue;
+            };
+            if current == wanted {
+                continue;
+            }
+            if section == "scripts" {
+                report.notes.push(format!(
+                    "kept script {name}: {current:?} (declared {wanted:?})"
+                ));
+                continue;
+            }
+            match compare_ranges(current, wanted) {
+                RangeCheck::Satisfies => {}
+                RangeCheck::Older => {
+                    report.changes.push(PackageChange {
+                        section: section.into(),
+                        name: name.clone(),
+                        action: PackageChangeAction::Updated,
+                        from: Some(current.clone()),
+                        to: wanted.clone(),
+                    });
+                    entries.insert(name.clone(), wanted.clone());
+                }
+                RangeCheck::Incomparable => report.notes.push(format!(
+                    "kept {section}.{name}@{current} (declared {wanted})"
+                )),
+            }
+        }
+        if report.changes.len() > before {
+            top.insert(
+                section.to_string(),
+                section_value(&entries, indent.as_deref())?,
+            );
+        }
+    }
+
+    if report.changes.is_empty() {
+        return Ok((report, None));
+    }
+    let mut out = render(&top, indent.as_deref())?;
+    if text.ends_with('\n') {
+        out.push(b'\n');
+    }
+    Ok((report, Some(out)))
+}
+
+enum RangeCheck {
+    /// The existing range already allows the declared minimum (or is newer), or
+    /// either range is `*`
+    Satisfies,
+    /// The existing range's minimum is below the declared one
+    Older,
+    /// Not semver ranges (tags, URLs, `workspace:`), or newer but disjoint
+    Incomparable,
+}
+
+fn compare_ranges(current: &str, wanted: &str) -> RangeCheck {
+    let (Ok(current), Ok(wanted)) = (parse_version_req(current), parse_version_req(wanted)) else {
+        return RangeCheck::Incomparable;
+    };
+    if any_version(&current) || any_version(&wanted) {
+        return RangeCheck::Satisfies;
+    }
+    let (Some(floor), Some(wanted_floor)) = (floor(&current), floor(&wanted)) else {
+        return RangeCheck::Incomparable;
+    };
+    if wanted.matches(&floor) {
+        RangeCheck::Satisfies
+    } else if floor < wanted_floor {
+        RangeCheck::Older
+    } else {
+        RangeCheck::Incomparable
+    }
+}
+
+/// `*` (or an empty range): every version matches.
+fn any_version(range: &VersionRange) -> bool {
+    range
+        .alternatives
+        .iter()
+        .any(|req| req.comparators.is_empty())
+}
+
+/// Lowest version a range allows, if every alternative has a lower bound.
+fn floor(range: &VersionRange) -> Option<Version> {
+    range
+        .alternatives
+        .iter()
+        .map(|req| {
+            req.comparators
+                .iter()
+                .filter(|c| !matches!(c.op, Op::Less | Op::LessEq))
+                .map(lower_bound)
+                .max()
+        })
+        .collect::<Option<Vec<_>>>()?
+        .into_iter()
+        .min()
+}
+
+/// Lowest version one comparator allows; `>` skips past every version the
+/// given parts cover (`>1` is 2.0.0, `>1.2` is 1.3.0).
+fn lower_bound(c: &Comparator) -> Version {
+    let mut version = Version::new(c.major, c.minor.unwrap_or(0), c.patch.unwrap_or(0));
+    version.pre = c.pre.clone();
+    if c.op == Op::Greater {
+        match (c.minor, c.patch) {
+            (None, _) => version = Version::new(c.major + 1, 0, 0),
+            (Some(minor), None) => version = Version::new(c.major, minor + 1, 0),
+            // Prereleases of the same version still sort above it
+            (Some(_), Some(_)) if c.pre.is_empty() => version.patch += 1,
+            (Some(_), Some(_)) => {}
+        }
+    }
+    version
+}
+
+/// Insert keeping alphabetical order in dependency sections that are already
+/// sorted (as npm keeps them); append otherwise.
+fn insert_entry(entries: &mut IndexMap<String, String>, name: &str, value: &str, sort: bool) {
+    let index = if sort && entries.keys().is_sorted() {
+        entries.keys().take_while(|k| k.as_str() < name).count()
+    } else {
+        entries.len()
+    };
+    entries.shift_insert(index, name.to_string(), value.to_string());
+}
+
+/// Section object rendered as it appears one level deep in the file.
+fn section_value(
+    entries: &IndexMap<String, String>,
+    indent: Option<&str>,
+) -> CoreResult<Box<RawValue>> {
+    let out = render(entries, indent)?;
+    let mut text = String::from_utf8_lossy(&out).into_owned();
+    if let Some(indent) = indent {
+        text = text.replace('\n', &format!("\n{indent}"));
+    }
+    Ok(RawValue::from_string(text)?)
+}
+
+/// `value` pretty-printed with `indent`, or on one line when the file has none.
+fn render(value: &impl Serialize, indent: Option<&str>) -> CoreResult<Vec<u8>> {
+    let Some(indent) = indent else {
+        return Ok(serde_json::to_vec(value)?);
+    };
+    let mut out = Vec::new();
+    let formatter = serde_json::ser::PrettyFormatter::with_indent(indent.as_bytes());
+    let mut serializer = serde_json::Serializer::with_formatter(&mut out, formatter);
+    value.serialize(&mut serializer)?;
+    Ok(out)
+}
+
+/// Indentation of the first indented line; `None` for minified files.
+fn detect_indent(text: &str) -> Option<String> {
+    text.lines().skip(1).find_map(|line| {
+        let indent: String = line
+            .chars()
+            .take_while(|c| *c == ' ' || *c == '\t')
+            .collect();
+        (!indent.is_empty()).then_some(indent)
+    })
+}
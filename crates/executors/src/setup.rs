@@ -1,31 +1,62 @@
-use std::fs;
 use std::path::{Path, PathBuf};
 
 use entity_core::error::{CoreError, CoreResult};
 use entity_core::model::{NodeKind, NodePayload};
 use entity_core::registry::Registry;
-use walkdir::WalkDir;
+use entity_core::schema::validate_selections;
+use entity_core::selections::Selections;
 
 use crate::components::CopyItemReport;
-use crate::util::ensure_writable_dir;
+use crate::entityignore::{IgnoreRules, SkippedFiles};
+use crate::package_json::PackageJsonReport;
+use crate::template::template_vars;
+use crate::transaction::TransactionReport;
+use crate::util::{ensure_writable_dir, install_root};
+use crate::write::{WriteOptions, WritePlan, Writer};
 
-#[derive(Debug)]
+#[derive(Debug, serde::Serialize)]
 pub struct SetupReport {
     pub scaffolded: Vec<String>,
     pub copied: Vec<CopyItemReport>,
     pub notes: Vec<String>,
+    /// Commands and files that would run and be written, on dry runs
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub plan: Option<WritePlan>,
+    /// Entries merged into the workspace `package.json`
+    #[serde(rename = "packageJson", skip_serializing_if = "Option::is_none")]
+    pub package_json: Option<PackageJsonReport>,
+    /// Pack files left out by `.entityignore` files, `exclude` or the defaults
+    #[serde(skip_serializing_if = "SkippedFiles::is_empty")]
+    pub skipped: SkippedFiles,
+    /// Outcome of the writes, absent on dry runs
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub transaction: Option<TransactionReport>,
 }
 
 pub struct SetupExecutor<'a> {
     registry: &'a Registry,
+    options: WriteOptions,
 }
 
 impl<'a> SetupExecutor<'a> {
     pub fn new(registry: &'a Registry) -> Self {
-        Self { registry }
+        Self {
+            registry,
+            options: WriteOptions::default(),
+        }
+    }
+
+    pub fn with_options(mut self, options: WriteOptions) -> Self {
+        self.options = options;
+        self
     }
 
-    pub fn run(&self, node_id: &str, workspace: &Path) -> CoreResult<SetupReport> {
+    pub fn run(
+        &self,
+        node_id: &str,
+        selections: &Selections,
+        workspace: &Path,
+    ) -> CoreResult<SetupReport> {
         let node = self.registry.get(node_id)?;
         if node.kind != NodeKind::Setup {
             return Err(CoreError::WrongKind {
@@ -33,21 +64,62 @@ impl<'a> SetupExecutor<'a> {
                 actual: format!("{:?}", node.kind),
             });
         }
+        validate_selections(&node.prerequisites, selections)?;
         if !workspace.exists() {
             return Err(CoreError::TargetNotFound(workspace.display().to_string()));
         }
 
-        ensure_writable_dir(workspace)
-            .map_err(|_| CoreError::TargetNotWritable(workspace.display().to_string()))?;
+        let vars = template_vars(self.registry, node_id, selections, workspace)?;
+        let mut writer = Writer::for_node(self.registry, node_id, workspace, &self.options)?
+            .journaled("setup run", selections)
+            .with_vars(vars);
+        if !writer.dry_run() {
+            ensure_writable_dir(workspace)
+                .map_err(|_| CoreError::TargetNotWritable(workspace.display().to_string()))?;
+        }
 
-        // Execute optional scaffold commands first
-        let mut scaffolded: Vec<String> = Vec::new();
+        // Copy template tree into the pack's install root
+        let mut report = SetupReport {
+            scaffolded: Vec::new(),
+            copied: Vec::new(),
+            notes: Vec::new(),
+            plan: None,
+            package_json: None,
+            skipped: SkippedFiles::default(),
+            transaction: None,
+        };
+        let (template_root, _) = match &node.payload {
+            NodePayload::Setup { template_root, .. } => (PathBuf::from(template_root), true),
+            _ => unreachable!(),
+        };
+        // Copy into the install root (e.g. workspace/entity-auth). TemplateRoot should
+        // contain the contents that belong directly under it to avoid double nesting.
+        let to_root = install_root(self.registry, node_id, workspace)?;
+        let from_root = template_root;
+        let mut ignore = IgnoreRules::for_node(self.registry, node_id)?;
+        let files_copied = writer.stage(ignore.tree_files(&from_root, &from_root, &to_root)?)?;
+        report.skipped = ignore.skipped();
+        report.copied.push(CopyItemReport {
+            from: from_root.display().to_string(),
+            to: to_root.display().to_string(),
+            count: files_copied,
+        });
+        if let Some(package) = node.package_json.as_ref().filter(|p| !p.is_empty()) {
+            report.package_json = Some(writer.merge_package_json(package)?);
+        }
+
+        // Run the scaffold commands only once the writes are known to go through,
+        // so an aborted setup leaves the workspace untouched
+        writer.check()?;
         if let NodePayload::Setup {
             commands: Some(cmds),
             ..
         } = &node.payload
         {
             for cmd in cmds {
+                if !writer.command(cmd) {
+                    continue;
+                }
                 // naive split by whitespace; in the future support args array in payload
                 let mut parts = cmd.split_whitespace();
                 if let Some(bin) = parts.next() {
@@ -58,7 +130,7 @@ impl<'a> SetupExecutor<'a> {
                         .status();
                     match status {
                         Ok(s) if s.success() => {
-                            scaffolded.push(format!("{} {:?}", bin, args));
+                            report.scaffolded.push(format!("{} {:?}", bin, args));
                         }
                         Ok(s) => {
                             return Err(CoreError::InvalidDescriptor(format!(
@@ -79,39 +151,12 @@ impl<'a> SetupExecutor<'a> {
             }
         }
 
-        // Copy template tree into /entity-auth
-        let mut report = SetupReport {
-            scaffolded,
-            copied: Vec::new(),
-            notes: vec!["Overwrite-on-write by default".into()],
-        };
-        let (template_root, _) = match &node.payload {
-            NodePayload::Setup { template_root, .. } => (PathBuf::from(template_root), true),
-            _ => unreachable!(),
-        };
-        // Copy into workspace/entity-auth. TemplateRoot should contain the contents
-        // that belong directly under entity-auth to avoid double nesting.
-        let to_root = workspace.join("entity-auth");
-        fs::create_dir_all(&to_root)?;
-        let from_root = template_root;
-        let mut files_copied = 0usize;
-        for entry in WalkDir::new(&from_root).into_iter().filter_map(Result::ok) {
-            let path = entry.path();
-            if path.is_file() {
-                let rel = path.strip_prefix(&from_root).unwrap();
-                let to_path = to_root.join(rel);
-                if let Some(parent) = to_path.parent() {
-                    fs::create_dir_all(parent)?;
-                }
-                fs::copy(path, &to_path)?;
-                files_copied += 1;
-            }
+        (report.notes, report.plan, report.transaction) = writer.commit()?;
+        if report.plan.as_ref().is_some_and(|p| !p.commands.is_empty()) {
+            report
+                .notes
+                .push("Files created by the commands are not part of the plan".into());
         }
-        report.copied.push(CopyItemReport {
-            from: from_root.display().to_string(),
-            to: to_root.display().to_string(),
-            count: files_copied,
-        });
         Ok(report)
     }
 }
@@ -0,0 +1,285 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+
+use entity_core::error::{CoreError, CoreResult};
+use entity_core::model::{NodeKind, NodePayload};
+use entity_core::registry::Registry;
+use entity_core::selections::Selections;
+use entity_core::workspace::WorkspaceConfig;
+use serde::Serialize;
+
+use crate::components::{ComponentSource, resolve_variant, select_names};
+use crate::entityignore::IgnoreRules;
+use crate::lock::{LockEntry, Lockfile, relative_key};
+use crate::template::{render_file, template_vars};
+use crate::util::name_variants;
+use crate::write::digest;
+
+/// How an installed file compares to its install record and the pack source.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum FileStatus {
+    Unchanged,
+    /// Edited in the workspace since install
+    Modified,
+    /// The pack source changed (or disappeared) since install
+    UpstreamChanged,
+    BothChanged,
+    /// Removed from the workspace
+    Deleted,
+    /// New in the pack since install; `ui update` creates it
+    Added,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FileStatusReport {
+    /// Workspace-relative path
+    pub path: String,
+    pub status: FileStatus,
+    /// Absolute path of the pack source
+    pub source: String,
+    #[serde(rename = "sourceMissing", skip_serializing_if = "std::ops::Not::not")]
+    pub source_missing: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ComponentStatus {
+    pub name: String,
+    pub node: String,
+    /// Some file has upstream changes to pick up
+    pub outdated: bool,
+    /// Some file was edited or deleted locally
+    pub modified: bool,
+    pub files: Vec<FileStatusReport>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct StatusReport {
+    pub components: Vec<ComponentStatus>,
+}
+
+/// Compare every component file recorded in the workspace lockfile with the
+/// workspace copy and the current pack source, and list the files the pack
+/// added to installed components since.
+pub(crate) fn component_status(registry: &Registry, workspace: &Path) -> CoreResult<StatusReport> {
+    let lock = Lockfile::load(workspace)?;
+    let config = WorkspaceConfig::load(workspace)?;
+    let nodes: BTreeSet<&str> = lock.files.values().map(|e| e.node.as_str()).collect();
+    let mut components = Vec::new();
+    for node_id in nodes {
+        let Ok(node) = registry.get(node_id) else {
+            continue;
+        };
+        if node.kind != NodeKind::Component {
+            continue;
+        }
+        let install_root = registry.install_root(node_id, &config);
+        let installed = installed_components(registry, &lock, node_id, &install_root, None)?;
+        let mut added = added_files(registry, &lock, node_id, &installed, workspace)?;
+        for (name, keys) in installed {
+            let mut files = keys
+                .iter()
+                .map(|key| file_status(registry, workspace, key, &lock.files[key]))
+                .collect::<CoreResult<Vec<_>>>()?;
+            files.extend(added.remove(&name).unwrap_or_default());
+            components.push(ComponentStatus {
+                outdated: files.iter().any(|f| {
+                    matches!(
+                        f.status,
+                        FileStatus::UpstreamChanged | FileStatus::BothChanged | FileStatus::Added
+                    )
+                }),
+                modified: files.iter().any(|f| {
+                    matches!(
+                        f.status,
+                        FileStatus::Modified | FileStatus::BothChanged | FileStatus::Deleted
+                    )
+                }),
+                name,
+                node: node_id.to_string(),
+                files,
+            });
+        }
+    }
+    Ok(StatusReport { components })
+}
+
+/// Locked files of `node_id` grouped by declared component name, restricted to
+/// `names` (which must all be installed) when given.
+pub(crate) fn installed_components(
+    registry: &Registry,
+    lock: &Lockfile,
+    node_id: &str,
+    install_root: &str,
+    names: Option<&[String]>,
+) -> CoreResult<BTreeMap<String, Vec<String>>> {
+    let declared = select_names(registry.get(node_id)?, "all", None)?;
+    let components_root = format!("{install_root}/components/");
+    let mut installed: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for (key, entry) in lock.node_files(node_id) {
+        installed
+            .entry(component_name(key, entry, &components_root, &declared))
+            .or_default()
+            .push(key.clone());
+    }
+    if let Some(names) = names {
+        let missing: Vec<String> = names
+            .iter()
+            .filter(|n| !installed.contains_key(*n))
+            .cloned()
+            .collect();
+        if !missing.is_empty() {
+            return Err(CoreError::InvalidNames(missing));
+        }
+        installed.retain(|name, _| names.contains(name));
+    }
+    Ok(installed)
+}
+
+/// Variant a component was installed from, judged by where its recorded sources live.
+pub(crate) fn installed_variant<'v>(
+    registry: &Registry,
+    lock: &Lockfile,
+    keys: &[String],
+    variants: &'v BTreeMap<String, String>,
+) -> Option<&'v str> {
+    let entry = keys.first().and_then(|key| lock.files.get(key))?;
+    let source = source_path(registry, entry);
+    variants
+        .iter()
+        .filter(|(_, root)| source.starts_with(root))
+        .max_by_key(|(_, root)| root.len())
+        .map(|(framework, _)| framework.as_str())
+}
+
+/// Absolute pack source of a lock entry.
+pub(crate) fn source_path(registry: &Registry, entry: &LockEntry) -> PathBuf {
+    match registry.pack_for(&entry.node) {
+        Some(pack) => Path::new(&pack.root).join(&entry.source),
+        None => PathBuf::from(&entry.source),
+    }
+}
+
+fn file_status(
+    registry: &Registry,
+    workspace: &Path,
+    key: &str,
+    entry: &LockEntry,
+) -> CoreResult<FileStatusReport> {
+    let source = source_path(registry, entry);
+    let local = workspace.join(key);
+    let upstream = source
+        .is_file()
+        .then(|| digest(&source))
+        .transpose()?
+        .map(|d| d.sha256);
+    // Rendered templates differ from their source; compare the source itself
+    let recorded = entry.source_sha256.as_ref().unwrap_or(&entry.sha256);
+    let upstream_changed = upstream.as_ref() != Some(recorded);
+    let status = if !local.is_file() {
+        FileStatus::Deleted
+    } else {
+        let local_changed = digest(&local)?.sha256 != entry.sha256;
+        match (local_changed, upstream_changed) {
+            (false, false) => FileStatus::Unchanged,
+            (true, false) => FileStatus::Modified,
+            (false, true) => FileStatus::UpstreamChanged,
+            (true, true) => FileStatus::BothChanged,
+        }
+    };
+    Ok(FileStatusReport {
+        path: key.to_string(),
+        status,
+        source: source.display().to_string(),
+        source_missing: upstream.is_none(),
+    })
+}
+
+/// Pack files of each installed component without a lock entry, as `ui update`
+/// would render them. Files using undefined template variables are left out.
+fn added_files(
+    registry: &Registry,
+    lock: &Lockfile,
+    node_id: &str,
+    installed: &BTreeMap<String, Vec<String>>,
+    workspace: &Path,
+) -> CoreResult<BTreeMap<String, Vec<FileStatusReport>>> {
+    let node = registry.get(node_id)?;
+    let NodePayload::Component {
+        source_root,
+        variants,
+        ..
+    } = &node.payload
+    else {
+        return Ok(BTreeMap::new());
+    };
+    let config = WorkspaceConfig::load(workspace)?;
+    let to_root = workspace
+        .join(registry.install_root(node_id, &config))
+        .join("components");
+    let mut vars = template_vars(registry, node_id, &Selections::new(), workspace)?;
+    for (_, entry) in lock.node_files(node_id) {
+        vars.extend(entry.vars.clone());
+    }
+    let mut ignore = IgnoreRules::for_node(registry, node_id)?;
+
+    let mut added: BTreeMap<String, Vec<FileStatusReport>> = BTreeMap::new();
+    for (name, keys) in installed {
+        let framework = installed_variant(registry, lock, keys, variants);
+        let resolved = resolve_variant(Path::new(source_root), variants, framework, name);
+        let pairs = match resolved.map(|(source, _)| source) {
+            Some(ComponentSource::Dir(dir)) => {
+                let root = dir.parent().unwrap_or(Path::new(source_root));
+                ignore.tree_files(root, &dir, &to_root.join(name))?
+            }
+            Some(ComponentSource::File(file)) => {
+                let to = to_root.join(file.file_name().unwrap_or_default());
+                vec![(file, to)]
+            }
+            None => Vec::new(),
+        };
+        for (from, to) in pairs {
+            let file = render_file(&from, &to, workspace, &vars)?;
+            let key = relative_key(&file.to, workspace);
+            if !file.undefined.is_empty() || lock.files.contains_key(&key) {
+                continue;
+            }
+            added
+                .entry(name.clone())
+                .or_default()
+                .push(FileStatusReport {
+                    path: key,
+                    status: FileStatus::Added,
+                    source: from.display().to_string(),
+                    source_missing: false,
+                });
+        }
+    }
+    Ok(added)
+}
+
+/// The component recorded at install, else the one the path was installed
+/// under: `<root>/components/<Name>/...` or `<root>/components/<name>.tsx`,
+/// mapped back to the declared name it is a variant of.
+fn component_name(
+    key: &str,
+    entry: &LockEntry,
+    components_root: &str,
+    declared: &[String],
+) -> String {
+    if let Some(component) = &entry.component {
+        return component.clone();
+    }
+    let Some(rest) = key.strip_prefix(components_root) else {
+        return key.to_string();
+    };
+    let base = match rest.split_once('/') {
+        Some((dir, _)) => dir,
+        None => rest.rsplit_once('.').map_or(rest, |(stem, _)| stem),
+    };
+    declared
+        .iter()
+        .find(|name| name_variants(name).iter().any(|v| v == base))
+        .cloned()
+        .unwrap_or_else(|| base.to_string())
+}
@@ -0,0 +1,232 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+
+use entity_core::error::{CoreError, CoreResult};
+use entity_core::registry::Registry;
+use entity_core::selections::Selections;
+use entity_core::workspace::WorkspaceConfig;
+use regex::{Captures, Regex};
+use serde_json::Value;
+
+use crate::lock::relative_key;
+use crate::package_json::PACKAGE_JSON;
+use crate::util::normalize;
+
+/// Sources with this suffix have their content rendered; the suffix is dropped
+/// from the destination.
+pub const TEMPLATE_SUFFIX: &str = ".tmpl";
+
+/// Template variables by dotted name.
+pub(crate) type Vars = BTreeMap<String, String>;
+
+/// `{{ name }}` inside `.tmpl` files.
+static CONTENT_VAR: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"\{\{\s*([A-Za-z_][A-Za-z0-9_-]*(?:\.[A-Za-z0-9_-]+)*)\s*\}\}").unwrap()
+});
+
+/// `__name__` in destination paths. Undotted names only count when a variable
+/// has that name, so `__init__.py` and `__tests__` are copied as they are.
+static PATH_VAR: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"__([A-Za-z][A-Za-z0-9_-]*(?:\.[A-Za-z0-9_-]+)*)__").unwrap());
+
+/// Lockfiles that identify the workspace's package manager, in priority order.
+const PACKAGE_MANAGER_LOCKS: [(&str, &str); 5] = [
+    ("pnpm-lock.yaml", "pnpm"),
+    ("yarn.lock", "yarn"),
+    ("bun.lock", "bun"),
+    ("bun.lockb", "bun"),
+    ("package-lock.json", "npm"),
+];
+
+/// Variables available to `node_id`'s templates. Later sources win: pack
+/// manifest `variables`, then the pack and workspace facts, then selections.
+pub(crate) fn template_vars(
+    registry: &Registry,
+    node_id: &str,
+    selections: &Selections,
+    workspace: &Path,
+) -> CoreResult<Vars> {
+    let config = WorkspaceConfig::load(workspace)?;
+    let install_root = registry.install_root(node_id, &config);
+    let mut vars = Vars::new();
+    if let Some(pack) = registry.pack_for(node_id) {
+        vars.extend(pack.manifest.variables.clone());
+        vars.insert("pack.name".into(), pack.name.clone());
+        if let Some(version) = &pack.manifest.version {
+            vars.insert("pack.version".into(), version.clone());
+        }
+    }
+    vars.insert("pack.installRoot".into(), install_root.clone());
+    vars.insert("node.id".into(), node_id.to_string());
+
+    let package: Option<Value> = fs::read_to_string(workspace.join(PACKAGE_JSON))
+        .ok()
+        .and_then(|text| serde_json::from_str(&text).ok());
+    let name = package
+        .as_ref()
+        .and_then(|p| p.get("name"))
+        .and_then(Value::as_str)
+        .map(str::to_string)
+        .or_else(|| {
+            workspace
+                .canonicalize()
+                .ok()?
+                .file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+        });
+    if let Some(name) = name {
+        vars.insert("workspace.name".into(), name);
+    }
+    let declared = package
+        .as_ref()
+        .and_then(|p| p.get("packageManager"))
+        .and_then(Value::as_str)
+        .map(|pm| pm.split('@').next().unwrap_or(pm).to_string());
+    let detected = PACKAGE_MANAGER_LOCKS
+        .iter()
+        .find(|(lock, _)| workspace.join(lock).is_file())
+        .map(|(_, pm)| pm.to_string());
+    if let Some(pm) = declared.or(detected) {
+        vars.insert("workspace.packageManager".into(), pm);
+    }
+    vars.insert("workspace.installRoot".into(), install_root);
+
+    flatten(&mut vars, "", &selections.to_value());
+    Ok(vars)
+}
+
+/// The selections alone, as `template_vars` adds them last.
+pub(crate) fn selection_vars(selections: &Selections) -> Vars {
+    let mut vars = Vars::new();
+    flatten(&mut vars, "", &selections.to_value());
+    vars
+}
+
+/// Selections as dotted keys. Strings render as they are, string arrays joined
+/// with `,`, other values as JSON.
+fn flatten(vars: &mut Vars, prefix: &str, value: &Value) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map {
+                let key = if prefix.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{prefix}.{key}")
+                };
+                flatten(vars, &key, value);
+            }
+        }
+        Value::Null => {}
+        Value::String(s) => {
+            vars.insert(prefix.to_string(), s.clone());
+        }
+        Value::Array(items) if items.iter().all(Value::is_string) => {
+            let items: Vec<&str> = items.iter().filter_map(Value::as_str).collect();
+            vars.insert(prefix.to_string(), items.join(","));
+        }
+        other => {
+            vars.insert(prefix.to_string(), other.to_string());
+        }
+    }
+}
+
+/// One source file after rendering its destination (and content, for templates).
+#[derive(Debug)]
+pub(crate) struct RenderedFile {
+    pub(crate) to: PathBuf,
+    /// Rendered content of `.tmpl` sources; `None` when the source is copied as is
+    pub(crate) content: Option<Vec<u8>>,
+    /// Variables the path and content referenced, with their values
+    pub(crate) used: Vars,
+    pub(crate) undefined: BTreeSet<String>,
+}
+
+/// Render `to`'s segments below `root` and, for `.tmpl` sources, the content of `from`.
+pub(crate) fn render_file(
+    from: &Path,
+    to: &Path,
+    root: &Path,
+    vars: &Vars,
+) -> CoreResult<RenderedFile> {
+    let mut rendered = RenderedFile {
+        to: to.to_path_buf(),
+        content: None,
+        used: Vars::new(),
+        undefined: BTreeSet::new(),
+    };
+    if to.starts_with(root) {
+        let key = relative_key(to, root);
+        rendered.to = root.join(substitute_path(&key, vars, &mut rendered)?);
+        if !normalize(&rendered.to).starts_with(normalize(root)) {
+            return Err(CoreError::InvalidSelection(format!(
+                "{key} renders outside {}",
+                root.display()
+            )));
+        }
+    }
+    if !from.to_string_lossy().ends_with(TEMPLATE_SUFFIX) {
+        return Ok(rendered);
+    }
+    let path = rendered.to.to_string_lossy().into_owned();
+    if let Some(stripped) = path.strip_suffix(TEMPLATE_SUFFIX) {
+        rendered.to = PathBuf::from(stripped);
+    }
+    let text = String::from_utf8(fs::read(from)?).map_err(|_| {
+        CoreError::InvalidDescriptor(format!("template {} is not UTF-8", from.display()))
+    })?;
+    let text = substitute(&CONTENT_VAR, &text, vars, &mut rendered);
+    rendered.content = Some(text.into_bytes());
+    Ok(rendered)
+}
+
+/// Render the `__name__` segments of `key`. Values can't add or leave directories.
+fn substitute_path(key: &str, vars: &Vars, rendered: &mut RenderedFile) -> CoreResult<String> {
+    let mut invalid = Vec::new();
+    let key = PATH_VAR
+        .replace_all(key, |caps: &Captures| {
+            let name = &caps[1];
+            match vars.get(name) {
+                Some(value) if value.contains(['/', '\\']) || value.contains("..") => {
+                    invalid.push(format!("{name}={value:?}"));
+                    caps[0].to_string()
+                }
+                Some(value) => {
+                    rendered.used.insert(name.to_string(), value.clone());
+                    value.clone()
+                }
+                None if !name.contains('.') => caps[0].to_string(),
+                None => {
+                    rendered.undefined.insert(name.to_string());
+                    caps[0].to_string()
+                }
+            }
+        })
+        .into_owned();
+    if !invalid.is_empty() {
+        return Err(CoreError::InvalidSelection(format!(
+            "path variables can't contain path separators or `..`: {}",
+            invalid.join(", ")
+        )));
+    }
+    Ok(key)
+}
+
+fn substitute(pattern: &Regex, text: &str, vars: &Vars, rendered: &mut RenderedFile) -> String {
+    pattern
+        .replace_all(text, |caps: &Captures| {
+            let name = &caps[1];
+            match vars.get(name) {
+                Some(value) => {
+                    rendered.used.insert(name.to_string(), value.clone());
+                    value.clone()
+                }
+                None => {
+                    rendered.undefined.insert(name.to_string());
+                    caps[0].to_string()
+                }
+            }
+        })
+        .into_owned()
+}
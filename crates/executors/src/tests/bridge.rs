@@ -2,6 +2,7 @@ use super::common::*;
 use std::fs;
 
 use crate::BridgeExecutor;
+use entity_core::selections::Selections;
 
 #[test]
 fn scaffold_and_state_roundtrip() {
@@ -29,10 +30,14 @@ fn scaffold_and_state_roundtrip() {
     let reg = bridge_registry(node);
     let exec = BridgeExecutor::new(&reg);
 
-    let report = exec.scaffold("x:bridge:test", workspace.path()).unwrap();
+    let report = exec
+        .scaffold("x:bridge:test", &Selections::new(), workspace.path())
+        .unwrap();
     assert_eq!(report.copied.len(), 1);
 
-    let info = exec.spawn_descriptor("x:bridge:test").unwrap();
+    let info = exec
+        .spawn_descriptor("x:bridge:test", &Selections::new())
+        .unwrap();
     let packs_root = packs.path().to_path_buf();
     exec.persist_state(
         "x:bridge:test",
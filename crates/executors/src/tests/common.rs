@@ -2,6 +2,9 @@ use std::fs;
 
 use entity_core::model::{Node, NodeKind, NodePayload};
 use entity_core::registry::Registry;
+use entity_core::selections::Selections;
+
+use crate::{MODE_KEY, NAMES_KEY};
 
 pub(crate) fn temp_dir() -> tempfile::TempDir {
     tempfile::tempdir().unwrap()
@@ -14,6 +17,15 @@ pub(crate) fn write_file(path: &std::path::Path, content: &str) {
     fs::write(path, content).unwrap();
 }
 
+/// Component selections as built from `--mode` / `--names`.
+pub(crate) fn picks(mode: &str, names: Option<&[&str]>) -> Selections {
+    let selections = Selections::new().with(MODE_KEY, mode);
+    match names {
+        Some(names) => selections.with(NAMES_KEY, names.to_vec()),
+        None => selections,
+    }
+}
+
 pub(crate) fn bridge_registry(node: Node) -> Registry {
     Registry::new(vec![node]).unwrap()
 }
@@ -38,6 +50,10 @@ pub(crate) fn doc_node(id: &str, path: &std::path::Path) -> Node {
         title: "t".into(),
         meta: Default::default(),
         prerequisites: vec![],
+        requires: vec![],
+        related_to: vec![],
+        conflict_policy: None,
+        package_json: None,
         payload: NodePayload::Doc {
             content_path: path.display().to_string(),
         },
@@ -53,8 +69,34 @@ pub(crate) fn component_node(id: &str, source_root: &std::path::Path, names: &[&
             .into_iter()
             .collect(),
         prerequisites: vec![],
+        requires: vec![],
+        related_to: vec![],
+        conflict_policy: None,
+        package_json: None,
         payload: NodePayload::Component {
             source_root: source_root.display().to_string(),
+            components: Default::default(),
+            variants: Default::default(),
+            exclude: Vec::new(),
+        },
+    }
+}
+
+pub(crate) fn setup_node(id: &str, template_root: &std::path::Path) -> Node {
+    Node {
+        id: id.into(),
+        kind: NodeKind::Setup,
+        title: "setup".into(),
+        meta: Default::default(),
+        prerequisites: vec![],
+        requires: vec![],
+        related_to: vec![],
+        conflict_policy: None,
+        package_json: None,
+        payload: NodePayload::Setup {
+            template_root: template_root.display().to_string(),
+            commands: None,
+            exclude: Vec::new(),
         },
     }
 }
@@ -72,6 +114,10 @@ pub(crate) fn bridge_node(
         title: "bridge".into(),
         meta: Default::default(),
         prerequisites: vec![],
+        requires: vec![],
+        related_to: vec![],
+        conflict_policy: None,
+        package_json: None,
         payload: NodePayload::Bridge {
             template_root: template_root.map(|p| p.display().to_string()),
             runner: runner.map(|p| p.display().to_string()),
@@ -79,6 +125,7 @@ pub(crate) fn bridge_node(
             spawn: None,
             logs_path: logs_path.map(|p| p.display().to_string()),
             heartbeat_interval_ms: Some(5_000),
+            exclude: Vec::new(),
         },
     }
 }
@@ -1,6 +1,13 @@
 use super::common::*;
 
-use crate::ComponentsExecutor;
+use crate::{
+    ComponentsExecutor, FRAMEWORK_KEY, FileAction, FileStatus, LOCK_FILE, Lockfile, MergeConflict,
+    UpdateAction, WriteOptions,
+};
+use entity_core::error::CoreError;
+use entity_core::model::{ComponentSpec, ConflictPolicy, NodePayload, Prerequisite};
+use entity_core::pack::{Pack, PackManifest};
+use entity_core::registry::Registry;
 
 #[test]
 fn install_mode_all_names_present_errors() {
@@ -10,8 +17,7 @@ fn install_mode_all_names_present_errors() {
     let err = exec
         .install(
             "x:comp:install",
-            "all",
-            Some(vec!["SignIn".into()]),
+            &picks("all", Some(&["SignIn"])),
             ws.path(),
         )
         .unwrap_err();
@@ -27,8 +33,7 @@ fn install_single_copies_files() {
     let rep = exec
         .install(
             "x:comp:install",
-            "single",
-            Some(vec!["SignIn".into()]),
+            &picks("single", Some(&["SignIn"])),
             ws.path(),
         )
         .unwrap();
@@ -48,8 +53,7 @@ fn install_multiple_validates_and_copies_many() {
     let rep = exec
         .install(
             "x:comp:install",
-            "multiple",
-            Some(vec!["SignIn".into(), "UserMenu".into()]),
+            &picks("multiple", Some(&["SignIn", "UserMenu"])),
             ws.path(),
         )
         .unwrap();
@@ -74,8 +78,7 @@ fn install_invalid_names_reports_list() {
     let err = exec
         .install(
             "x:comp:install",
-            "multiple",
-            Some(vec!["Nope".into(), "SignIn".into()]),
+            &picks("multiple", Some(&["Nope", "SignIn"])),
             ws.path(),
         )
         .unwrap_err();
@@ -96,8 +99,7 @@ fn install_overwrite_copies_and_counts_files() {
     let rep1 = exec
         .install(
             "x:comp:install",
-            "single",
-            Some(vec!["AuthProvider".into()]),
+            &picks("single", Some(&["AuthProvider"])),
             ws.path(),
         )
         .unwrap();
@@ -110,10 +112,722 @@ fn install_overwrite_copies_and_counts_files() {
     let rep2 = exec
         .install(
             "x:comp:install",
-            "single",
-            Some(vec!["AuthProvider".into()]),
+            &picks("single", Some(&["AuthProvider"])),
             ws.path(),
         )
         .unwrap();
     assert_eq!(rep2.copied[0].count, 2);
 }
+
+#[test]
+fn install_uses_pack_install_root_and_workspace_override() {
+    let dir = temp_dir();
+    let root = dir.path().join("microsoft/components");
+    write_file(&root.join("SignIn/index.tsx"), "export {};\n");
+    let mut reg = Registry::default();
+    reg.add_pack(Pack::new(
+        "microsoft",
+        &dir.path().join("microsoft"),
+        PackManifest::default(),
+    ));
+    reg.insert_in_pack(
+        component_node("ms:comp:install", &root, &["SignIn"]),
+        "microsoft",
+    )
+    .unwrap();
+    let exec = ComponentsExecutor::new(&reg);
+
+    let ws = temp_dir();
+    exec.install("ms:comp:install", &picks("all", None), ws.path())
+        .unwrap();
+    assert!(
+        ws.path()
+            .join("microsoft/components/SignIn/index.tsx")
+            .exists()
+    );
+    assert!(!ws.path().join("entity-auth").exists());
+
+    write_file(
+        &ws.path().join("entitycli.json"),
+        r#"{ "installRoots": { "microsoft": "src/ms" } }"#,
+    );
+    exec.install("ms:comp:install", &picks("all", None), ws.path())
+        .unwrap();
+    assert!(
+        ws.path()
+            .join("src/ms/components/SignIn/index.tsx")
+            .exists()
+    );
+}
+
+#[test]
+fn install_reports_schema_violations_with_pointers() {
+    let (mut reg, _dir) = component_registry_fixture(&["SignIn"]);
+    let mut node = reg.get("x:comp:install").unwrap().clone();
+    node.prerequisites = vec![
+        Prerequisite {
+            key: "selection.mode".into(),
+            schema: serde_json::json!({ "enum": ["single", "multiple"] }),
+            optional: false,
+        },
+        Prerequisite {
+            key: "selection.names".into(),
+            schema: serde_json::json!({ "type": "array", "items": { "enum": ["SignIn"] } }),
+            optional: true,
+        },
+    ];
+    reg = Registry::new(vec![node]).unwrap();
+    let exec = ComponentsExecutor::new(&reg);
+    let ws = temp_dir();
+
+    let err = exec
+        .install("x:comp:install", &picks("all", None), ws.path())
+        .unwrap_err();
+    let CoreError::SchemaViolations(list) = err else {
+        panic!("expected schema violations, got {err:?}");
+    };
+    assert_eq!(list[0].path, "/selection/mode");
+    assert_eq!(list[0].keyword, "enum");
+
+    let err = exec
+        .install(
+            "x:comp:install",
+            &picks("single", Some(&["Nope"])),
+            ws.path(),
+        )
+        .unwrap_err();
+    assert!(matches!(err, CoreError::InvalidNames(ref names) if names == &["Nope"]));
+}
+
+#[test]
+fn install_dry_run_plans_without_writing() {
+    let (reg, dir) = component_registry_fixture(&["SignIn"]);
+    write_file(
+        &dir.path().join("pack/components/SignIn/styles.css"),
+        ".a {}\n",
+    );
+    let ws = temp_dir();
+    let installed = ws.path().join("entity-auth/components/SignIn");
+    write_file(
+        &installed.join("index.tsx"),
+        "export const Component = () => null;\n",
+    );
+    write_file(&installed.join("styles.css"), "local edit\n");
+
+    let exec = ComponentsExecutor::new(&reg).with_options(WriteOptions {
+        dry_run: true,
+        ..Default::default()
+    });
+    let rep = exec
+        .install(
+            "x:comp:install",
+            &picks("single", Some(&["SignIn"])),
+            ws.path(),
+        )
+        .unwrap();
+
+    let plan = rep.plan.unwrap();
+    let actions: Vec<(FileAction, bool)> = plan
+        .files
+        .iter()
+        .map(|f| (f.action, f.existing.is_some()))
+        .collect();
+    assert_eq!(
+        actions,
+        [(FileAction::Unchanged, true), (FileAction::Overwrite, true)]
+    );
+    let css = &plan.files[1];
+    assert_eq!(css.source.size, 6);
+    assert_ne!(css.source.sha256, css.existing.as_ref().unwrap().sha256);
+    assert_eq!(
+        std::fs::read_to_string(installed.join("styles.css")).unwrap(),
+        "local edit\n"
+    );
+
+    // Nothing installed yet: every file is a create and no directory appears
+    let fresh = temp_dir();
+    let rep = exec
+        .install(
+            "x:comp:install",
+            &picks("single", Some(&["SignIn"])),
+            fresh.path(),
+        )
+        .unwrap();
+    let plan = rep.plan.unwrap();
+    assert!(plan.files.iter().all(|f| f.action == FileAction::Create));
+    assert!(!fresh.path().join("entity-auth").exists());
+}
+
+#[test]
+fn install_conflict_policies_protect_local_edits() {
+    let (mut reg, _dir) = component_registry_fixture(&["SignIn"]);
+    let ws = temp_dir();
+    let installed = ws.path().join("entity-auth/components/SignIn/index.tsx");
+    let pick = picks("single", Some(&["SignIn"]));
+    let with_policy = |policy| WriteOptions {
+        conflict_policy: Some(policy),
+        ..Default::default()
+    };
+    write_file(&installed, "local edit\n");
+
+    let err = ComponentsExecutor::new(&reg)
+        .with_options(with_policy(ConflictPolicy::FailOnConflict))
+        .install("x:comp:install", &pick, ws.path())
+        .unwrap_err();
+    assert!(matches!(err, CoreError::WriteConflict(ref files) if files.len() == 1));
+    assert_eq!(std::fs::read_to_string(&installed).unwrap(), "local edit\n");
+
+    let rep = ComponentsExecutor::new(&reg)
+        .with_options(with_policy(ConflictPolicy::SkipExisting))
+        .install("x:comp:install", &pick, ws.path())
+        .unwrap();
+    assert_eq!(rep.copied[0].count, 0);
+    assert!(rep.notes.iter().any(|n| n.starts_with("kept existing")));
+    assert_eq!(std::fs::read_to_string(&installed).unwrap(), "local edit\n");
+
+    let rep = ComponentsExecutor::new(&reg)
+        .with_options(with_policy(ConflictPolicy::Backup))
+        .install("x:comp:install", &pick, ws.path())
+        .unwrap();
+    let note = rep
+        .notes
+        .iter()
+        .find(|n| n.starts_with("backed up"))
+        .unwrap();
+    let backup = note.rsplit(" to ").next().unwrap();
+    assert!(backup.ends_with(".orig"));
+    assert_eq!(std::fs::read_to_string(backup).unwrap(), "local edit\n");
+    assert_ne!(std::fs::read_to_string(&installed).unwrap(), "local edit\n");
+
+    // The node default applies without a command override; entitycli.json beats it
+    let mut node = reg.get("x:comp:install").unwrap().clone();
+    node.conflict_policy = Some(ConflictPolicy::FailOnConflict);
+    reg = Registry::new(vec![node]).unwrap();
+    write_file(&installed, "edited again\n");
+    let exec = ComponentsExecutor::new(&reg);
+    assert_eq!(
+        exec.install("x:comp:install", &pick, ws.path())
+            .unwrap_err()
+            .code(),
+        "WRITE_CONFLICT"
+    );
+    write_file(
+        &ws.path().join("entitycli.json"),
+        r#"{ "conflictPolicy": "skip-existing" }"#,
+    );
+    let rep = exec.install("x:comp:install", &pick, ws.path()).unwrap();
+    assert_eq!(rep.notes[0], "Conflict policy: skip-existing");
+}
+
+#[test]
+fn install_records_written_files_in_lockfile() {
+    let dir = temp_dir();
+    let root = dir.path().join("microsoft/components");
+    write_file(&root.join("SignIn/index.tsx"), "export {};\n");
+    let mut reg = Registry::default();
+    reg.add_pack(Pack::new(
+        "microsoft",
+        &dir.path().join("microsoft"),
+        PackManifest {
+            version: Some("1.2.0".into()),
+            ..Default::default()
+        },
+    ));
+    reg.insert_in_pack(
+        component_node("ms:comp:install", &root, &["SignIn"]),
+        "microsoft",
+    )
+    .unwrap();
+    let exec = ComponentsExecutor::new(&reg);
+    let ws = temp_dir();
+
+    exec.install("ms:comp:install", &picks("all", None), ws.path())
+        .unwrap();
+    let lock = Lockfile::load(ws.path()).unwrap();
+    let entry = &lock.files["microsoft/components/SignIn/index.tsx"];
+    assert_eq!(entry.node, "ms:comp:install");
+    assert_eq!(entry.pack.as_deref(), Some("microsoft"));
+    assert_eq!(entry.pack_version.as_deref(), Some("1.2.0"));
+    assert_eq!(entry.source, "components/SignIn/index.tsx");
+    assert_eq!(
+        entry.sha256,
+        crate::digest(&root.join("SignIn/index.tsx"))
+            .unwrap()
+            .sha256
+    );
+
+    // Dry runs leave the lockfile alone
+    std::fs::remove_file(ws.path().join(LOCK_FILE)).unwrap();
+    ComponentsExecutor::new(&reg)
+        .with_options(WriteOptions {
+            dry_run: true,
+            ..Default::default()
+        })
+        .install("ms:comp:install", &picks("all", None), ws.path())
+        .unwrap();
+    assert!(!ws.path().join(LOCK_FILE).exists());
+}
+
+#[test]
+fn reinstall_drops_lock_entries_of_files_the_pack_removed() {
+    let (reg, dir) = component_registry_fixture(&["SignIn"]);
+    let exec = ComponentsExecutor::new(&reg);
+    let ws = temp_dir();
+    write_file(
+        &dir.path().join("pack/components/SignIn/extra.tsx"),
+        "extra\n",
+    );
+    exec.install("x:comp:install", &picks("all", None), ws.path())
+        .unwrap();
+    let lock = Lockfile::load(ws.path()).unwrap();
+    assert!(lock.files.keys().any(|k| k.ends_with("SignIn/extra.tsx")));
+
+    std::fs::remove_file(dir.path().join("pack/components/SignIn/extra.tsx")).unwrap();
+    let report = exec
+        .install("x:comp:install", &picks("all", None), ws.path())
+        .unwrap();
+    let lock = Lockfile::load(ws.path()).unwrap();
+    let stale: Vec<&String> = lock
+        .files
+        .keys()
+        .filter(|k| k.ends_with("SignIn/extra.tsx"))
+        .collect();
+    assert!(stale.is_empty());
+    assert!(!lock.files.is_empty());
+    assert!(report.notes.iter().any(|n| n.ends_with(
+        "SignIn/extra.tsx is no longer in the pack; dropped from the lockfile, file kept"
+    )));
+}
+
+#[test]
+fn status_classifies_local_and_upstream_changes() {
+    let dir = temp_dir();
+    let root = dir.path().join("microsoft/components");
+    for file in ["same", "local", "upstream", "both", "gone"] {
+        write_file(&root.join(format!("SignIn/{file}.tsx")), "v1\n");
+    }
+    write_file(&root.join("Avatar.tsx"), "v1\n");
+    let mut reg = Registry::default();
+    reg.add_pack(Pack::new(
+        "microsoft",
+        &dir.path().join("microsoft"),
+        PackManifest::default(),
+    ));
+    reg.insert_in_pack(
+        component_node("ms:comp:install", &root, &["SignIn", "Avatar"]),
+        "microsoft",
+    )
+    .unwrap();
+    let exec = ComponentsExecutor::new(&reg);
+    let ws = temp_dir();
+    exec.install("ms:comp:install", &picks("all", None), ws.path())
+        .unwrap();
+
+    let installed = ws.path().join("microsoft/components/SignIn");
+    write_file(&installed.join("local.tsx"), "edited\n");
+    write_file(&installed.join("both.tsx"), "edited\n");
+    std::fs::remove_file(installed.join("gone.tsx")).unwrap();
+    write_file(&root.join("SignIn/upstream.tsx"), "v2\n");
+    write_file(&root.join("SignIn/both.tsx"), "v2\n");
+
+    let report = exec.status(ws.path()).unwrap();
+    let names: Vec<&str> = report.components.iter().map(|c| c.name.as_str()).collect();
+    assert_eq!(names, ["Avatar", "SignIn"]);
+    let avatar = &report.components[0];
+    assert!(!avatar.outdated && !avatar.modified);
+    assert_eq!(avatar.files[0].status, FileStatus::Unchanged);
+
+    let sign_in = &report.components[1];
+    assert!(sign_in.outdated && sign_in.modified);
+    let status = |name: &str| {
+        sign_in
+            .files
+            .iter()
+            .find(|f| f.path == format!("microsoft/components/SignIn/{name}.tsx"))
+            .unwrap()
+            .status
+    };
+    assert_eq!(status("same"), FileStatus::Unchanged);
+    assert_eq!(status("local"), FileStatus::Modified);
+    assert_eq!(status("upstream"), FileStatus::UpstreamChanged);
+    assert_eq!(status("both"), FileStatus::BothChanged);
+    assert_eq!(status("gone"), FileStatus::Deleted);
+}
+
+#[test]
+fn update_merges_local_edits_with_pack_changes() {
+    let dir = temp_dir();
+    let root = dir.path().join("microsoft/components");
+    let original = "line 1\nline 2\nline 3\nline 4\nline 5\n";
+    for file in ["clean", "merge", "clash"] {
+        write_file(&root.join(format!("SignIn/{file}.tsx")), original);
+    }
+    let mut reg = Registry::default();
+    reg.add_pack(Pack::new(
+        "microsoft",
+        &dir.path().join("microsoft"),
+        PackManifest::default(),
+    ));
+    reg.insert_in_pack(
+        component_node("ms:comp:install", &root, &["SignIn"]),
+        "microsoft",
+    )
+    .unwrap();
+    let exec = ComponentsExecutor::new(&reg);
+    let ws = temp_dir();
+    exec.install("ms:comp:install", &picks("all", None), ws.path())
+        .unwrap();
+
+    let installed = ws.path().join("microsoft/components/SignIn");
+    let pack = "line 1\nline 2 (pack)\nline 3\nline 4\nline 5\n";
+    for file in ["clean", "merge", "clash"] {
+        write_file(&root.join(format!("SignIn/{file}.tsx")), pack);
+    }
+    write_file(&root.join("SignIn/new.tsx"), "new\n");
+    write_file(
+        &installed.join("merge.tsx"),
+        "line 1\nline 2\nline 3\nline 4\nline 5 (local)\n",
+    );
+    write_file(
+        &installed.join("clash.tsx"),
+        "line 1\nline 2 (local)\nline 3\nline 4\nline 5\n",
+    );
+
+    let report = exec.update("ms:comp:install", None, ws.path()).unwrap();
+    let action = |name: &str| {
+        report
+            .files
+            .iter()
+            .find(|f| f.path == format!("microsoft/components/SignIn/{name}.tsx"))
+            .unwrap()
+            .action
+    };
+    assert_eq!(action("clean"), UpdateAction::Updated);
+    assert_eq!(action("merge"), UpdateAction::Merged);
+    assert_eq!(action("clash"), UpdateAction::Conflict);
+    assert_eq!(action("new"), UpdateAction::Created);
+
+    let read = |name: &str| std::fs::read_to_string(installed.join(name)).unwrap();
+    assert_eq!(read("clean.tsx"), pack);
+    assert_eq!(
+        read("merge.tsx"),
+        "line 1\nline 2 (pack)\nline 3\nline 4\nline 5 (local)\n"
+    );
+    assert!(
+        read("clash.tsx")
+            .contains("<<<<<<< ours\nline 2 (local)\n=======\nline 2 (pack)\n>>>>>>> theirs\n")
+    );
+    assert_eq!(
+        report.conflicts,
+        [MergeConflict {
+            path: "microsoft/components/SignIn/clash.tsx".into(),
+            line: 2,
+            ours: "line 2 (local)\n".into(),
+            theirs: "line 2 (pack)\n".into(),
+        }]
+    );
+
+    // The pack version is the new base: a second update has nothing to do
+    let again = exec.update("ms:comp:install", None, ws.path()).unwrap();
+    assert!(
+        again
+            .files
+            .iter()
+            .all(|f| f.action == UpdateAction::UpToDate)
+    );
+    assert!(again.conflicts.is_empty());
+
+    let err = exec
+        .update("ms:comp:install", Some(&["Avatar".into()]), ws.path())
+        .unwrap_err();
+    assert!(matches!(err, CoreError::InvalidNames(names) if names == ["Avatar"]));
+}
+
+#[test]
+fn update_notes_that_conflict_policies_do_not_apply() {
+    let (reg, _dir) = component_registry_fixture(&["SignIn"]);
+    let ws = temp_dir();
+    ComponentsExecutor::new(&reg)
+        .install("x:comp:install", &picks("all", None), ws.path())
+        .unwrap();
+    let report = ComponentsExecutor::new(&reg)
+        .with_options(WriteOptions {
+            conflict_policy: Some(ConflictPolicy::FailOnConflict),
+            ..Default::default()
+        })
+        .update("x:comp:install", None, ws.path())
+        .unwrap();
+    assert_eq!(
+        report.notes[0],
+        "Conflict policy fail-on-conflict does not apply: `ui update` always merges"
+    );
+}
+
+#[test]
+fn uninstall_removes_installed_files_and_prunes_dirs() {
+    let dir = temp_dir();
+    let root = dir.path().join("microsoft/components");
+    write_file(&root.join("SignIn/parts/form.tsx"), "form\n");
+    write_file(&root.join("SignIn/index.tsx"), "index\n");
+    write_file(&root.join("Avatar.tsx"), "avatar\n");
+    let mut reg = Registry::default();
+    reg.add_pack(Pack::new(
+        "microsoft",
+        &dir.path().join("microsoft"),
+        PackManifest::default(),
+    ));
+    reg.insert_in_pack(
+        component_node("ms:comp:install", &root, &["SignIn", "Avatar"]),
+        "microsoft",
+    )
+    .unwrap();
+    let exec = ComponentsExecutor::new(&reg);
+    let ws = temp_dir();
+    exec.install("ms:comp:install", &picks("all", None), ws.path())
+        .unwrap();
+    let components = ws.path().join("microsoft/components");
+    write_file(&components.join("SignIn/index.tsx"), "edited\n");
+    write_file(&components.join("SignIn/notes.md"), "mine\n");
+
+    // Local edits block the uninstall unless forced
+    let err = exec
+        .uninstall(
+            "ms:comp:install",
+            Some(&["SignIn".into()]),
+            ws.path(),
+            false,
+        )
+        .unwrap_err();
+    assert!(
+        matches!(err, CoreError::EditedSinceInstall(files) if files == ["microsoft/components/SignIn/index.tsx"])
+    );
+    assert!(components.join("SignIn/parts/form.tsx").exists());
+
+    let report = exec
+        .uninstall("ms:comp:install", Some(&["SignIn".into()]), ws.path(), true)
+        .unwrap();
+    assert_eq!(report.removed.len(), 2);
+    assert_eq!(report.pruned, ["microsoft/components/SignIn/parts"]);
+    assert!(report.notes[0].contains("despite local edits"));
+    // Files the component did not install are left alone
+    assert!(components.join("SignIn/notes.md").exists());
+    assert!(!components.join("SignIn/parts").exists());
+    assert!(components.join("Avatar.tsx").exists());
+
+    let lock = Lockfile::load(ws.path()).unwrap();
+    let keys: Vec<&str> = lock.files.keys().map(String::as_str).collect();
+    assert_eq!(keys, ["microsoft/components/Avatar.tsx"]);
+
+    exec.uninstall("ms:comp:install", None, ws.path(), false)
+        .unwrap();
+    assert!(components.is_dir());
+    assert!(!components.join("Avatar.tsx").exists());
+    assert!(Lockfile::load(ws.path()).unwrap().files.is_empty());
+}
+
+#[test]
+fn installed_components_are_known_by_their_declared_name() {
+    let dir = temp_dir();
+    let root = dir.path().join("microsoft/components");
+    write_file(&root.join("sign-in.tsx"), "sign in\n");
+    write_file(&root.join("UserMenu/index.tsx"), "menu\n");
+    let mut reg = Registry::default();
+    reg.add_pack(Pack::new(
+        "microsoft",
+        &dir.path().join("microsoft"),
+        PackManifest::default(),
+    ));
+    reg.insert_in_pack(
+        component_node("ms:comp:install", &root, &["SignIn", "UserMenu"]),
+        "microsoft",
+    )
+    .unwrap();
+    let exec = ComponentsExecutor::new(&reg);
+    let ws = temp_dir();
+    exec.install("ms:comp:install", &picks("all", None), ws.path())
+        .unwrap();
+
+    // Files the pack added since install are listed too
+    write_file(&root.join("UserMenu/avatar.tsx"), "avatar\n");
+    let report = exec.status(ws.path()).unwrap();
+    let names: Vec<&str> = report.components.iter().map(|c| c.name.as_str()).collect();
+    assert_eq!(names, ["SignIn", "UserMenu"]);
+    let menu = &report.components[1];
+    assert!(menu.outdated && !menu.modified);
+    assert!(menu.files.iter().any(|f| {
+        f.path == "microsoft/components/UserMenu/avatar.tsx" && f.status == FileStatus::Added
+    }));
+
+    // Lockfiles that predate recorded components map paths back to declared names
+    let mut lock = Lockfile::load(ws.path()).unwrap();
+    for entry in lock.files.values_mut() {
+        entry.component = None;
+    }
+    std::fs::write(
+        ws.path().join(LOCK_FILE),
+        serde_json::to_vec_pretty(&lock).unwrap(),
+    )
+    .unwrap();
+    let report = exec.status(ws.path()).unwrap();
+    let names: Vec<&str> = report.components.iter().map(|c| c.name.as_str()).collect();
+    assert_eq!(names, ["SignIn", "UserMenu"]);
+
+    let update = exec
+        .update("ms:comp:install", Some(&["SignIn".into()]), ws.path())
+        .unwrap();
+    assert_eq!(update.files.len(), 1);
+    assert_eq!(update.files[0].action, UpdateAction::UpToDate);
+    let removed = exec
+        .uninstall(
+            "ms:comp:install",
+            Some(&["SignIn".into()]),
+            ws.path(),
+            false,
+        )
+        .unwrap();
+    assert_eq!(removed.removed, ["microsoft/components/sign-in.tsx"]);
+}
+
+#[test]
+fn install_pulls_in_required_components_and_reports_npm_packages() {
+    let dir = temp_dir();
+    let root = dir.path().join("components");
+    for name in ["SignIn", "Form", "Button", "Avatar"] {
+        write_file(&root.join(format!("{name}/index.tsx")), "export {};\n");
+    }
+    let mut node = component_node("c:install", &root, &["SignIn", "Form", "Button", "Avatar"]);
+    let spec = |requires: &[&str], npm: &[(&str, &str)]| ComponentSpec {
+        requires: requires.iter().map(|s| s.to_string()).collect(),
+        npm_dependencies: npm
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect(),
+    };
+    node.payload = NodePayload::Component {
+        source_root: root.display().to_string(),
+        components: [
+            ("SignIn".into(), spec(&["Form"], &[("zod", "^3.23.0")])),
+            (
+                "Form".into(),
+                spec(&["Button", "SignIn"], &[("zod", "^3.0.0")]),
+            ),
+            ("Button".into(), spec(&[], &[("clsx", "^2.1.0")])),
+        ]
+        .into(),
+        variants: Default::default(),
+        exclude: Vec::new(),
+    };
+    let reg = Registry::new(vec![node]).unwrap();
+    let ws = temp_dir();
+
+    let report = ComponentsExecutor::new(&reg)
+        .install("c:install", &picks("single", Some(&["SignIn"])), ws.path())
+        .unwrap();
+    assert_eq!(report.components, ["Button", "Form", "SignIn"]);
+    assert_eq!(report.copied.len(), 3);
+    assert!(!ws.path().join("entity-auth/components/Avatar").exists());
+    assert!(
+        ws.path()
+            .join("entity-auth/components/Button/index.tsx")
+            .exists()
+    );
+    assert_eq!(
+        serde_json::to_value(&report.npm_dependencies).unwrap(),
+        serde_json::json!({ "clsx": "^2.1.0", "zod": "^3.0.0" })
+    );
+    assert!(
+        report
+            .notes
+            .contains(&"Button installed as a dependency of Form".to_string())
+    );
+    assert!(
+        report
+            .notes
+            .contains(&"SignIn wants zod@^3.23.0; keeping zod@^3.0.0".to_string())
+    );
+}
+
+#[test]
+fn install_picks_the_framework_variant_of_the_workspace() {
+    let dir = temp_dir();
+    let root = dir.path().join("components");
+    write_file(&root.join("ui/Button/index.tsx"), "default button\n");
+    write_file(&root.join("ui/Card/index.tsx"), "default card\n");
+    write_file(&root.join("svelte/Button.svelte"), "svelte button\n");
+    write_file(&root.join("react/Button/index.tsx"), "react button\n");
+    let mut node = component_node("x:comp:install", &root.join("ui"), &["Button", "Card"]);
+    node.payload = NodePayload::Component {
+        source_root: root.join("ui").display().to_string(),
+        components: Default::default(),
+        variants: ["svelte", "react"]
+            .map(|f| (f.to_string(), root.join(f).display().to_string()))
+            .into(),
+        exclude: Vec::new(),
+    };
+    let reg = Registry::new(vec![node]).unwrap();
+    let exec = ComponentsExecutor::new(&reg);
+    let sveltekit = r#"{ "devDependencies": { "@sveltejs/kit": "^2.0.0", "react": "^18.0.0" } }"#;
+
+    let ws = temp_dir();
+    write_file(&ws.path().join("package.json"), sveltekit);
+    let report = exec
+        .install("x:comp:install", &picks("all", None), ws.path())
+        .unwrap();
+    assert_eq!(report.framework.as_deref(), Some("svelte"));
+    assert_eq!(
+        report.variants,
+        [("Button".to_string(), "svelte".to_string())].into()
+    );
+    assert!(
+        report
+            .notes
+            .contains(&"Card has no svelte variant; installed the default".to_string())
+    );
+    let installed = ws.path().join("entity-auth/components");
+    assert_eq!(
+        std::fs::read_to_string(installed.join("Button.svelte")).unwrap(),
+        "svelte button\n"
+    );
+    assert_eq!(
+        std::fs::read_to_string(installed.join("Card/index.tsx")).unwrap(),
+        "default card\n"
+    );
+
+    // Updates keep to the variant that was installed
+    write_file(&root.join("svelte/Button.svelte"), "svelte button v2\n");
+    write_file(&root.join("ui/Button/index.tsx"), "default button v2\n");
+    exec.update("x:comp:install", None, ws.path()).unwrap();
+    assert_eq!(
+        std::fs::read_to_string(installed.join("Button.svelte")).unwrap(),
+        "svelte button v2\n"
+    );
+    assert!(!installed.join("Button").exists());
+
+    // An explicit framework wins over detection
+    let ws = temp_dir();
+    write_file(&ws.path().join("package.json"), sveltekit);
+    let report = exec
+        .install(
+            "x:comp:install",
+            &picks("single", Some(&["Button"])).with(FRAMEWORK_KEY, "react"),
+            ws.path(),
+        )
+        .unwrap();
+    assert_eq!(report.framework.as_deref(), Some("react"));
+    assert_eq!(
+        std::fs::read_to_string(ws.path().join("entity-auth/components/Button/index.tsx")).unwrap(),
+        "react button\n"
+    );
+
+    let err = exec
+        .install(
+            "x:comp:install",
+            &picks("all", None).with(FRAMEWORK_KEY, "angular"),
+            ws.path(),
+        )
+        .unwrap_err();
+    assert!(
+        matches!(&err, CoreError::InvalidSelection(m) if m == "framework must be one of react|svelte"),
+        "{err:?}"
+    );
+}
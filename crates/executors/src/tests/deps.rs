@@ -0,0 +1,110 @@
+use super::common::*;
+
+use crate::{DependencyExecutor, StepResult};
+use entity_core::error::CoreError;
+use entity_core::registry::Registry;
+use entity_core::selections::Selections;
+
+#[test]
+fn run_applies_dependency_closure_in_topological_order() {
+    let packs = temp_dir();
+    let doc = packs.path().join("docs/intro.md");
+    write_file(&doc, "# Intro");
+    let ui = packs.path().join("components/ui");
+    write_file(&ui.join("SignIn/index.tsx"), "export {};\n");
+    write_file(&ui.join("SignUp.tsx"), "export {};\n");
+    let template = packs.path().join("setup/templates/basic");
+    write_file(&template.join("client.ts"), "export {};\n");
+
+    let mut components = component_node("x:comp:install", &ui, &["SignIn", "SignUp"]);
+    components.requires = vec!["x:docs:intro".into()];
+    let mut setup = setup_node("x:setup:basic", &template);
+    setup.requires = vec!["x:comp:install".into(), "x:docs:intro".into()];
+    let reg = Registry::new(vec![setup, components, doc_node("x:docs:intro", &doc)]).unwrap();
+    reg.validate_edges().unwrap();
+    let ws = temp_dir();
+
+    let report = DependencyExecutor::new(&reg)
+        .run("x:setup:basic", &Selections::new(), ws.path())
+        .unwrap();
+    assert_eq!(
+        report.order,
+        ["x:docs:intro", "x:comp:install", "x:setup:basic"]
+    );
+    assert!(
+        matches!(report.steps[0].result, StepResult::Doc { ref content } if content == "# Intro")
+    );
+    // Component dependencies install everything without a mode selection
+    assert!(
+        ws.path()
+            .join("entity-auth/components/SignIn/index.tsx")
+            .exists()
+    );
+    assert!(ws.path().join("entity-auth/components/SignUp.tsx").exists());
+    assert!(ws.path().join("entity-auth/client.ts").exists());
+}
+
+#[test]
+fn run_reports_the_steps_applied_before_a_failure() {
+    let packs = temp_dir();
+    let ui = packs.path().join("components/ui");
+    write_file(&ui.join("SignUp.tsx"), "export {};\n");
+    let template = packs.path().join("setup/templates/basic");
+    write_file(&template.join("client.ts.tmpl"), "{{ app.title }}\n");
+
+    let components = component_node("x:comp:install", &ui, &["SignUp"]);
+    let mut setup = setup_node("x:setup:basic", &template);
+    setup.requires = vec!["x:comp:install".into()];
+    let reg = Registry::new(vec![setup, components]).unwrap();
+    let ws = temp_dir();
+
+    let err = DependencyExecutor::new(&reg)
+        .run("x:setup:basic", &Selections::new(), ws.path())
+        .unwrap_err();
+    let CoreError::PartiallyApplied {
+        ref failed,
+        ref applied,
+        ..
+    } = err
+    else {
+        panic!("unexpected error: {err:?}");
+    };
+    assert_eq!(failed, "x:setup:basic");
+    assert_eq!(applied, &["x:comp:install"]);
+    assert_eq!(err.code(), "UNDEFINED_VARIABLE");
+    let details = err.details().unwrap();
+    assert_eq!(details["failedNode"], "x:setup:basic");
+    assert_eq!(details["applied"]["steps"][0]["node"], "x:comp:install");
+    assert_eq!(details["variables"][0], "app.title");
+    assert!(ws.path().join("entity-auth/components/SignUp.tsx").exists());
+}
+
+#[test]
+fn requires_cycles_and_unknown_targets_are_rejected() {
+    let packs = temp_dir();
+    let doc = packs.path().join("a.md");
+    write_file(&doc, "a");
+    let mut a = doc_node("x:docs:a", &doc);
+    a.requires = vec!["x:docs:b".into()];
+    let mut b = doc_node("x:docs:b", &doc);
+    b.requires = vec!["x:docs:a".into()];
+    let reg = Registry::new(vec![a.clone(), b]).unwrap();
+
+    let err = reg.validate_edges().unwrap_err();
+    assert!(
+        matches!(err, CoreError::DependencyCycle(ref cycle) if cycle == &["x:docs:a", "x:docs:b", "x:docs:a"])
+    );
+    let err = DependencyExecutor::new(&reg)
+        .run("x:docs:a", &Selections::new(), packs.path())
+        .unwrap_err();
+    assert!(matches!(err, CoreError::DependencyCycle(_)));
+
+    a.related_to = vec!["x:docs:missing".into()];
+    a.requires.clear();
+    let reg = Registry::new(vec![a]).unwrap();
+    let err = reg.validate_edges().unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("relatedTo unknown node x:docs:missing")
+    );
+}
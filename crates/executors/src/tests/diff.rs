@@ -0,0 +1,124 @@
+use super::common::*;
+
+use crate::{ComponentsExecutor, DiffStatus, NAMES_KEY};
+use entity_core::error::CoreError;
+use entity_core::pack::{Pack, PackManifest};
+use entity_core::registry::Registry;
+use entity_core::selections::Selections;
+
+#[test]
+fn diff_lists_hunks_and_added_and_removed_files() {
+    let dir = temp_dir();
+    let root = dir.path().join("microsoft/components");
+    write_file(&root.join("SignIn/index.tsx"), "one\ntwo\nthree\n");
+    write_file(&root.join("SignIn/Old.tsx"), "old\n");
+    write_file(
+        &root.join("SignIn/pack.ts.tmpl"),
+        "export const pack = \"{{ pack.name }}\";\n",
+    );
+    write_file(&root.join("Widget.tsx"), "widget\n");
+    let mut reg = Registry::default();
+    reg.add_pack(Pack::new(
+        "microsoft",
+        &dir.path().join("microsoft"),
+        PackManifest::default(),
+    ));
+    reg.insert_in_pack(
+        component_node("ms:comp:install", &root, &["SignIn", "Widget"]),
+        "microsoft",
+    )
+    .unwrap();
+    let exec = ComponentsExecutor::new(&reg);
+    let ws = temp_dir();
+    exec.install("ms:comp:install", &picks("all", None), ws.path())
+        .unwrap();
+
+    write_file(&root.join("SignIn/index.tsx"), "one\n2\nthree\n");
+    write_file(&root.join("SignIn/New.tsx"), "new");
+    std::fs::remove_file(root.join("SignIn/Old.tsx")).unwrap();
+
+    let report = exec
+        .diff(
+            "ms:comp:install",
+            &Selections::new().with(NAMES_KEY, vec!["SignIn"]),
+            ws.path(),
+        )
+        .unwrap();
+    assert_eq!(report.components, ["SignIn"]);
+    // The rendered template matches what was installed
+    assert_eq!(report.unchanged, 1);
+    let files: Vec<(&str, DiffStatus)> = report
+        .files
+        .iter()
+        .map(|f| (f.path.as_str(), f.status))
+        .collect();
+    assert_eq!(
+        files,
+        [
+            ("microsoft/components/SignIn/New.tsx", DiffStatus::Added),
+            ("microsoft/components/SignIn/Old.tsx", DiffStatus::Removed),
+            (
+                "microsoft/components/SignIn/index.tsx",
+                DiffStatus::Modified
+            ),
+        ]
+    );
+    let hunk = &report.files[2].hunks[0];
+    assert_eq!((hunk.old_start, hunk.old_lines), (1, 3));
+    assert_eq!(hunk.lines, [" one", "-two", "+2", " three"]);
+    assert_eq!(
+        report.patch(),
+        "--- /dev/null\n\
+         +++ b/microsoft/components/SignIn/New.tsx\n\
+         @@ -0,0 +1 @@\n\
+         +new\n\
+         \\ No newline at end of file\n\
+         --- a/microsoft/components/SignIn/Old.tsx\n\
+         +++ /dev/null\n\
+         @@ -1 +0,0 @@\n\
+         -old\n\
+         --- a/microsoft/components/SignIn/index.tsx\n\
+         +++ b/microsoft/components/SignIn/index.tsx\n\
+         @@ -1,3 +1,3 @@\n \
+         one\n\
+         -two\n\
+         +2\n \
+         three\n"
+    );
+
+    // Names go through the same checks as an install
+    let err = exec
+        .diff(
+            "ms:comp:install",
+            &Selections::new().with(NAMES_KEY, vec!["Nope"]),
+            ws.path(),
+        )
+        .unwrap_err();
+    assert!(
+        matches!(err, CoreError::InvalidNames(ref n) if n == &["Nope"]),
+        "{err:?}"
+    );
+
+    // Selections win over the variables recorded at install
+    let report = exec
+        .diff(
+            "ms:comp:install",
+            &Selections::new()
+                .with(NAMES_KEY, vec!["SignIn"])
+                .with("pack", serde_json::json!({ "name": "renamed" })),
+            ws.path(),
+        )
+        .unwrap();
+    let pack = report
+        .files
+        .iter()
+        .find(|f| f.path == "microsoft/components/SignIn/pack.ts")
+        .unwrap();
+    assert_eq!(
+        pack.hunks[0].lines,
+        [
+            "-export const pack = \"microsoft\";",
+            "+export const pack = \"renamed\";"
+        ]
+    );
+}
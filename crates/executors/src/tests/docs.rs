@@ -2,6 +2,7 @@ use super::common::*;
 
 use crate::DocsExecutor;
 use entity_core::registry::Registry;
+use entity_core::selections::Selections;
 
 #[test]
 fn read_happy_path() {
@@ -13,6 +14,6 @@ fn read_happy_path() {
     let reg = Registry::new(vec![node]).unwrap();
     let exec = DocsExecutor::new(&reg);
 
-    let out = exec.read("x:doc:one").unwrap();
+    let out = exec.read("x:doc:one", &Selections::new()).unwrap();
     assert_eq!(out, "hello");
 }
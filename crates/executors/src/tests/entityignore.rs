@@ -0,0 +1,108 @@
+use super::common::*;
+
+use crate::{ComponentsExecutor, SetupExecutor, SkipRule, UpdateAction};
+use entity_core::error::CoreError;
+use entity_core::model::NodePayload;
+use entity_core::pack::{Pack, PackManifest};
+use entity_core::registry::Registry;
+use entity_core::selections::Selections;
+
+fn rule(source: &str, pattern: &str, count: usize) -> SkipRule {
+    SkipRule {
+        source: source.into(),
+        pattern: pattern.into(),
+        count,
+    }
+}
+
+#[test]
+fn install_skips_ignored_files_and_summarizes_them() {
+    let dir = temp_dir();
+    let pack = dir.path().join("microsoft");
+    let root = pack.join("components");
+    write_file(&pack.join(".entityignore"), "# Storybook\n*.stories.tsx\n");
+    write_file(
+        &root.join("SignIn/.entityignore"),
+        "fixtures/\n!keep.stories.tsx\n",
+    );
+    write_file(&root.join("SignIn/index.tsx"), "export {};\n");
+    write_file(&root.join("SignIn/Form.stories.tsx"), "story\n");
+    write_file(&root.join("SignIn/keep.stories.tsx"), "story\n");
+    write_file(&root.join("SignIn/index.test.tsx"), "test\n");
+    write_file(&root.join("SignIn/fixtures/a.json"), "{}\n");
+    write_file(&root.join("SignIn/fixtures/b.json"), "{}\n");
+    write_file(&root.join("SignIn/.DS_Store"), "\0");
+    let mut node = component_node("ms:comp:install", &root, &["SignIn"]);
+    node.payload = NodePayload::Component {
+        source_root: root.display().to_string(),
+        components: Default::default(),
+        variants: Default::default(),
+        exclude: vec!["**/*.test.tsx".into()],
+    };
+    let mut reg = Registry::default();
+    reg.add_pack(Pack::new("microsoft", &pack, PackManifest::default()));
+    reg.insert_in_pack(node, "microsoft").unwrap();
+    let exec = ComponentsExecutor::new(&reg);
+    let ws = temp_dir();
+
+    let report = exec
+        .install("ms:comp:install", &picks("all", None), ws.path())
+        .unwrap();
+    let installed = ws.path().join("microsoft/components/SignIn");
+    assert!(installed.join("index.tsx").is_file());
+    // A `!` line of the nearer ignore file re-includes what the pack ignores
+    assert!(installed.join("keep.stories.tsx").is_file());
+    for skipped in [
+        "Form.stories.tsx",
+        "index.test.tsx",
+        "fixtures",
+        ".DS_Store",
+        ".entityignore",
+    ] {
+        assert!(!installed.join(skipped).exists(), "{skipped} was copied");
+    }
+    assert_eq!(report.copied[0].count, 2);
+    assert_eq!(report.skipped.count, 5);
+    assert_eq!(
+        report.skipped.rules,
+        [
+            rule(".entityignore", "*.stories.tsx", 1),
+            rule("components/SignIn/.entityignore", "fixtures/", 1),
+            rule("default", ".DS_Store", 1),
+            rule("default", ".entityignore", 1),
+            rule("exclude", "**/*.test.tsx", 1),
+        ]
+    );
+
+    // Ignored files are not brought in by updates either
+    write_file(&root.join("SignIn/index.test.tsx"), "changed\n");
+    let report = exec.update("ms:comp:install", None, ws.path()).unwrap();
+    assert!(
+        report
+            .files
+            .iter()
+            .all(|f| f.action == UpdateAction::UpToDate)
+    );
+    assert!(!installed.join("index.test.tsx").exists());
+}
+
+#[test]
+fn setup_rejects_invalid_exclude_patterns() {
+    let packs = temp_dir();
+    let template = packs.path().join("setup/templates/basic");
+    write_file(&template.join("client.ts"), "export {};\n");
+    let mut node = setup_node("x:setup:basic", &template);
+    node.payload = NodePayload::Setup {
+        template_root: template.display().to_string(),
+        commands: None,
+        exclude: vec!["*.{ts,tsx".into()],
+    };
+    let reg = Registry::new(vec![node]).unwrap();
+    let ws = temp_dir();
+
+    let err = SetupExecutor::new(&reg)
+        .run("x:setup:basic", &Selections::new(), ws.path())
+        .unwrap_err();
+    assert!(matches!(err, CoreError::InvalidDescriptor(_)), "{err:?}");
+    assert!(!ws.path().join("client.ts").exists());
+}
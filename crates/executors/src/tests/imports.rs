@@ -0,0 +1,119 @@
+use super::common::*;
+
+use crate::{ComponentsExecutor, FileStatus, Lockfile};
+use entity_core::pack::{Pack, PackManifest};
+use entity_core::registry::Registry;
+
+const SIGN_IN: &str = r#"import { Button } from "../Button";
+import { Form } from '$components/Form';
+import { Label } from "./parts/Label";
+import { useState } from "react";
+export { Form };
+"#;
+
+fn sign_in_registry(dir: &std::path::Path) -> Registry {
+    let root = dir.join("microsoft/components");
+    write_file(&root.join("SignIn/index.tsx"), SIGN_IN);
+    write_file(&root.join("SignIn/parts/Label.tsx"), "export {};\n");
+    write_file(&root.join("SignIn/styles.css"), "@import \"../Button\";\n");
+    write_file(&root.join("Button/index.tsx"), "export {};\n");
+    write_file(&root.join("Form/index.tsx"), "export {};\n");
+    let mut reg = Registry::default();
+    reg.add_pack(Pack::new(
+        "microsoft",
+        &dir.join("microsoft"),
+        PackManifest::default(),
+    ));
+    reg.insert_in_pack(
+        component_node("ms:comp:install", &root, &["SignIn", "Button", "Form"]),
+        "microsoft",
+    )
+    .unwrap();
+    reg
+}
+
+#[test]
+fn install_rewrites_component_imports_to_the_tsconfig_alias() {
+    let dir = temp_dir();
+    let reg = sign_in_registry(dir.path());
+    let ws = temp_dir();
+    write_file(
+        &ws.path().join("tsconfig.json"),
+        r#"{
+  // Shared options live in config/
+  "extends": "./config/tsconfig.base.json",
+  "compilerOptions": {
+    "strict": true, // everywhere
+  },
+}
+"#,
+    );
+    write_file(
+        &ws.path().join("config/tsconfig.base.json"),
+        r#"{
+  "compilerOptions": {
+    "baseUrl": "..", /* paths resolve from the workspace */
+    "paths": { "@/*": ["./*"], "~lib/*": ["./lib/*"] }, /* more to come */
+  }
+}
+"#,
+    );
+
+    let report = ComponentsExecutor::new(&reg)
+        .install("ms:comp:install", &picks("all", None), ws.path())
+        .unwrap();
+    let installed = ws.path().join("microsoft/components");
+    assert_eq!(
+        report.imports_rewritten,
+        [installed.join("SignIn/index.tsx").display().to_string()]
+    );
+    assert_eq!(
+        std::fs::read_to_string(installed.join("SignIn/index.tsx")).unwrap(),
+        r#"import { Button } from "@/microsoft/components/Button";
+import { Form } from '@/microsoft/components/Form';
+import { Label } from "./parts/Label";
+import { useState } from "react";
+export { Form };
+"#
+    );
+    // Only script files are rewritten
+    assert_eq!(
+        std::fs::read_to_string(installed.join("SignIn/styles.css")).unwrap(),
+        "@import \"../Button\";\n"
+    );
+
+    let lock = Lockfile::load(ws.path()).unwrap();
+    let entry = &lock.files["microsoft/components/SignIn/index.tsx"];
+    assert_ne!(entry.source_sha256.as_ref(), Some(&entry.sha256));
+    let status = ComponentsExecutor::new(&reg).status(ws.path()).unwrap();
+    assert!(
+        status
+            .components
+            .iter()
+            .flat_map(|c| &c.files)
+            .all(|f| f.status == FileStatus::Unchanged)
+    );
+}
+
+#[test]
+fn install_without_alias_resolves_placeholders_relatively() {
+    let dir = temp_dir();
+    let reg = sign_in_registry(dir.path());
+    let ws = temp_dir();
+
+    let report = ComponentsExecutor::new(&reg)
+        .install("ms:comp:install", &picks("all", None), ws.path())
+        .unwrap();
+    let index = ws.path().join("microsoft/components/SignIn/index.tsx");
+    assert_eq!(
+        std::fs::read_to_string(&index).unwrap(),
+        SIGN_IN.replace("$components/Form", "../Form")
+    );
+    assert_eq!(report.imports_rewritten.len(), 1);
+    assert!(
+        report
+            .notes
+            .iter()
+            .any(|n| n.starts_with("No tsconfig.json path alias covers"))
+    );
+}
@@ -0,0 +1,95 @@
+use super::common::*;
+
+use crate::{
+    ComponentsExecutor, JOURNAL_FILE, JournalAction, LOCK_FILE, UndoAction, load_journal, undo,
+};
+use entity_core::error::CoreError;
+use entity_core::model::PackageJsonSpec;
+use entity_core::registry::Registry;
+
+const PACKAGE: &str = "{\n  \"name\": \"app\"\n}\n";
+
+#[test]
+fn undo_reverts_the_last_install() {
+    let dir = temp_dir();
+    let root = dir.path().join("components");
+    write_file(&root.join("SignIn/index.tsx"), "export const SignIn = 1;\n");
+    write_file(&root.join("SignIn/parts/Label.tsx"), "export {};\n");
+    let mut node = component_node("c:install", &root, &["SignIn"]);
+    node.package_json = Some(PackageJsonSpec {
+        dependencies: [("zod".to_string(), "^3.23.0".to_string())].into(),
+        ..Default::default()
+    });
+    let reg = Registry::new(vec![node]).unwrap();
+    let ws = temp_dir();
+    let installed = ws.path().join("entity-auth/components/SignIn");
+    write_file(&ws.path().join("package.json"), PACKAGE);
+    write_file(&installed.join("index.tsx"), "local edit\n");
+
+    let report = ComponentsExecutor::new(&reg)
+        .install("c:install", &picks("all", None), ws.path())
+        .unwrap();
+    assert_eq!(report.transaction.unwrap().journal, Some(1));
+    let journal = load_journal(ws.path()).unwrap();
+    assert_eq!(journal.len(), 1);
+    assert_eq!(journal[0].command, "ui install");
+    assert_eq!(journal[0].node.as_deref(), Some("c:install"));
+    let action = |path: &str| {
+        journal[0]
+            .files
+            .iter()
+            .find(|f| f.path == path)
+            .map(|f| f.action)
+    };
+    assert_eq!(
+        action("entity-auth/components/SignIn/index.tsx"),
+        Some(JournalAction::Overwritten)
+    );
+    assert_eq!(
+        action("entity-auth/components/SignIn/parts/Label.tsx"),
+        Some(JournalAction::Created)
+    );
+    assert_eq!(action("package.json"), Some(JournalAction::Overwritten));
+    assert_eq!(action(LOCK_FILE), Some(JournalAction::Created));
+
+    // Files edited since the install are never clobbered
+    write_file(&installed.join("parts/Label.tsx"), "edited\n");
+    let err = undo(ws.path(), 1).unwrap_err();
+    assert!(
+        matches!(&err, CoreError::WriteConflict(paths)
+            if paths == &["entity-auth/components/SignIn/parts/Label.tsx"]),
+        "{err:?}"
+    );
+    assert_eq!(
+        std::fs::read_to_string(installed.join("index.tsx")).unwrap(),
+        "export const SignIn = 1;\n"
+    );
+
+    write_file(&installed.join("parts/Label.tsx"), "export {};\n");
+    let report = undo(ws.path(), 1).unwrap();
+    assert_eq!(report.reverted.len(), 1);
+    assert_eq!(report.reverted[0].id, 1);
+    assert!(
+        report
+            .files
+            .iter()
+            .any(|f| f.path == "package.json" && f.action == UndoAction::Restored)
+    );
+    assert_eq!(
+        std::fs::read_to_string(installed.join("index.tsx")).unwrap(),
+        "local edit\n"
+    );
+    assert!(!installed.join("parts").exists());
+    assert_eq!(
+        std::fs::read_to_string(ws.path().join("package.json")).unwrap(),
+        PACKAGE
+    );
+    assert!(!ws.path().join(LOCK_FILE).exists());
+    assert!(ws.path().join(JOURNAL_FILE).is_file());
+
+    // The undo is journaled too, but never undone itself
+    assert_eq!(load_journal(ws.path()).unwrap()[1].reverts, [1]);
+    let report = undo(ws.path(), 1).unwrap();
+    assert!(report.reverted.is_empty());
+    assert_eq!(report.notes, ["Nothing to undo"]);
+}
@@ -1,5 +1,13 @@
 mod bridge;
 mod common;
 mod components;
+mod deps;
+mod diff;
 mod docs;
+mod entityignore;
+mod imports;
+mod journal;
 mod performance;
+mod setup;
+mod template;
+mod transaction;
@@ -4,6 +4,7 @@ use super::common::*;
 
 use crate::{ComponentsExecutor, DocsExecutor};
 use entity_core::registry::Registry;
+use entity_core::selections::Selections;
 
 #[test]
 fn smoke_targets() {
@@ -28,15 +29,14 @@ fn smoke_targets() {
     let ws = temp_dir();
 
     let t0 = Instant::now();
-    let _ = docs.read("x:doc:perf").unwrap();
+    let _ = docs.read("x:doc:perf", &Selections::new()).unwrap();
     assert!(t0.elapsed().as_millis() <= 10, "docs read too slow");
 
     let t1 = Instant::now();
     let rep = comps
         .install(
             "x:comp:perf",
-            "single",
-            Some(vec!["SignIn".into()]),
+            &picks("single", Some(&["SignIn"])),
             ws.path(),
         )
         .unwrap();
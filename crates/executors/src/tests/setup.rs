@@ -0,0 +1,289 @@
+use super::common::*;
+
+use crate::{PackageChangeAction, SetupExecutor, WriteOptions};
+use entity_core::error::CoreError;
+use entity_core::model::{NodePayload, PackageJsonSpec, Prerequisite};
+use entity_core::registry::Registry;
+use entity_core::selections::Selections;
+
+#[test]
+fn run_requires_and_validates_selections() {
+    let packs = temp_dir();
+    let template = packs.path().join("setup/templates/basic");
+    write_file(&template.join("client.ts"), "export {};\n");
+    let mut node = setup_node("x:setup:basic", &template);
+    node.prerequisites = vec![Prerequisite {
+        key: "setup.region".into(),
+        schema: serde_json::json!({ "enum": ["us", "eu"] }),
+        optional: false,
+    }];
+    let reg = Registry::new(vec![node]).unwrap();
+    let exec = SetupExecutor::new(&reg);
+    let ws = temp_dir();
+
+    let err = exec
+        .run("x:setup:basic", &Selections::new(), ws.path())
+        .unwrap_err();
+    assert!(matches!(err, CoreError::MissingSelections(ref keys) if keys == &["setup.region"]));
+
+    let err = exec
+        .run(
+            "x:setup:basic",
+            &Selections::new().with("setup.region", "ap"),
+            ws.path(),
+        )
+        .unwrap_err();
+    assert_eq!(err.code(), "INVALID_SELECTION");
+
+    let report = exec
+        .run(
+            "x:setup:basic",
+            &Selections::new().with("setup.region", "eu"),
+            ws.path(),
+        )
+        .unwrap();
+    assert_eq!(report.copied[0].count, 1);
+    assert!(ws.path().join("entity-auth/client.ts").exists());
+}
+
+#[test]
+fn run_dry_run_lists_commands_without_running_them() {
+    let packs = temp_dir();
+    let template = packs.path().join("setup/templates/basic");
+    write_file(&template.join("client.ts"), "export {};\n");
+    let mut node = setup_node("x:setup:basic", &template);
+    node.payload = NodePayload::Setup {
+        template_root: template.display().to_string(),
+        commands: Some(vec!["touch ran.txt".into()]),
+        exclude: Vec::new(),
+    };
+    let reg = Registry::new(vec![node]).unwrap();
+    let ws = temp_dir();
+
+    let report = SetupExecutor::new(&reg)
+        .with_options(WriteOptions {
+            dry_run: true,
+            ..Default::default()
+        })
+        .run("x:setup:basic", &Selections::new(), ws.path())
+        .unwrap();
+    let plan = report.plan.unwrap();
+    assert_eq!(plan.commands, ["touch ran.txt"]);
+    assert_eq!(plan.files.len(), 1);
+    assert!(plan.files[0].to.ends_with("entity-auth/client.ts"));
+    assert!(report.scaffolded.is_empty());
+    assert!(!ws.path().join("ran.txt").exists());
+    assert!(!ws.path().join("entity-auth").exists());
+}
+
+#[test]
+fn run_checks_the_writes_before_running_commands() {
+    let packs = temp_dir();
+    let template = packs.path().join("setup/templates/basic");
+    write_file(
+        &template.join("client.ts.tmpl"),
+        "export const x = {{missing}};\n",
+    );
+    let mut node = setup_node("x:setup:basic", &template);
+    node.payload = NodePayload::Setup {
+        template_root: template.display().to_string(),
+        commands: Some(vec!["touch ran.txt".into()]),
+        exclude: Vec::new(),
+    };
+    let reg = Registry::new(vec![node]).unwrap();
+    let ws = temp_dir();
+
+    let err = SetupExecutor::new(&reg)
+        .run("x:setup:basic", &Selections::new(), ws.path())
+        .unwrap_err();
+    assert_eq!(err.code(), "UNDEFINED_VARIABLE");
+    assert!(!ws.path().join("ran.txt").exists());
+    assert!(!ws.path().join("entity-auth").exists());
+}
+
+#[test]
+fn run_merges_declared_entries_into_package_json() {
+    let packs = temp_dir();
+    let template = packs.path().join("setup/templates/basic");
+    write_file(&template.join("client.ts"), "export {};\n");
+    let mut node = setup_node("x:setup:basic", &template);
+    let map = |entries: &[(&str, &str)]| {
+        entries
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    };
+    node.package_json = Some(PackageJsonSpec {
+        dependencies: map(&[
+            ("@entityauth/react", "^1.2.0"),
+            ("next", "^14.0.0"),
+            ("react", "^18.0.0"),
+            ("zod", "^3.0.0"),
+        ]),
+        dev_dependencies: map(&[("typescript", "^5.4.0")]),
+        scripts: map(&[("dev", "next dev"), ("auth:sync", "entity-auth sync")]),
+    });
+    let reg = Registry::new(vec![node]).unwrap();
+    let ws = temp_dir();
+    let original = r#"{
+    "name": "app",
+    "scripts": {
+        "dev": "vite"
+    },
+    "dependencies": {
+        "next": "^13.5.0",
+        "react": "^18.3.1",
+        "zod": "^4.0.0"
+    },
+    "browserslist": ["defaults"]
+}
+"#;
+    write_file(&ws.path().join("package.json"), original);
+
+    // Dry runs report without writing
+    let report = SetupExecutor::new(&reg)
+        .with_options(WriteOptions {
+            dry_run: true,
+            ..Default::default()
+        })
+        .run("x:setup:basic", &Selections::new(), ws.path())
+        .unwrap();
+    assert_eq!(report.package_json.unwrap().changes.len(), 4);
+    assert_eq!(
+        std::fs::read_to_string(ws.path().join("package.json")).unwrap(),
+        original
+    );
+
+    let report = SetupExecutor::new(&reg)
+        .run("x:setup:basic", &Selections::new(), ws.path())
+        .unwrap();
+    let package = report.package_json.unwrap();
+    let changes: Vec<(&str, &str, PackageChangeAction)> = package
+        .changes
+        .iter()
+        .map(|c| (c.section.as_str(), c.name.as_str(), c.action))
+        .collect();
+    assert_eq!(
+        changes,
+        [
+            (
+                "dependencies",
+                "@entityauth/react",
+                PackageChangeAction::Added
+            ),
+            ("dependencies", "next", PackageChangeAction::Updated),
+            ("devDependencies", "typescript", PackageChangeAction::Added),
+            ("scripts", "auth:sync", PackageChangeAction::Added),
+        ]
+    );
+    assert_eq!(
+        std::fs::read_to_string(ws.path().join("package.json")).unwrap(),
+        r#"{
+    "name": "app",
+    "scripts": {
+        "dev": "vite",
+        "auth:sync": "entity-auth sync"
+    },
+    "dependencies": {
+        "@entityauth/react": "^1.2.0",
+        "next": "^14.0.0",
+        "react": "^18.3.1",
+        "zod": "^4.0.0"
+    },
+    "browserslist": ["defaults"],
+    "devDependencies": {
+        "typescript": "^5.4.0"
+    }
+}
+"#
+    );
+    // Never downgraded, never replaced
+    assert!(package.notes.iter().any(|n| n.contains("zod@^4.0.0")));
+    assert!(package.notes.iter().any(|n| n.contains("kept script dev")));
+}
+
+#[test]
+fn run_compares_npm_range_forms() {
+    let packs = temp_dir();
+    let template = packs.path().join("setup/templates/basic");
+    write_file(&template.join("client.ts"), "export {};\n");
+    let mut node = setup_node("x:setup:basic", &template);
+    let wanted = [
+        ("spaced", "^1.5.0"),
+        ("xrange", "^1.2.0"),
+        ("either", "^2.1.0"),
+        ("hyphen", "^1.2.0"),
+        ("greater", "^2.0.0"),
+        ("pinned", "^1.2.0"),
+        ("star", "^3.0.0"),
+    ];
+    node.package_json = Some(PackageJsonSpec {
+        dependencies: wanted
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect(),
+        ..Default::default()
+    });
+    let reg = Registry::new(vec![node]).unwrap();
+    let ws = temp_dir();
+    write_file(
+        &ws.path().join("package.json"),
+        r#"{
+  "dependencies": {
+    "either": "^1 || ^2",
+    "greater": ">1",
+    "hyphen": "1.2.3 - 2.0.0",
+    "pinned": "1.2.3",
+    "spaced": ">=1.0.0 <2.0.0",
+    "star": "*",
+    "xrange": "1.x"
+  }
+}
+"#,
+    );
+
+    let report = SetupExecutor::new(&reg)
+        .run("x:setup:basic", &Selections::new(), ws.path())
+        .unwrap();
+    let mut raised: Vec<&str> = report
+        .package_json
+        .as_ref()
+        .unwrap()
+        .changes
+        .iter()
+        .filter(|c| c.action == PackageChangeAction::Updated)
+        .map(|c| c.name.as_str())
+        .collect();
+    raised.sort();
+    // Ranges whose lowest version is below the declared one are raised; `>1`
+    // starts at 2.0.0, `*` allows any version and the others already start
+    // inside the declared range
+    assert_eq!(raised, ["either", "spaced", "xrange"]);
+    assert!(report.package_json.unwrap().notes.is_empty());
+}
+
+#[test]
+fn run_keeps_a_minified_package_json_on_one_line() {
+    let packs = temp_dir();
+    let template = packs.path().join("setup/templates/basic");
+    write_file(&template.join("client.ts"), "export {};\n");
+    let mut node = setup_node("x:setup:basic", &template);
+    node.package_json = Some(PackageJsonSpec {
+        dependencies: [("zod".to_string(), "^3.0.0".to_string())].into(),
+        ..Default::default()
+    });
+    let reg = Registry::new(vec![node]).unwrap();
+    let ws = temp_dir();
+    write_file(
+        &ws.path().join("package.json"),
+        r#"{"name":"app","dependencies":{"react":"^18.0.0"}}"#,
+    );
+
+    SetupExecutor::new(&reg)
+        .run("x:setup:basic", &Selections::new(), ws.path())
+        .unwrap();
+    assert_eq!(
+        std::fs::read_to_string(ws.path().join("package.json")).unwrap(),
+        r#"{"name":"app","dependencies":{"react":"^18.0.0","zod":"^3.0.0"}}"#
+    );
+}
@@ -0,0 +1,186 @@
+use super::common::*;
+
+use crate::{ComponentsExecutor, FileStatus, LOCK_FILE, Lockfile, UpdateAction, WriteOptions};
+use entity_core::error::CoreError;
+use entity_core::pack::{Pack, PackManifest};
+use entity_core::registry::Registry;
+
+fn templated_registry(dir: &std::path::Path) -> Registry {
+    let mut reg = Registry::default();
+    reg.add_pack(Pack::new(
+        "microsoft",
+        &dir.join("microsoft"),
+        PackManifest {
+            version: Some("1.2.0".into()),
+            variables: [("brand.color".to_string(), "blue".to_string())].into(),
+            ..Default::default()
+        },
+    ));
+    reg.insert_in_pack(
+        component_node(
+            "ms:comp:install",
+            &dir.join("microsoft/components"),
+            &["SignIn"],
+        ),
+        "microsoft",
+    )
+    .unwrap();
+    reg
+}
+
+#[test]
+fn install_renders_templates_and_path_variables() {
+    let dir = temp_dir();
+    let root = dir.path().join("microsoft/components/SignIn");
+    write_file(
+        &root.join("index.tsx.tmpl"),
+        "export const pack = \"{{ pack.name }}@{{ pack.version }}\";\nexport const color = \"{{brand.color}}\";\n",
+    );
+    write_file(&root.join("__brand.color__.css"), "a {}\n");
+    write_file(
+        &root.join("__tests__/plain.tsx"),
+        "<div style={{ color }} />\n",
+    );
+    let reg = templated_registry(dir.path());
+    let exec = ComponentsExecutor::new(&reg);
+    let ws = temp_dir();
+
+    exec.install(
+        "ms:comp:install",
+        &picks("all", None).with("brand.color", "red"),
+        ws.path(),
+    )
+    .unwrap();
+    let installed = ws.path().join("microsoft/components/SignIn");
+    assert_eq!(
+        std::fs::read_to_string(installed.join("index.tsx")).unwrap(),
+        "export const pack = \"microsoft@1.2.0\";\nexport const color = \"red\";\n"
+    );
+    assert!(!installed.join("index.tsx.tmpl").exists());
+    assert!(installed.join("red.css").is_file());
+    // Undotted names and files without the suffix are copied as they are
+    assert_eq!(
+        std::fs::read_to_string(installed.join("__tests__/plain.tsx")).unwrap(),
+        "<div style={{ color }} />\n"
+    );
+
+    let lock = Lockfile::load(ws.path()).unwrap();
+    let entry = &lock.files["microsoft/components/SignIn/index.tsx"];
+    assert_eq!(entry.source, "components/SignIn/index.tsx.tmpl");
+    assert_eq!(
+        entry.source_sha256,
+        Some(crate::digest(&root.join("index.tsx.tmpl")).unwrap().sha256)
+    );
+    assert_eq!(entry.vars["brand.color"], "red");
+    assert_eq!(entry.vars["pack.version"], "1.2.0");
+    let report = exec.status(ws.path()).unwrap();
+    assert!(
+        report.components[0]
+            .files
+            .iter()
+            .all(|f| f.status == FileStatus::Unchanged)
+    );
+
+    // Updates render the new template with the variables recorded at install
+    write_file(
+        &root.join("index.tsx.tmpl"),
+        "export const color = \"{{ brand.color }}\";\n",
+    );
+    let report = exec.update("ms:comp:install", None, ws.path()).unwrap();
+    let index = report
+        .files
+        .iter()
+        .find(|f| f.path == "microsoft/components/SignIn/index.tsx")
+        .unwrap();
+    assert_eq!(index.action, UpdateAction::Updated);
+    assert_eq!(
+        std::fs::read_to_string(installed.join("index.tsx")).unwrap(),
+        "export const color = \"red\";\n"
+    );
+}
+
+#[test]
+fn install_fails_on_undefined_variables_before_writing() {
+    let dir = temp_dir();
+    let root = dir.path().join("microsoft/components/SignIn");
+    write_file(&root.join("index.tsx.tmpl"), "<h1>{{ app.title }}</h1>\n");
+    write_file(&root.join("__app.slug__.ts"), "export {};\n");
+    write_file(&root.join("other.tsx.tmpl"), "{{ app.title }}\n");
+    let reg = templated_registry(dir.path());
+    let ws = temp_dir();
+
+    for dry_run in [true, false] {
+        let err = ComponentsExecutor::new(&reg)
+            .with_options(WriteOptions {
+                dry_run,
+                ..Default::default()
+            })
+            .install("ms:comp:install", &picks("all", None), ws.path())
+            .unwrap_err();
+        let CoreError::UndefinedVariables { variables, files } = &err else {
+            panic!("unexpected error: {err:?}");
+        };
+        assert_eq!(variables, &["app.slug", "app.title"]);
+        assert_eq!(files.len(), 3);
+        assert_eq!(err.code(), "UNDEFINED_VARIABLE");
+        assert_eq!(err.exit_code(), 17);
+    }
+    assert!(!ws.path().join("microsoft").exists());
+    assert!(!ws.path().join(LOCK_FILE).exists());
+
+    let selections = picks("all", None)
+        .with("app.title", "Demo")
+        .with("app.slug", "demo");
+    ComponentsExecutor::new(&reg)
+        .install("ms:comp:install", &selections, ws.path())
+        .unwrap();
+    let installed = ws.path().join("microsoft/components/SignIn");
+    assert!(installed.join("demo.ts").is_file());
+    assert_eq!(
+        std::fs::read_to_string(installed.join("index.tsx")).unwrap(),
+        "<h1>Demo</h1>\n"
+    );
+}
+
+#[test]
+fn path_variables_stay_inside_the_install_root() {
+    let dir = temp_dir();
+    let root = dir.path().join("microsoft/components/SignIn");
+    write_file(&root.join("__brand.color__/index.ts"), "export {};\n");
+    write_file(&root.join("__appName__.ts"), "export {};\n");
+    let mut reg = templated_registry(dir.path());
+    reg.add_pack(Pack::new(
+        "microsoft",
+        &dir.path().join("microsoft"),
+        PackManifest {
+            variables: [
+                ("brand.color".to_string(), "blue".to_string()),
+                ("appName".to_string(), "demo".to_string()),
+            ]
+            .into(),
+            ..Default::default()
+        },
+    ));
+    let ws = temp_dir();
+
+    for color in ["../../escape", "a/b", ".."] {
+        let err = ComponentsExecutor::new(&reg)
+            .install(
+                "ms:comp:install",
+                &picks("all", None).with("brand.color", color),
+                ws.path(),
+            )
+            .unwrap_err();
+        assert!(matches!(err, CoreError::InvalidSelection(_)), "{err:?}");
+    }
+    assert!(!ws.path().join("microsoft").exists());
+    assert!(!dir.path().join("escape").exists());
+
+    // Undotted pack variables render in paths too
+    ComponentsExecutor::new(&reg)
+        .install("ms:comp:install", &picks("all", None), ws.path())
+        .unwrap();
+    let installed = ws.path().join("microsoft/components/SignIn");
+    assert!(installed.join("blue/index.ts").is_file());
+    assert!(installed.join("demo.ts").is_file());
+}
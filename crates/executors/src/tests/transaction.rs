@@ -0,0 +1,100 @@
+use super::common::*;
+
+use crate::{
+    BASE_DIR, ComponentsExecutor, JOURNAL_FILE, LOCK_FILE, Lockfile, TRANSACTION_DIR,
+    TransactionStatus,
+};
+use entity_core::error::CoreError;
+use entity_core::model::PackageJsonSpec;
+use entity_core::registry::Registry;
+
+const PACKAGE: &str = "{\n  \"name\": \"app\"\n}\n";
+
+fn two_component_registry(dir: &std::path::Path) -> Registry {
+    let root = dir.join("components");
+    write_file(&root.join("SignIn/index.tsx"), "export const SignIn = 1;\n");
+    write_file(&root.join("SignIn/parts/Label.tsx"), "export {};\n");
+    write_file(&root.join("Widget/index.tsx"), "export const Widget = 1;\n");
+    let mut node = component_node("c:install", &root, &["SignIn", "Widget"]);
+    node.package_json = Some(PackageJsonSpec {
+        dependencies: [("zod".to_string(), "^3.23.0".to_string())].into(),
+        ..Default::default()
+    });
+    Registry::new(vec![node]).unwrap()
+}
+
+#[test]
+fn failed_install_restores_the_workspace() {
+    let dir = temp_dir();
+    let reg = two_component_registry(dir.path());
+    let ws = temp_dir();
+    let installed = ws.path().join("entity-auth/components");
+    write_file(&ws.path().join("package.json"), PACKAGE);
+    write_file(&installed.join("SignIn/index.tsx"), "local edit\n");
+    // A directory where Widget's file goes makes the last move fail
+    write_file(&installed.join("Widget/index.tsx/keep"), "");
+
+    let err = ComponentsExecutor::new(&reg)
+        .install("c:install", &picks("all", None), ws.path())
+        .unwrap_err();
+    let CoreError::RolledBack {
+        restored,
+        unrestored,
+        ..
+    } = &err
+    else {
+        panic!("unexpected error: {err:?}");
+    };
+    assert_eq!(restored, &["entity-auth/components/SignIn/index.tsx"]);
+    assert!(unrestored.is_empty());
+    assert_eq!(err.code(), "ROLLED_BACK");
+    assert_eq!(err.exit_code(), 18);
+    assert_eq!(err.details().unwrap()["transaction"], "rolledBack");
+
+    assert_eq!(
+        std::fs::read_to_string(installed.join("SignIn/index.tsx")).unwrap(),
+        "local edit\n"
+    );
+    assert!(!installed.join("SignIn/parts").exists());
+    assert_eq!(
+        std::fs::read_to_string(ws.path().join("package.json")).unwrap(),
+        PACKAGE
+    );
+    assert!(!ws.path().join(LOCK_FILE).exists());
+    assert!(!ws.path().join(BASE_DIR).exists());
+    assert!(!ws.path().join(TRANSACTION_DIR).exists());
+
+    std::fs::remove_dir_all(installed.join("Widget")).unwrap();
+    let report = ComponentsExecutor::new(&reg)
+        .install("c:install", &picks("all", None), ws.path())
+        .unwrap();
+    let transaction = report.transaction.unwrap();
+    assert_eq!(transaction.status, TransactionStatus::Committed);
+    // Three component files, package.json and the lockfile
+    assert_eq!(transaction.operations, 5);
+    assert_eq!(Lockfile::load(ws.path()).unwrap().files.len(), 3);
+    assert!(!ws.path().join(TRANSACTION_DIR).exists());
+}
+
+#[test]
+fn journal_failure_keeps_the_committed_install() {
+    let dir = temp_dir();
+    let reg = two_component_registry(dir.path());
+    let ws = temp_dir();
+    // A directory where the journal goes makes appending to it fail
+    std::fs::create_dir_all(ws.path().join(JOURNAL_FILE)).unwrap();
+
+    let report = ComponentsExecutor::new(&reg)
+        .install("c:install", &picks("all", None), ws.path())
+        .unwrap();
+    let transaction = report.transaction.unwrap();
+    assert_eq!(transaction.status, TransactionStatus::Committed);
+    assert_eq!(transaction.journal, None);
+    assert_eq!(transaction.warnings.len(), 1);
+    assert!(
+        ws.path()
+            .join("entity-auth/components/SignIn/index.tsx")
+            .is_file()
+    );
+    assert!(ws.path().join(BASE_DIR).is_dir());
+}
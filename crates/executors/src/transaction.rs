@@ -0,0 +1,315 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use entity_core::error::{CoreError, CoreResult};
+use serde::Serialize;
+
+use crate::journal::{JournalAction, JournalFile, Operation, append, keep_backup};
+use crate::lock::relative_key;
+use crate::write::{digest, digest_bytes};
+
+/// Workspace-relative directory transactions stage files in, on the same
+/// filesystem as the files they replace so moving them into place is a rename.
+pub const TRANSACTION_DIR: &str = ".entitycli/tx";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TransactionStatus {
+    Committed,
+    RolledBack,
+}
+
+/// Outcome of the writes of one call; failed transactions surface as `ROLLED_BACK`.
+#[derive(Debug, Clone, Serialize)]
+pub struct TransactionReport {
+    pub status: TransactionStatus,
+    /// Files written, moved or removed
+    pub operations: usize,
+    /// Id of the journal entry recording the operation
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub journal: Option<u64>,
+    /// Problems after the changes were applied, which leave them in place
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+}
+
+enum Op {
+    Write {
+        to: PathBuf,
+        staged: PathBuf,
+        sha256: String,
+    },
+    Rename {
+        from: PathBuf,
+        to: PathBuf,
+    },
+    Remove {
+        path: PathBuf,
+    },
+}
+
+/// How to take back one applied step.
+enum Undo {
+    /// Move the original kept in the transaction directory back to `to`
+    Restore {
+        original: PathBuf,
+        to: PathBuf,
+    },
+    /// Delete a file the transaction created
+    Delete(PathBuf),
+    Rename {
+        from: PathBuf,
+        to: PathBuf,
+    },
+    /// Remove a directory the transaction created
+    RemoveDir(PathBuf),
+}
+
+/// Workspace changes staged in full before any is applied, then applied as
+/// renames; if one fails, the applied ones are undone and replaced files restored.
+pub(crate) struct Transaction {
+    workspace: PathBuf,
+    dir: PathBuf,
+    ops: Vec<Op>,
+    /// Journaled once committed
+    operation: Option<Operation>,
+    /// Replaced and removed files set aside in `dir`, with their sha256
+    originals: Vec<(PathBuf, String)>,
+    /// Keep the directory when a rollback could not restore every original
+    keep: bool,
+}
+
+impl Transaction {
+    pub(crate) fn new(workspace: &Path) -> Self {
+        let stamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos();
+        Self {
+            workspace: workspace.to_path_buf(),
+            dir: workspace
+                .join(TRANSACTION_DIR)
+                .join(format!("{stamp}-{}", std::process::id())),
+            ops: Vec::new(),
+            operation: None,
+            originals: Vec::new(),
+            keep: false,
+        }
+    }
+
+    /// Record the committed changes in the workspace journal as `operation`,
+    /// keeping what they replace so `undo` can put it back.
+    pub(crate) fn journaled(mut self, operation: Operation) -> Self {
+        self.operation = Some(operation);
+        self
+    }
+
+    /// Stage `content` for `to`. The content is written into the transaction
+    /// directory right away; `to` is only touched on commit.
+    pub(crate) fn write(&mut self, to: &Path, content: &[u8]) -> CoreResult<()> {
+        fs::create_dir_all(&self.dir)?;
+        let staged = self.dir.join(self.ops.len().to_string());
+        fs::write(&staged, content)?;
+        self.ops.push(Op::Write {
+            to: to.to_path_buf(),
+            staged,
+            sha256: digest_bytes(content).sha256,
+        });
+        Ok(())
+    }
+
+    pub(crate) fn rename(&mut self, from: &Path, to: &Path) {
+        self.ops.push(Op::Rename {
+            from: from.to_path_buf(),
+            to: to.to_path_buf(),
+        });
+    }
+
+    pub(crate) fn remove(&mut self, path: &Path) {
+        self.ops.push(Op::Remove {
+            path: path.to_path_buf(),
+        });
+    }
+
+    /// Apply every staged change in order, then journal them. On failure the
+    /// applied changes are undone in reverse and the call fails with `ROLLED_BACK`.
+    /// Once everything is applied the transaction stands: a journal that can't be
+    /// written only adds a warning, and the operation can't be undone.
+    pub(crate) fn commit(mut self) -> CoreResult<TransactionReport> {
+        let mut undo = Vec::new();
+        let mut files = Vec::new();
+        let ops = std::mem::take(&mut self.ops);
+        for (index, op) in ops.iter().enumerate() {
+            match self.apply(index, op, &mut undo) {
+                Ok(file) => files.extend(file),
+                Err(err) => return Err(self.roll_back(err, undo)),
+            }
+        }
+        let mut report = TransactionReport {
+            status: TransactionStatus::Committed,
+            operations: ops.len(),
+            journal: None,
+            warnings: Vec::new(),
+        };
+        if let Some(operation) = self.operation.take() {
+            match self.journal(operation, files) {
+                Ok(id) => report.journal = Some(id),
+                Err(err) => report
+                    .warnings
+                    .push(format!("Not journaled, so `undo` can't revert it: {err}")),
+            }
+        }
+        Ok(report)
+    }
+
+    fn journal(&self, operation: Operation, files: Vec<JournalFile>) -> CoreResult<u64> {
+        for (original, sha256) in &self.originals {
+            keep_backup(&self.workspace, sha256, original)?;
+        }
+        let stamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        Ok(append(&self.workspace, operation, files, stamp)?.id)
+    }
+
+    fn apply(
+        &mut self,
+        index: usize,
+        op: &Op,
+        undo: &mut Vec<Undo>,
+    ) -> CoreResult<Option<JournalFile>> {
+        let file = match op {
+            Op::Write { to, staged, sha256 } => {
+                create_parents(to, undo)?;
+                let before = if to.is_file() {
+                    Some(self.set_aside(index, to, undo)?)
+                } else {
+                    None
+                };
+                fs::rename(staged, to)?;
+                undo.push(Undo::Delete(to.clone()));
+                JournalFile {
+                    path: relative_key(to, &self.workspace),
+                    action: if before.is_some() {
+                        JournalAction::Overwritten
+                    } else {
+                        JournalAction::Created
+                    },
+                    before,
+                    after: Some(sha256.clone()),
+                    to: None,
+                }
+            }
+            Op::Rename { from, to } => {
+                let before = digest(from)?.sha256;
+                create_parents(to, undo)?;
+                fs::rename(from, to)?;
+                undo.push(Undo::Rename {
+                    from: to.clone(),
+                    to: from.clone(),
+                });
+                JournalFile {
+                    path: relative_key(from, &self.workspace),
+                    action: JournalAction::Moved,
+                    before: Some(before),
+                    after: None,
+                    to: Some(relative_key(to, &self.workspace)),
+                }
+            }
+            Op::Remove { path } => {
+                if !path.is_file() {
+                    return Ok(None);
+                }
+                JournalFile {
+                    path: relative_key(path, &self.workspace),
+                    action: JournalAction::Removed,
+                    before: Some(self.set_aside(index, path, undo)?),
+                    after: None,
+                    to: None,
+                }
+            }
+        };
+        Ok(Some(file))
+    }
+
+    /// Move `path` into the transaction directory until the commit is over and
+    /// return the sha256 of its content.
+    fn set_aside(&mut self, index: usize, path: &Path, undo: &mut Vec<Undo>) -> CoreResult<String> {
+        let sha256 = digest(path)?.sha256;
+        fs::create_dir_all(&self.dir)?;
+        let original = self.dir.join(format!("{index}.orig"));
+        fs::rename(path, &original)?;
+        undo.push(Undo::Restore {
+            original: original.clone(),
+            to: path.to_path_buf(),
+        });
+        self.originals.push((original, sha256.clone()));
+        Ok(sha256)
+    }
+
+    fn roll_back(&mut self, err: CoreError, undo: Vec<Undo>) -> CoreError {
+        let mut restored = Vec::new();
+        let mut unrestored = Vec::new();
+        for step in undo.into_iter().rev() {
+            match step {
+                Undo::Restore { original, to } => {
+                    let key = relative_key(&to, &self.workspace);
+                    match fs::rename(&original, &to) {
+                        Ok(()) => restored.push(key),
+                        Err(_) => {
+                            self.keep = true;
+                            unrestored.push(format!("{key} (kept as {})", original.display()));
+                        }
+                    }
+                }
+                Undo::Delete(path) => {
+                    if fs::remove_file(&path).is_err() {
+                        unrestored.push(relative_key(&path, &self.workspace));
+                    }
+                }
+                Undo::Rename { from, to } => {
+                    if fs::rename(&from, &to).is_err() {
+                        unrestored.push(relative_key(&to, &self.workspace));
+                    }
+                }
+                Undo::RemoveDir(dir) => {
+                    let _ = fs::remove_dir(dir);
+                }
+            }
+        }
+        restored.sort();
+        CoreError::RolledBack {
+            cause: err.to_string(),
+            restored,
+            unrestored,
+        }
+    }
+}
+
+impl Drop for Transaction {
+    fn drop(&mut self) {
+        if !self.keep {
+            let _ = fs::remove_dir_all(&self.dir);
+            // Only succeeds once no other transaction is running
+            let _ = fs::remove_dir(self.workspace.join(TRANSACTION_DIR));
+        }
+    }
+}
+
+/// Create the missing ancestors of `path`, noting each so a rollback removes them.
+fn create_parents(path: &Path, undo: &mut Vec<Undo>) -> CoreResult<()> {
+    let Some(parent) = path.parent() else {
+        return Ok(());
+    };
+    let missing: Vec<&Path> = parent.ancestors().take_while(|dir| !dir.exists()).collect();
+    fs::create_dir_all(parent)?;
+    undo.extend(
+        missing
+            .into_iter()
+            .rev()
+            .map(|dir| Undo::RemoveDir(dir.to_path_buf())),
+    );
+    Ok(())
+}
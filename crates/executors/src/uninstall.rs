@@ -0,0 +1,126 @@
+use std::fs;
+use std::path::Path;
+
+use entity_core::error::{CoreError, CoreResult};
+use entity_core::model::NodeKind;
+use entity_core::registry::Registry;
+use entity_core::workspace::WorkspaceConfig;
+use serde::Serialize;
+
+use crate::journal::{Operation, names_selection};
+use crate::lock::{Lockfile, relative_key};
+use crate::status::installed_components;
+use crate::transaction::{Transaction, TransactionReport};
+use crate::write::{WriteOptions, digest};
+
+#[derive(Debug, Serialize)]
+pub struct UninstallReport {
+    /// Workspace-relative files deleted
+    pub removed: Vec<String>,
+    /// Recorded files that were already gone
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub missing: Vec<String>,
+    /// Directories left empty and deleted
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub pruned: Vec<String>,
+    pub notes: Vec<String>,
+    /// Outcome of the writes, absent on dry runs
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub transaction: Option<TransactionReport>,
+}
+
+/// Delete the files `node_id` installed for each component (all, or `names`),
+/// then prune empty directories up to the components root. Files edited since
+/// install fail the call with `EditedSinceInstall` (`WRITE_CONFLICT`) unless
+/// `force` is set.
+pub(crate) fn uninstall_components(
+    registry: &Registry,
+    node_id: &str,
+    names: Option<&[String]>,
+    workspace: &Path,
+    force: bool,
+    options: &WriteOptions,
+) -> CoreResult<UninstallReport> {
+    let node = registry.get(node_id)?;
+    if node.kind != NodeKind::Component {
+        return Err(CoreError::WrongKind {
+            expected: "component".into(),
+            actual: format!("{:?}", node.kind),
+        });
+    }
+    let config = WorkspaceConfig::load(workspace)?;
+    let install_root = registry.install_root(node_id, &config);
+    let components_root = workspace.join(&install_root).join("components");
+
+    let mut lock = Lockfile::load(workspace)?;
+    let installed = installed_components(registry, &lock, node_id, &install_root, names)?;
+    let mut report = UninstallReport {
+        removed: Vec::new(),
+        missing: Vec::new(),
+        pruned: Vec::new(),
+        notes: Vec::new(),
+        transaction: None,
+    };
+    let mut modified = Vec::new();
+    for key in installed.values().flatten() {
+        let path = workspace.join(key);
+        if !path.is_file() {
+            report.missing.push(key.clone());
+        } else if digest(&path)?.sha256 != lock.files[key].sha256 {
+            modified.push(key.clone());
+            report.removed.push(key.clone());
+        } else {
+            report.removed.push(key.clone());
+        }
+    }
+    if !modified.is_empty() {
+        if !force {
+            return Err(CoreError::EditedSinceInstall(modified));
+        }
+        report.notes.extend(
+            modified
+                .iter()
+                .map(|key| format!("removed {key} despite local edits")),
+        );
+    }
+
+    if options.dry_run {
+        report
+            .notes
+            .push("Dry run: the workspace was not touched".into());
+        return Ok(report);
+    }
+    let mut tx = Transaction::new(workspace).journaled(Operation::new(
+        "ui uninstall",
+        Some(node_id),
+        &names_selection(names),
+    ));
+    for key in report.removed.iter().chain(&report.missing) {
+        tx.remove(&workspace.join(key));
+        lock.files.remove(key);
+    }
+    lock.stage(workspace, &mut tx)?;
+    report.transaction = Some(tx.commit()?);
+    for key in report.removed.iter().chain(&report.missing) {
+        report.pruned.extend(prune_empty_dirs(
+            &workspace.join(key),
+            &components_root,
+            workspace,
+        ));
+    }
+    Ok(report)
+}
+
+/// Remove `file`'s now-empty ancestors, stopping at `root` (which is kept).
+pub(crate) fn prune_empty_dirs(file: &Path, root: &Path, workspace: &Path) -> Vec<String> {
+    let mut pruned = Vec::new();
+    let mut dir = file.parent();
+    while let Some(current) = dir {
+        if current == root || !current.starts_with(root) || fs::remove_dir(current).is_err() {
+            break;
+        }
+        pruned.push(relative_key(current, workspace));
+        dir = current.parent();
+    }
+    pruned
+}
@@ -0,0 +1,300 @@
+use std::fs;
+use std::path::Path;
+
+use diffy::{ConflictStyle, MergeOptions};
+use entity_core::error::{CoreError, CoreResult};
+use entity_core::model::{ConflictPolicy, NodePayload};
+use entity_core::registry::Registry;
+use entity_core::selections::Selections;
+use entity_core::workspace::WorkspaceConfig;
+use serde::Serialize;
+
+use crate::components::{ComponentSource, resolve_variant};
+use crate::entityignore::IgnoreRules;
+use crate::imports::ImportRewriter;
+use crate::journal::{Operation, names_selection};
+use crate::lock::{LockOwner, Lockfile, load_base, relative_key, store_bases};
+use crate::status::{installed_components, installed_variant};
+use crate::template::{render_file, template_vars};
+use crate::transaction::{Transaction, TransactionReport};
+use crate::util::conflict_policy;
+use crate::write::{FileDigest, WriteOptions, digest, digest_bytes};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum UpdateAction {
+    /// The pack did not change the file (or already matches the workspace copy)
+    UpToDate,
+    /// Untouched locally, replaced with the pack version
+    Updated,
+    /// Local and pack edits merged cleanly
+    Merged,
+    /// Overlapping edits; conflict markers were written
+    Conflict,
+    /// New in the pack since install
+    Created,
+    /// Deleted locally; the deletion is kept
+    KeptDeleted,
+    /// Gone from the pack; the workspace copy is kept
+    RemovedUpstream,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FileUpdate {
+    /// Workspace-relative path
+    pub path: String,
+    pub action: UpdateAction,
+}
+
+/// One colliding hunk, as written between conflict markers.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct MergeConflict {
+    pub path: String,
+    /// 1-based line of the `<<<<<<< ours` marker in the written file
+    pub line: usize,
+    pub ours: String,
+    pub theirs: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct UpdateReport {
+    pub files: Vec<FileUpdate>,
+    pub conflicts: Vec<MergeConflict>,
+    pub notes: Vec<String>,
+    /// Outcome of the writes, absent on dry runs
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub transaction: Option<TransactionReport>,
+}
+
+/// Bring installed components of `node_id` up to the current pack: the installed
+/// version is the merge base, the pack file is theirs and the workspace file ours.
+/// Components installed from a framework variant are updated from that variant.
+/// Templates are rendered again with the variables recorded at install, and
+/// imports of other components rewritten as on install. Conflict policies do not
+/// apply: local edits are always merged, and overlaps get conflict markers.
+pub(crate) fn update_components(
+    registry: &Registry,
+    node_id: &str,
+    names: Option<&[String]>,
+    workspace: &Path,
+    options: &WriteOptions,
+) -> CoreResult<UpdateReport> {
+    let node = registry.get(node_id)?;
+    let NodePayload::Component {
+        source_root,
+        variants,
+        ..
+    } = &node.payload
+    else {
+        return Err(CoreError::WrongKind {
+            expected: "component".into(),
+            actual: format!("{:?}", node.kind),
+        });
+    };
+    let config = WorkspaceConfig::load(workspace)?;
+    let install_root = registry.install_root(node_id, &config);
+    let to_root = workspace.join(&install_root).join("components");
+
+    let mut lock = Lockfile::load(workspace)?;
+    let installed = installed_components(registry, &lock, node_id, &install_root, names)?;
+    let mut vars = template_vars(registry, node_id, &Selections::new(), workspace)?;
+    for (_, entry) in lock.node_files(node_id) {
+        vars.extend(entry.vars.clone());
+    }
+    let imports = ImportRewriter::load(workspace, &to_root)?;
+    let mut ignore = IgnoreRules::for_node(registry, node_id)?;
+
+    let owner = LockOwner::new(registry, node_id);
+    let mut report = UpdateReport {
+        files: Vec::new(),
+        conflicts: Vec::new(),
+        notes: Vec::new(),
+        transaction: None,
+    };
+    let policy = conflict_policy(registry, node_id, workspace, options)?;
+    if policy != ConflictPolicy::Overwrite {
+        report.notes.push(format!(
+            "Conflict policy {} does not apply: `ui update` always merges",
+            policy.as_str()
+        ));
+    }
+    let mut tx = Transaction::new(workspace).journaled(Operation::new(
+        "ui update",
+        Some(node_id),
+        &names_selection(names),
+    ));
+    let mut bases = Vec::new();
+    for (name, keys) in installed {
+        let framework = installed_variant(registry, &lock, &keys, variants);
+        let resolved = resolve_variant(Path::new(source_root), variants, framework, &name);
+        let pairs = match resolved.map(|(source, _)| source) {
+            Some(ComponentSource::Dir(dir)) => {
+                let root = dir.parent().unwrap_or(Path::new(source_root));
+                ignore.tree_files(root, &dir, &to_root.join(&name))?
+            }
+            Some(ComponentSource::File(file)) => {
+                let to = to_root.join(file.file_name().unwrap_or_default());
+                vec![(file, to)]
+            }
+            None => Vec::new(),
+        };
+        let mut rendered = Vec::new();
+        for (from, to) in pairs {
+            let mut file = render_file(&from, &to, workspace, &vars)?;
+            imports.apply(&from, &mut file)?;
+            rendered.push((from, file));
+        }
+        let current: Vec<String> = rendered
+            .iter()
+            .map(|(_, file)| relative_key(&file.to, workspace))
+            .collect();
+        for (from, file) in rendered {
+            let to = file.to;
+            let key = relative_key(&to, workspace);
+            if !file.undefined.is_empty() {
+                let names: Vec<String> = file.undefined.into_iter().collect();
+                report.notes.push(format!(
+                    "{key} uses undefined template variables ({}); skipped",
+                    names.join(", ")
+                ));
+                continue;
+            }
+            let original = file.content.is_some().then(|| digest(&from)).transpose()?;
+            let theirs = match file.content {
+                Some(content) => content,
+                None => fs::read(&from)?,
+            };
+            let digest = digest_bytes(&theirs);
+            let base = lock.files.get(&key).map(|e| e.sha256.clone());
+            let (action, content) = update_file(workspace, &to, base, &theirs, &digest)?;
+            match action {
+                UpdateAction::Conflict => {
+                    let Some(content) = &content else {
+                        report.notes.push(format!(
+                            "{key} is binary and changed on both sides; kept the workspace copy"
+                        ));
+                        report.files.push(FileUpdate { path: key, action });
+                        continue;
+                    };
+                    report.conflicts.extend(conflict_hunks(&key, content));
+                }
+                UpdateAction::KeptDeleted => {
+                    report.files.push(FileUpdate { path: key, action });
+                    continue;
+                }
+                _ => {}
+            }
+            if !options.dry_run {
+                if let Some(content) = content {
+                    tx.write(&to, &content)?;
+                }
+                bases.push((digest.sha256.clone(), theirs.clone()));
+            }
+            let mut entry = owner.entry(&from, &digest);
+            entry.source_sha256 = original.map(|o| o.sha256);
+            entry.vars = file.used;
+            entry.component = Some(name.clone());
+            lock.files.insert(key.clone(), entry);
+            report.files.push(FileUpdate { path: key, action });
+        }
+        for key in keys.into_iter().filter(|k| !current.contains(k)) {
+            report
+                .notes
+                .push(format!("{key} is no longer in the pack; kept"));
+            report.files.push(FileUpdate {
+                path: key,
+                action: UpdateAction::RemovedUpstream,
+            });
+        }
+    }
+
+    if options.dry_run {
+        report
+            .notes
+            .push("Dry run: the workspace was not touched".into());
+    } else {
+        lock.stage(workspace, &mut tx)?;
+        report.transaction = Some(tx.commit()?);
+        store_bases(workspace, bases, &mut report.notes);
+    }
+    if !report.conflicts.is_empty() {
+        report.notes.push(format!(
+            "{} conflicting hunk(s): resolve the conflict markers, then run `ui status`",
+            report.conflicts.len()
+        ));
+    }
+    Ok(report)
+}
+
+/// What to do with one file, and the content to write (if any). `base` is the
+/// sha256 recorded at install, `None` for files the pack added since.
+fn update_file(
+    workspace: &Path,
+    to: &Path,
+    base: Option<String>,
+    theirs: &[u8],
+    digest: &FileDigest,
+) -> CoreResult<(UpdateAction, Option<Vec<u8>>)> {
+    if base.as_deref() == Some(digest.sha256.as_str()) {
+        return Ok((UpdateAction::UpToDate, None));
+    }
+    if !to.is_file() {
+        return Ok(match base {
+            Some(_) => (UpdateAction::KeptDeleted, None),
+            None => (UpdateAction::Created, Some(theirs.to_vec())),
+        });
+    }
+    let ours = fs::read(to)?;
+    let ours_sha = digest_bytes(&ours).sha256;
+    if ours_sha == digest.sha256 {
+        return Ok((UpdateAction::UpToDate, None));
+    }
+    if base.as_deref() == Some(ours_sha.as_str()) {
+        return Ok((UpdateAction::Updated, Some(theirs.to_vec())));
+    }
+
+    // Without a recorded base (older installs, or files that predate the
+    // install), the whole file is one conflict
+    let ancestor = base
+        .and_then(|sha| load_base(workspace, &sha))
+        .unwrap_or_default();
+    let (Ok(ancestor), Ok(ours), Ok(theirs)) = (
+        String::from_utf8(ancestor),
+        String::from_utf8(ours),
+        std::str::from_utf8(theirs),
+    ) else {
+        return Ok((UpdateAction::Conflict, None));
+    };
+    let mut merge = MergeOptions::new();
+    merge.set_conflict_style(ConflictStyle::Merge);
+    Ok(match merge.merge(&ancestor, &ours, theirs) {
+        Ok(merged) => (UpdateAction::Merged, Some(merged.into_bytes())),
+        Err(conflicted) => (UpdateAction::Conflict, Some(conflicted.into_bytes())),
+    })
+}
+
+/// Hunks between `<<<<<<< ours`, `=======` and `>>>>>>> theirs` markers.
+fn conflict_hunks(path: &str, content: &[u8]) -> Vec<MergeConflict> {
+    let text = String::from_utf8_lossy(content);
+    let mut hunks = Vec::new();
+    let mut current: Option<(MergeConflict, bool)> = None;
+    for (index, line) in text.split_inclusive('\n').enumerate() {
+        match (&mut current, line.trim_end_matches(['\n', '\r'])) {
+            (None, "<<<<<<< ours") => {
+                let hunk = MergeConflict {
+                    path: path.to_string(),
+                    line: index + 1,
+                    ours: String::new(),
+                    theirs: String::new(),
+                };
+                current = Some((hunk, false));
+            }
+            (Some((_, in_theirs)), "=======") if !*in_theirs => *in_theirs = true,
+            (Some(_), ">>>>>>> theirs") => hunks.extend(current.take().map(|(h, _)| h)),
+            (Some((hunk, false)), _) => hunk.ours.push_str(line),
+            (Some((hunk, true)), _) => hunk.theirs.push_str(line),
+            (None, _) => {}
+        }
+    }
+    hunks
+}
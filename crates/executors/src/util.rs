@@ -1,4 +1,11 @@
-use std::path::Path;
+use std::path::{Component, Path, PathBuf};
+
+use entity_core::error::CoreResult;
+use entity_core::model::ConflictPolicy;
+use entity_core::registry::Registry;
+use entity_core::workspace::WorkspaceConfig;
+
+use crate::write::WriteOptions;
 
 pub fn to_kebab(input: &str) -> String {
     let mut out = String::with_capacity(input.len());
@@ -61,3 +68,39 @@ pub fn ensure_writable_dir(dir: &Path) -> std::io::Result<()> {
     let _ = std::fs::remove_file(&probe_file);
     std::fs::remove_dir_all(&probe_dir)
 }
+
+/// Absolute directory a node writes into for the given workspace.
+pub fn install_root(registry: &Registry, node_id: &str, workspace: &Path) -> CoreResult<PathBuf> {
+    let config = WorkspaceConfig::load(workspace)?;
+    Ok(workspace.join(registry.install_root(node_id, &config)))
+}
+
+/// Conflict policy for a node's writes: the command's choice wins over the workspace,
+/// node and pack settings.
+pub fn conflict_policy(
+    registry: &Registry,
+    node_id: &str,
+    workspace: &Path,
+    options: &WriteOptions,
+) -> CoreResult<ConflictPolicy> {
+    if let Some(policy) = options.conflict_policy {
+        return Ok(policy);
+    }
+    let config = WorkspaceConfig::load(workspace)?;
+    Ok(registry.conflict_policy(node_id, &config))
+}
+
+/// `path` with `.` and `..` components resolved lexically.
+pub fn normalize(path: &Path) -> PathBuf {
+    let mut out = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                out.pop();
+            }
+            other => out.push(other),
+        }
+    }
+    out
+}
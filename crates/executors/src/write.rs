@@ -0,0 +1,410 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use entity_core::error::{CoreError, CoreResult};
+use entity_core::model::{ConflictPolicy, PackageJsonSpec};
+use entity_core::registry::Registry;
+use entity_core::selections::Selections;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+use crate::imports::ImportRewriter;
+use crate::journal::Operation;
+use crate::lock::{LockOwner, Lockfile, relative_key, store_bases};
+use crate::package_json::{PackageJsonReport, merge_package_json};
+use crate::template::{RenderedFile, Vars, render_file};
+use crate::transaction::{Transaction, TransactionReport};
+use crate::util::conflict_policy;
+
+/// How executors write into the workspace.
+#[derive(Debug, Clone, Default)]
+pub struct WriteOptions {
+    /// Only report what would be written and run; leave the workspace untouched
+    pub dry_run: bool,
+    /// Overrides the workspace, node and pack conflict policy
+    pub conflict_policy: Option<ConflictPolicy>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FileAction {
+    Create,
+    Overwrite,
+    /// The destination already holds identical content
+    Unchanged,
+    /// `skip-existing`: the destination is kept as is
+    Skip,
+    /// `backup`: the destination is moved to `backup`, then written
+    Backup,
+    /// `fail-on-conflict`: the destination differs, so nothing is written
+    Conflict,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FileDigest {
+    pub size: u64,
+    pub sha256: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PlannedFile {
+    pub action: FileAction,
+    pub from: String,
+    pub to: String,
+    /// What will be written: the source, or its rendered or rewritten content
+    pub source: FileDigest,
+    /// The source itself, when what is written differs from it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub original: Option<FileDigest>,
+    /// Imports of other components were rewritten
+    #[serde(
+        rename = "importsRewritten",
+        skip_serializing_if = "std::ops::Not::not"
+    )]
+    pub imports_rewritten: bool,
+    /// Declared component the file belongs to
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub component: Option<String>,
+    /// What the destination holds today, for `overwrite` and `unchanged`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub existing: Option<FileDigest>,
+    /// Where the existing file is moved to, for `backup`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub backup: Option<String>,
+    /// Rendered content, written instead of the source
+    #[serde(skip)]
+    pub(crate) content: Option<Vec<u8>>,
+    /// Template variables the path and content used
+    #[serde(skip)]
+    pub(crate) vars: Vars,
+}
+
+/// Everything a write operation would do, returned instead of doing it on dry runs.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct WritePlan {
+    #[serde(rename = "conflictPolicy")]
+    pub conflict_policy: ConflictPolicy,
+    pub files: Vec<PlannedFile>,
+    /// Commands that would run, in order
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub commands: Vec<String>,
+}
+
+const DRY_RUN_NOTE: &str = "Dry run: the workspace was not touched; see `plan`";
+
+/// Size and sha256 of a file's content.
+pub fn digest(path: &Path) -> io::Result<FileDigest> {
+    Ok(digest_bytes(&fs::read(path)?))
+}
+
+pub(crate) fn digest_bytes(bytes: &[u8]) -> FileDigest {
+    FileDigest {
+        size: bytes.len() as u64,
+        sha256: format!("{:x}", Sha256::digest(bytes)),
+    }
+}
+
+/// Collects the writes of one executor call, then performs them all at once (or,
+/// on dry runs, only returns them as a plan).
+pub(crate) struct Writer {
+    dry_run: bool,
+    policy: ConflictPolicy,
+    stamp: u64,
+    plan: WritePlan,
+    workspace: PathBuf,
+    owner: LockOwner,
+    vars: Vars,
+    imports: Option<ImportRewriter>,
+    /// Undefined template variables, with the sources referencing them
+    undefined: BTreeMap<String, BTreeSet<String>>,
+    /// Merged `package.json`, written with the files
+    package_json: Option<(PathBuf, Vec<u8>)>,
+    /// Recorded in the workspace journal on commit
+    operation: Operation,
+    /// Lock entries to drop on commit
+    pruned: Vec<String>,
+}
+
+/// What a commit did: notes, the plan on dry runs, and the transaction otherwise.
+pub(crate) type Committed = (Vec<String>, Option<WritePlan>, Option<TransactionReport>);
+
+impl Writer {
+    /// Writer for `node_id`'s files in `workspace`, with the conflict policy resolved.
+    pub(crate) fn for_node(
+        registry: &Registry,
+        node_id: &str,
+        workspace: &Path,
+        options: &WriteOptions,
+    ) -> CoreResult<Self> {
+        let policy = conflict_policy(registry, node_id, workspace, options)?;
+        Ok(Self {
+            dry_run: options.dry_run,
+            policy,
+            stamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+            plan: WritePlan {
+                conflict_policy: policy,
+                ..WritePlan::default()
+            },
+            workspace: workspace.to_path_buf(),
+            owner: LockOwner::new(registry, node_id),
+            vars: Vars::new(),
+            imports: None,
+            undefined: BTreeMap::new(),
+            package_json: None,
+            operation: Operation::new("", Some(node_id), &Selections::new()),
+            pruned: Vec::new(),
+        })
+    }
+
+    /// Journal the commit as `command` (e.g. `ui install`) run with `selections`.
+    pub(crate) fn journaled(mut self, command: &str, selections: &Selections) -> Self {
+        self.operation.command = command.to_string();
+        self.operation.selections = selections.clone();
+        self
+    }
+
+    /// Variables for `__name__` path segments and `.tmpl` sources.
+    pub(crate) fn with_vars(mut self, vars: Vars) -> Self {
+        self.vars = vars;
+        self
+    }
+
+    /// Rewrite imports between components in the script files written.
+    pub(crate) fn with_imports(mut self, imports: ImportRewriter) -> Self {
+        self.imports = Some(imports);
+        self
+    }
+
+    pub(crate) fn dry_run(&self) -> bool {
+        self.dry_run
+    }
+
+    /// Queue every `(from, to)` pair, rendering templated paths and `.tmpl`
+    /// sources, and return how many files will be written.
+    pub(crate) fn stage(&mut self, files: Vec<(PathBuf, PathBuf)>) -> CoreResult<usize> {
+        let mut count = 0;
+        for (from, to) in files {
+            let mut rendered = render_file(&from, &to, &self.workspace, &self.vars)?;
+            let imports_rewritten = match &self.imports {
+                Some(imports) => imports.apply(&from, &mut rendered)?,
+                None => false,
+            };
+            for name in &rendered.undefined {
+                self.undefined
+                    .entry(name.clone())
+                    .or_default()
+                    .insert(from.display().to_string());
+            }
+            let mut planned = self.plan_file(&from, rendered)?;
+            planned.imports_rewritten = imports_rewritten;
+            if !matches!(planned.action, FileAction::Skip | FileAction::Conflict) {
+                count += 1;
+            }
+            self.plan.files.push(planned);
+        }
+        Ok(count)
+    }
+
+    /// [`stage`](Self::stage) the files of one component, recording its declared
+    /// name in the lockfile.
+    pub(crate) fn stage_component(
+        &mut self,
+        component: &str,
+        files: Vec<(PathBuf, PathBuf)>,
+    ) -> CoreResult<usize> {
+        let start = self.plan.files.len();
+        let count = self.stage(files)?;
+        for planned in &mut self.plan.files[start..] {
+            planned.component = Some(component.to_string());
+        }
+        Ok(count)
+    }
+
+    /// Drop the lock entries among `keys` this commit does not write again: files
+    /// a reinstalled component no longer ships. Their workspace copies are kept.
+    pub(crate) fn prune(&mut self, keys: &[String]) {
+        let staged: BTreeSet<String> = self
+            .plan
+            .files
+            .iter()
+            .map(|f| relative_key(Path::new(&f.to), &self.workspace))
+            .collect();
+        self.pruned
+            .extend(keys.iter().filter(|key| !staged.contains(*key)).cloned());
+    }
+
+    /// Destinations of staged files whose imports were rewritten.
+    pub(crate) fn rewritten_imports(&self) -> Vec<String> {
+        self.plan
+            .files
+            .iter()
+            .filter(|f| f.imports_rewritten)
+            .map(|f| f.to.clone())
+            .collect()
+    }
+
+    /// Merge `spec` into the workspace `package.json`, written on commit.
+    pub(crate) fn merge_package_json(
+        &mut self,
+        spec: &PackageJsonSpec,
+    ) -> CoreResult<PackageJsonReport> {
+        let (report, content) = merge_package_json(&self.workspace, spec)?;
+        self.package_json = content.map(|content| (PathBuf::from(&report.path), content));
+        Ok(report)
+    }
+
+    /// Record a command; returns whether the caller should actually run it.
+    pub(crate) fn command(&mut self, command: &str) -> bool {
+        if self.dry_run {
+            self.plan.commands.push(command.to_string());
+        } else {
+            self.operation.commands.push(command.to_string());
+        }
+        !self.dry_run
+    }
+
+    /// Fail on what would abort the commit: undefined template variables, and
+    /// with `fail-on-conflict` (outside dry runs) any conflict. Callers with side
+    /// effects of their own, like setup commands, check before causing them.
+    pub(crate) fn check(&self) -> CoreResult<()> {
+        if !self.undefined.is_empty() {
+            return Err(CoreError::UndefinedVariables {
+                variables: self.undefined.keys().cloned().collect(),
+                files: self
+                    .undefined
+                    .values()
+                    .flatten()
+                    .cloned()
+                    .collect::<BTreeSet<_>>()
+                    .into_iter()
+                    .collect(),
+            });
+        }
+        if self.dry_run {
+            return Ok(());
+        }
+        let conflicts: Vec<String> = self
+            .plan
+            .files
+            .iter()
+            .filter(|f| f.action == FileAction::Conflict)
+            .map(|f| f.to.clone())
+            .collect();
+        if !conflicts.is_empty() {
+            return Err(CoreError::WriteConflict(conflicts));
+        }
+        Ok(())
+    }
+
+    /// Write the staged files, the merged `package.json` and the lockfile in one
+    /// transaction and return per-file notes, plus the plan on dry runs. Whatever
+    /// [`check`](Self::check) rejects aborts before the first write.
+    pub(crate) fn commit(self) -> CoreResult<Committed> {
+        self.check()?;
+        let mut notes = vec![format!("Conflict policy: {}", self.policy.as_str())];
+        notes.extend(self.plan.files.iter().filter_map(file_note));
+        notes.extend(self.pruned.iter().map(|key| {
+            format!("{key} is no longer in the pack; dropped from the lockfile, file kept")
+        }));
+        if self.dry_run {
+            notes.push(DRY_RUN_NOTE.into());
+            return Ok((notes, Some(self.plan), None));
+        }
+
+        let mut lock = Lockfile::load(&self.workspace)?;
+        for key in &self.pruned {
+            lock.files.remove(key);
+        }
+        let mut tx = Transaction::new(&self.workspace).journaled(self.operation.clone());
+        let mut bases = Vec::new();
+        for file in &self.plan.files {
+            let to = Path::new(&file.to);
+            match file.action {
+                FileAction::Skip | FileAction::Conflict => continue,
+                FileAction::Backup => {
+                    tx.rename(to, Path::new(file.backup.as_deref().unwrap()));
+                }
+                _ => {}
+            }
+            let content = match &file.content {
+                Some(content) => content.clone(),
+                None => fs::read(&file.from)?,
+            };
+            tx.write(to, &content)?;
+            bases.push((file.source.sha256.clone(), content));
+            let mut entry = self.owner.entry(Path::new(&file.from), &file.source);
+            entry.source_sha256 = file.original.as_ref().map(|o| o.sha256.clone());
+            entry.vars = file.vars.clone();
+            entry.component = file.component.clone();
+            lock.files.insert(relative_key(to, &self.workspace), entry);
+        }
+        if let Some((path, content)) = &self.package_json {
+            tx.write(path, content)?;
+        }
+        if !self.plan.files.is_empty() || !self.pruned.is_empty() {
+            lock.stage(&self.workspace, &mut tx)?;
+        }
+        let report = tx.commit()?;
+        store_bases(&self.workspace, bases, &mut notes);
+        Ok((notes, None, Some(report)))
+    }
+
+    fn plan_file(&self, from: &Path, rendered: RenderedFile) -> io::Result<PlannedFile> {
+        let to = rendered.to.as_path();
+        let (source, original) = match &rendered.content {
+            Some(content) => (digest_bytes(content), Some(digest(from)?)),
+            None => (digest(from)?, None),
+        };
+        let existing = if to.is_file() {
+            Some(digest(to)?)
+        } else {
+            None
+        };
+        let action = match &existing {
+            None => FileAction::Create,
+            Some(current) if *current == source => FileAction::Unchanged,
+            Some(_) => match self.policy {
+                ConflictPolicy::Overwrite => FileAction::Overwrite,
+                ConflictPolicy::SkipExisting => FileAction::Skip,
+                ConflictPolicy::Backup => FileAction::Backup,
+                ConflictPolicy::FailOnConflict => FileAction::Conflict,
+            },
+        };
+        let backup =
+            (action == FileAction::Backup).then(|| format!("{}.{}.orig", to.display(), self.stamp));
+        Ok(PlannedFile {
+            action,
+            from: from.display().to_string(),
+            to: to.display().to_string(),
+            source,
+            original,
+            imports_rewritten: false,
+            component: None,
+            existing,
+            backup,
+            content: rendered.content,
+            vars: rendered.used,
+        })
+    }
+}
+
+/// Report line for a file whose destination already existed with other content.
+fn file_note(file: &PlannedFile) -> Option<String> {
+    let to = &file.to;
+    match file.action {
+        FileAction::Create | FileAction::Unchanged => None,
+        FileAction::Overwrite => Some(format!("overwrote {to}")),
+        FileAction::Skip => Some(format!("kept existing {to}")),
+        FileAction::Backup => Some(format!(
+            "backed up {to} to {}",
+            file.backup.as_deref().unwrap_or_default()
+        )),
+        FileAction::Conflict => Some(format!("conflict: {to} differs from the pack")),
+    }
+}